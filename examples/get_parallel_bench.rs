@@ -0,0 +1,46 @@
+// This example compares how long `get_file_data` and
+// `get_file_data_parallel` take to scan and checksum a directory.
+//
+// Usage: cargo run --release --example get_parallel_bench -- [DIR] [THREADS] [RUNS]
+
+extern crate filearco;
+
+use std::env;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let dir = args.get(1).map(|arg| arg.as_str()).unwrap_or("testarchives/reqchandocs");
+    let threads = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(4);
+    let runs = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or(10);
+    let path = Path::new(dir);
+
+    let serial = time(runs, || {
+        filearco::get_file_data(path).ok().unwrap();
+    });
+    let parallel = time(runs, || {
+        filearco::get_file_data_parallel(path, threads).ok().unwrap();
+    });
+
+    let file_data = filearco::get_file_data(path).ok().unwrap();
+    println!("{} files, {} bytes, best of {} runs", file_data.len(), file_data.total_len(), runs);
+    println!("get_file_data:               {:>10.3} ms", millis(serial));
+    println!("get_file_data_parallel ({:>2}): {:>10.3} ms", threads, millis(parallel));
+}
+
+// This function returns the fastest of `runs` calls to `f`.
+fn time<F: FnMut()>(runs: usize, mut f: F) -> Duration {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or(Duration::from_secs(0))
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1000.0 + duration.subsec_nanos() as f64 / 1_000_000.0
+}
@@ -2,34 +2,73 @@
 extern crate clap;
 extern crate filearco;
 
-use std::error::Error;
 use std::fs::File;
 use std::io;
 use std::process::exit;
 
+use clap::ArgMatches;
+use filearco::Error;
+use filearco::v1::{read_raw_header, ExtractOptions, FileArco, FileArcoV1Error};
+
 fn main() {
-    // let args = env::args().collect::<Vec<_>>();
     let matches = clap_app!(myapp =>
                             (version: "1.0")
                             (author: "Philip Woods <elzairthesorcerer@gmail.com>")
-                            (about: "Archives FileArco files")
-                            (@arg DIRPATH: +required "Path to directory to archive")
-                            (@arg ARCHIVEVERSION: -v --archive_version +takes_value "Specify version of FileArco format to create")
-                            (@arg FILEPATH: -p --path +takes_value "Write to FILEPATH instead of stdout")).get_matches();
-    
+                            (about: "Creates and inspects FileArco archives")
+                            (@setting SubcommandRequiredElseHelp)
+                            (@subcommand create =>
+                             (about: "Archives the files in a directory")
+                             (@arg DIRPATH: +required "Path to directory to archive")
+                             (@arg ARCHIVEVERSION: -v --archive_version +takes_value "Specify version of FileArco format to create")
+                             (@arg FILEPATH: -p --path +takes_value "Write to FILEPATH instead of stdout")
+                             (@arg INCLUDE: -i --include +takes_value +multiple number_of_values(1) "Only archive files whose relative path matches this glob (repeatable)")
+                             (@arg EXCLUDE: -x --exclude +takes_value +multiple number_of_values(1) "Skip files whose relative path matches this glob (repeatable, wins over --include)"))
+                            (@subcommand info =>
+                             (about: "Prints the header fields of an archive")
+                             (@arg ARCHIVE: +required "Path to archive"))
+                            (@subcommand list =>
+                             (about: "Prints the name and size of every file in an archive")
+                             (@arg ARCHIVE: +required "Path to archive"))
+                            (@subcommand extract =>
+                             (about: "Writes every file in an archive to a directory")
+                             (@arg ARCHIVE: +required "Path to archive")
+                             (@arg DEST: +required "Directory to extract files into"))
+                            (@subcommand verify =>
+                             (about: "Checks every file in an archive for corruption")
+                             (@arg ARCHIVE: +required "Path to archive"))).get_matches();
+
+    match matches.subcommand() {
+        ("create", Some(matches)) => create(matches),
+        ("info", Some(matches)) => info(matches),
+        ("list", Some(matches)) => list(matches),
+        ("extract", Some(matches)) => extract(matches),
+        ("verify", Some(matches)) => verify(matches),
+        _ => unreachable!(),
+    }
+}
+
+fn create(matches: &ArgMatches) {
     let dirpath = matches.value_of("DIRPATH").unwrap();
     let archive_version = matches.value_of("ARCHIVEVERSION").unwrap_or("1");
 
-    if archive_version != "1" {
-        println!("Invalid FileArco version: {}", archive_version);
+    if archive_version != "1" && archive_version != "2" {
+        eprintln!("Invalid FileArco version: {}", archive_version);
         exit(-1);
     }
 
-    let file_data = match filearco::get_file_data(dirpath) {
+    // A file matching both an include and an exclude pattern is skipped.
+    let mut options = filearco::GetOptions::new();
+    for pattern in matches.values_of("INCLUDE").into_iter().flatten() {
+        options = options.include(pattern);
+    }
+    for pattern in matches.values_of("EXCLUDE").into_iter().flatten() {
+        options = options.exclude(pattern);
+    }
+
+    let file_data = match filearco::get_file_data_with_options(dirpath, &options) {
         Ok(data) => data,
         Err(err) => {
-            // panic!(err.to_string())
-            println!("{}", err.description());
+            eprintln!("{}", err);
             exit(-2);
         }
     };
@@ -39,7 +78,7 @@ fn main() {
             match File::create(file_path) {
                 Ok(handle) => Box::new(handle) as Box<io::Write>,
                 Err(err) => {
-                    println!("{}", err.description());
+                    eprintln!("{}", err);
                     exit(-3);
                 },
             }
@@ -49,13 +88,127 @@ fn main() {
         },
     };
 
-    match filearco::v1::FileArco::make(file_data, handle) {
+    let result = if archive_version == "1" {
+        FileArco::make(file_data, handle)
+    }
+    else {
+        filearco::v2::FileArco::make(file_data, handle)
+    };
+
+    match result {
         Ok(_) => {
             exit(0);
         },
         Err(err) => {
-            println!("{}", err.description());
+            eprintln!("{}", err);
+            exit(-4);
+        }
+    }
+}
+
+// Each line is `key: value`, so scripts can grep for the fields they need.
+fn info(matches: &ArgMatches) {
+    let path = matches.value_of("ARCHIVE").unwrap();
+
+    // Opening an archive validates both checksums.
+    let archive = match FileArco::new(path) {
+        Ok(archive) => archive,
+        Err(Error::FileArcoV1(FileArcoV1Error::CorruptedHeader)) => {
+            // The fields can still help to find out what went wrong.
+            if let Ok(header) = read_raw_header(path) {
+                println!("version_number: {:#018x}", header.version_number());
+                println!("major_version: {}", header.version_number() & 0xffff_ffff);
+                println!("revision: {}", (header.version_number() >> 32) & 0xffff);
+                println!("flags: {:#x}", header.version_number() >> 48);
+                println!("page_size: {}", header.page_size());
+                println!("file_length: {}", header.file_length());
+                println!("file_offset: {}", header.file_offset());
+                println!("entries_length: {}", header.entries_length());
+                println!("entries_checksum: {:#018x}", header.entries_checksum());
+            }
+            println!("header_checksum_valid: false");
+            exit(1);
+        },
+        Err(Error::FileArcoV1(FileArcoV1Error::CorruptedEntriesTable)) => {
+            println!("header_checksum_valid: true");
+            println!("entries_checksum_valid: false");
+            exit(1);
+        },
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            exit(-2);
+        },
+    };
+
+    println!("version_number: {:#018x}", archive.version_number());
+    println!("major_version: {}", archive.version_number() & 0xffff_ffff);
+    println!("revision: {}", archive.revision());
+    println!("flags: {:#x}", archive.version_number() >> 48);
+    println!("page_size: {}", archive.page_size());
+    println!("file_length: {}", archive.file_length());
+    println!("file_offset: {}", archive.file_offset());
+    println!("entries_count: {}", archive.file_count());
+    println!("entries_length: {}", archive.entries_length());
+    println!("entries_checksum: {:#018x}", archive.entries_checksum());
+    println!("header_checksum_valid: true");
+    println!("entries_checksum_valid: true");
+}
+
+fn list(matches: &ArgMatches) {
+    let archive = open(matches.value_of("ARCHIVE").unwrap());
+
+    let mut entries = archive.entries().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (name, info) in entries.into_iter() {
+        println!("{:>12}  {}", info.len(), name);
+    }
+
+    println!("{} files", archive.file_count());
+}
+
+fn extract(matches: &ArgMatches) {
+    let archive = open(matches.value_of("ARCHIVE").unwrap());
+    let dest = matches.value_of("DEST").unwrap();
+
+    match archive.extract_to_with(dest, &ExtractOptions::new()) {
+        Ok(written) => {
+            println!("Extracted {} files to {}", written, dest);
+        },
+        Err(err) => {
+            eprintln!("{}", err);
             exit(-4);
         }
     }
 }
+
+fn verify(matches: &ArgMatches) {
+    let archive = open(matches.value_of("ARCHIVE").unwrap());
+
+    let corrupted = archive.verify_all().into_iter()
+        .filter(|&(_, valid)| !valid)
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+
+    for name in corrupted.iter() {
+        println!("Corrupted: {}", name);
+    }
+
+    if !corrupted.is_empty() {
+        println!("{} of {} files are corrupted", corrupted.len(), archive.file_count());
+        exit(1);
+    }
+
+    println!("All {} files are valid", archive.file_count());
+}
+
+// Only FileArco v1 archives can be inspected.
+fn open(path: &str) -> FileArco {
+    match FileArco::new(path) {
+        Ok(archive) => archive,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            exit(-2);
+        }
+    }
+}
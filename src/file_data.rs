@@ -12,17 +12,33 @@
 //! let file_data = filearco::get_file_data(path).unwrap();
 //! ```
 
+use std::cmp;
+use std::collections::HashMap;
 use std::convert::AsRef;
 use std::error;
 use std::fmt;
-use std::fs::File;
+use std::fs::{File, Metadata};
+use std::io;
 use std::io::prelude::*;
-use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+use std::time::UNIX_EPOCH;
 
 use crc::crc64::checksum_iso as checksum;
+use glob::Pattern;
+use page_size::get as get_page_size;
 use walkdir::WalkDir;
     
 use super::{Error, Result};
+use v1::get_aligned_length;
+
+// This is the mode of files whose permissions could not be determined
+// (e.g. on Windows or in archives predating file modes).
+pub const DEFAULT_MODE: u32 = 0o644;
 
 /// This function retrieves basic information (i.e. path, length and checksum)
 /// of all files under a specific `base_path`.
@@ -44,50 +60,556 @@ use super::{Error, Result};
 /// let file_data = filearco::get_file_data(path).unwrap();
 /// ```
 pub fn get<P: AsRef<Path>>(base_path: P) -> Result<FileData> {
-    if !base_path.as_ref().is_dir() {
+    get_with_options(base_path, &GetOptions::new())
+}
+
+/// This function retrieves basic information (i.e. path, length and checksum)
+/// of all files under a specific `base_path`, honoring the given `options`.
+///
+/// **NOTE:** All file paths are relative to `base_dir`
+///
+/// # Arguments
+///
+/// * base_path - the path of a *directory* to list.
+///
+/// * options - settings controlling how the directory is scanned
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::path::Path;
+///
+/// let path = Path::new("testarchives/reqchandocs");
+/// let options = filearco::GetOptions::new().max_depth(Some(1));
+/// let file_data = filearco::get_file_data_with_options(path, &options).unwrap();
+/// ```
+pub fn get_with_options<P: AsRef<Path>>(base_path: P,
+                                        options: &GetOptions) -> Result<FileData> {
+    let (names_root, files) = scan(base_path, options)?;
+
+    let mut file_data = Vec::<FileDatum>::with_capacity(files.len());
+
+    for file in files.into_iter() {
+        file_data.push(file.into_datum()?);
+    }
+
+    Ok(FileData {
+        base_path: names_root,
+        data: file_data,
+    })
+}
+
+/// This function retrieves the same information as `get()`, but only for the
+/// files in `list`, which are relative to `base_path`. Files are returned in
+/// the order they are listed. Listing a path more than once is an error, as
+/// is listing a path that is absolute or contains `..`.
+///
+/// # Arguments
+///
+/// * base_path - the path of a *directory* containing the listed files.
+///
+/// * list - relative paths of the files to include
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::path::Path;
+///
+/// let path = Path::new("testarchives/simple");
+/// let list = ["Cargo.toml", "LICENSE-MIT"];
+/// let file_data = filearco::get_file_data_from_list(path, &list).unwrap();
+/// ```
+pub fn get_from_list<P: AsRef<Path>, Q: AsRef<Path>>(base_path: P,
+                                                     list: &[Q]) -> Result<FileData> {
+    get_from_list_with_options(base_path, list, &GetOptions::new())
+}
+
+/// This function retrieves the same information as `get_from_list()`, using
+/// `options` to decide how paths listed more than once are handled.
+///
+/// # Arguments
+///
+/// * base_path - the path of a *directory* containing the listed files.
+///
+/// * list - relative paths of the files to include
+///
+/// * options - settings such as the duplicate policy
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::path::Path;
+///
+/// use filearco::{DuplicatePolicy, GetOptions};
+///
+/// let path = Path::new("testarchives/simple");
+/// let list = ["Cargo.toml", "LICENSE-MIT", "Cargo.toml"];
+/// let options = GetOptions::new().duplicate_policy(DuplicatePolicy::First);
+/// let file_data = filearco::get_file_data_from_list_with_options(path, &list, &options)
+///     .unwrap();
+/// ```
+pub fn get_from_list_with_options<P: AsRef<Path>, Q: AsRef<Path>>(
+    base_path: P,
+    list: &[Q],
+    options: &GetOptions
+) -> Result<FileData> {
+    check_base_path(base_path.as_ref())?;
+
+    let full_base_path = base_path.as_ref().canonicalize()?;
+    let names_root = options.root_policy.names_root(&full_base_path);
+
+    // Normalize listed paths so "./a/b" and "a/b" count as duplicates. A
+    // path dropped by `DuplicatePolicy::Last` leaves a `None` behind, and
+    // `indices` maps each kept path to its position.
+    let mut paths = Vec::<Option<PathBuf>>::with_capacity(list.len());
+    let mut indices = HashMap::<PathBuf, usize>::with_capacity(list.len());
+
+    for file_path in list.iter() {
+        // Only plain relative paths stay inside `base_path`.
+        let file_path = file_path.as_ref();
+        if !file_path.components().all(|c| match c {
+            Component::Normal(_) | Component::CurDir => true,
+            _ => false,
+        }) {
+            return Err(Error::FileData(FileDataError::InvalidPath(
+                String::from(file_path.to_string_lossy())
+            )));
+        }
+
+        let file_path = file_path.components()
+            .filter(|c| *c != Component::CurDir)
+            .collect::<PathBuf>();
+
+        match indices.get(&file_path).cloned() {
+            Some(index) => {
+                match options.duplicate_policy {
+                    DuplicatePolicy::Error => {
+                        return Err(Error::FileData(FileDataError::DuplicatePath(
+                            String::from(file_path.to_string_lossy())
+                        )));
+                    },
+                    DuplicatePolicy::First => {},
+                    DuplicatePolicy::Last => {
+                        paths[index] = None;
+                        indices.insert(file_path.clone(), paths.len());
+                        paths.push(Some(file_path));
+                    },
+                }
+            },
+            None => {
+                indices.insert(file_path.clone(), paths.len());
+                paths.push(Some(file_path));
+            },
+        }
+    }
+
+    let mut file_data = Vec::<FileDatum>::with_capacity(indices.len());
+
+    for file_path in paths.into_iter().filter_map(|file_path| file_path) {
+        let full_path = full_base_path.join(&file_path);
+        let metadata = full_path.metadata()?;
+        let name = match full_path.strip_prefix(&names_root) {
+            Ok(name) => name.to_path_buf(),
+            Err(_) => {
+                return Err(Error::FileData(FileDataError::InvalidPath(
+                    String::from(file_path.to_string_lossy())
+                )));
+            },
+        };
+
+        let file = ScannedFile {
+            full_path: full_path,
+            name: path_to_bytes(&name)?,
+            length: metadata.len(),
+            mode: get_mode(&metadata),
+            modified: get_modified(&metadata),
+        };
+        file_data.push(file.into_datum()?);
+    }
+
+    Ok(FileData {
+        base_path: names_root,
+        data: file_data,
+    })
+}
+
+/// This function retrieves the same information as `get()`, but it computes
+/// the checksums of the files on `num_threads` worker threads.
+///
+/// The order of the resulting entries is identical to that of `get()`, so
+/// archives created from either function have the same layout. Run the
+/// `get_parallel_bench` example to compare the two on a directory.
+///
+/// # Arguments
+///
+/// * base_path - the path of a *directory* to list.
+///
+/// * num_threads - number of worker threads to use (at least 1)
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::path::Path;
+///
+/// let path = Path::new("testarchives/reqchandocs");
+/// let file_data = filearco::get_file_data_parallel(path, 4).unwrap();
+/// ```
+pub fn get_parallel<P: AsRef<Path>>(base_path: P,
+                                    num_threads: usize) -> Result<FileData> {
+    get_parallel_with_options(base_path, num_threads, &GetOptions::new())
+}
+
+/// This function retrieves the same information as `get_with_options()`, but
+/// it computes the checksums of the files on `num_threads` worker threads.
+/// If a worker thread panics, it returns `FileDataError::WorkerPanicked`.
+///
+/// # Arguments
+///
+/// * base_path - the path of a *directory* to list.
+///
+/// * num_threads - number of worker threads to use (at least 1)
+///
+/// * options - settings controlling how the directory is scanned
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::path::Path;
+///
+/// let path = Path::new("testarchives/reqchandocs");
+/// let options = filearco::GetOptions::new().exclude("*.woff");
+/// let file_data = filearco::get_file_data_parallel_with_options(path, 4, &options).unwrap();
+/// ```
+pub fn get_parallel_with_options<P: AsRef<Path>>(base_path: P,
+                                                 num_threads: usize,
+                                                 options: &GetOptions) -> Result<FileData> {
+    let (names_root, files) = scan(base_path, options)?;
+
+    // Split files into one contiguous chunk per thread so the results can
+    // be concatenated back in their original order.
+    let num_threads = cmp::max(num_threads, 1);
+    let chunk_size = cmp::max(files.len().div_ceil(num_threads), 1);
+
+    let mut handles = Vec::with_capacity(num_threads);
+    let mut files = files.into_iter();
+
+    loop {
+        let chunk = files.by_ref().take(chunk_size).collect::<Vec<_>>();
+
+        if chunk.is_empty() {
+            break;
+        }
+
+        handles.push(thread::spawn(move || {
+            chunk.into_iter()
+                .map(|file| file.into_datum())
+                .collect::<io::Result<Vec<FileDatum>>>()
+        }));
+    }
+
+    Ok(FileData {
+        base_path: names_root,
+        data: join_workers(handles)?,
+    })
+}
+
+// This function concatenates the results of the worker threads in order.
+fn join_workers(handles: Vec<thread::JoinHandle<io::Result<Vec<FileDatum>>>>)
+                -> Result<Vec<FileDatum>> {
+    let mut file_data = Vec::<FileDatum>::new();
+
+    for handle in handles.into_iter() {
+        match handle.join() {
+            Ok(data) => file_data.extend(data?),
+            Err(_) => return Err(Error::FileData(FileDataError::WorkerPanicked)),
+        }
+    }
+
+    Ok(file_data)
+}
+
+// This struct holds a file found by `scan()` whose checksum has not yet
+// been computed.
+struct ScannedFile {
+    full_path: PathBuf,
+    name: Vec<u8>,
+    length: u64,
+    mode: u32,
+    modified: u64,
+}
+
+impl ScannedFile {
+    fn into_datum(self) -> io::Result<FileDatum> {
+        // Compute checksum of file contents. 
+        let mut in_file = File::open(&self.full_path)?;
+        let mut contents = Vec::<u8>::with_capacity(self.length as usize); 
+        in_file.read_to_end(&mut contents)?;
+
+        Ok(FileDatum {
+            name: self.name,
+            length: self.length,
+            checksum: checksum(&contents),
+            mode: self.mode,
+            modified: self.modified,
+        })
+    }
+}
+
+// This function ensures `base_path` exists and is a directory.
+fn check_base_path(base_path: &Path) -> Result<()> {
+    if !base_path.exists() {
+        return Err(Error::FileData(FileDataError::BasePathMissing));
+    }
+
+    if !base_path.is_dir() {
         return Err(Error::FileData(FileDataError::BasePathNotDirectory));
     }
+
+    Ok(())
+}
+
+// This function walks `base_path` and returns every ordinary file found
+// beneath it, along with the directory their names are relative to.
+fn scan<P: AsRef<Path>>(base_path: P,
+                        options: &GetOptions) -> Result<(PathBuf, Vec<ScannedFile>)> {
+    check_base_path(base_path.as_ref())?;
     
     let full_base_path = base_path.as_ref().canonicalize()?;
+    let names_root = options.root_policy.names_root(&full_base_path);
 
-    let mut file_data = Vec::<FileDatum>::new();
+    let include = options.include.iter()
+        .map(|pattern| Pattern::new(pattern))
+        .collect::<::std::result::Result<Vec<_>, _>>()?;
+    let exclude = options.exclude.iter()
+        .map(|pattern| Pattern::new(pattern))
+        .collect::<::std::result::Result<Vec<_>, _>>()?;
+
+    let mut files = Vec::<ScannedFile>::new();
+
+    let mut walker = WalkDir::new(&full_base_path);
+    if let Some(depth) = options.max_depth {
+        walker = walker.max_depth(depth);
+    }
 
-    for entry in WalkDir::new(&full_base_path) {
+    for entry in walker {
         let ent = entry?;
 
         if ent.file_type().is_file() {
+            // Patterns match against the path relative to `base_path`.
+            let relative_path = ent.path().strip_prefix(&full_base_path).unwrap();
+
+            if exclude.iter().any(|pattern| pattern.matches_path(relative_path)) ||
+                (!include.is_empty() &&
+                 !include.iter().any(|pattern| pattern.matches_path(relative_path))) {
+                continue;
+            }
+
             let full_path = ent.path().to_path_buf();
-            let file_path = full_path.strip_prefix(&full_base_path)
+            let file_path = full_path.strip_prefix(&names_root)
                 .unwrap().to_path_buf();
             let metadata = ent.metadata()?;
-            let length = metadata.len();
-
-            // We only support valid UTF-8 file paths.
-            if let Some(p) = file_path.to_str() {
-                // Compute checksum of file contents. 
-                let mut in_file = File::open(full_path)?;
-                let mut contents = Vec::<u8>::with_capacity(length as usize); 
-                in_file.read_to_end(&mut contents)?;
-                let contents_checksum = checksum(&contents); 
-
-                file_data.push(FileDatum {
-                    name: String::from(p),
-                    length: length,
-                    checksum: contents_checksum,
-                });
-            }
-            else {
-                return Err(Error::FileData(FileDataError::NonUtf8Filepath(
-                    String::from(file_path.to_string_lossy())
-                )));
-            }
+
+            files.push(ScannedFile {
+                full_path: full_path,
+                name: path_to_bytes(&file_path)?,
+                length: metadata.len(),
+                mode: get_mode(&metadata),
+                modified: get_modified(&metadata),
+            });
         }
     }
 
-    Ok(FileData {
-        base_path: full_base_path,
-        data: file_data,
-    })
+    Ok((names_root, files))
+}
+
+// This function returns the raw bytes of a relative file path.
+#[cfg(unix)]
+fn path_to_bytes(file_path: &Path) -> Result<Vec<u8>> {
+    Ok(file_path.as_os_str().as_bytes().to_vec())
+}
+
+// This function returns the UTF-8 bytes of a relative file path, using `/`
+// as the separator like on every other platform. Paths that are not valid
+// Unicode cannot be represented as bytes on this platform.
+#[cfg(not(unix))]
+fn path_to_bytes(file_path: &Path) -> Result<Vec<u8>> {
+    if let Some(p) = file_path.to_str() {
+        Ok(p.replace('\\', "/").into_bytes())
+    }
+    else {
+        Err(Error::FileData(FileDataError::NonUtf8Filepath(
+            String::from(file_path.to_string_lossy())
+        )))
+    }
+}
+
+// This function returns the modification time of a file in seconds since
+// the Unix epoch, or 0 if it cannot be determined.
+fn get_modified(metadata: &Metadata) -> u64 {
+    metadata.modified().ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// This function returns the permissions of a file as Unix mode bits,
+// without the file type bits.
+#[cfg(unix)]
+fn get_mode(metadata: &Metadata) -> u32 {
+    metadata.permissions().mode() & 0o7777
+}
+
+// This function approximates Unix mode bits from the read-only flag.
+#[cfg(not(unix))]
+fn get_mode(metadata: &Metadata) -> u32 {
+    if metadata.permissions().readonly() {
+        0o444
+    }
+    else {
+        DEFAULT_MODE
+    }
+}
+
+/// This enum specifies what `get_from_list_with_options()` does with a path
+/// that is listed more than once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Return a `FileDataError::DuplicatePath` error.
+    Error,
+    /// Keep the first occurrence and ignore the rest.
+    First,
+    /// Keep the last occurrence and ignore the rest.
+    Last,
+}
+
+impl Default for DuplicatePolicy {
+    fn default() -> Self {
+        DuplicatePolicy::Error
+    }
+}
+
+/// This enum specifies whether the name of the scanned directory is part of
+/// the names of the files in it. For example, `testarchives/simple/Cargo.toml`
+/// is named `simple/Cargo.toml` or `Cargo.toml`, respectively.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RootPolicy {
+    /// Names start with the name of the scanned directory.
+    IncludeBasename,
+    /// Names are relative to the scanned directory.
+    StripToContents,
+}
+
+impl Default for RootPolicy {
+    fn default() -> Self {
+        RootPolicy::StripToContents
+    }
+}
+
+impl RootPolicy {
+    // This method returns the directory that names are relative to when
+    // scanning `full_base_path`. The root directory has no name, so its
+    // contents are always named relative to it.
+    fn names_root(&self, full_base_path: &Path) -> PathBuf {
+        match (*self, full_base_path.parent()) {
+            (RootPolicy::IncludeBasename, Some(parent)) => parent.to_path_buf(),
+            _ => full_base_path.to_path_buf(),
+        }
+    }
+}
+
+/// This struct contains settings for scanning a directory with
+/// `get_with_options()` or reading a list with `get_from_list_with_options()`.
+#[derive(Clone, Debug, Default)]
+pub struct GetOptions {
+    max_depth: Option<usize>,
+    duplicate_policy: DuplicatePolicy,
+    root_policy: RootPolicy,
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl GetOptions {
+    /// This method returns the default options (i.e. unlimited recursion).
+    pub fn new() -> Self {
+        GetOptions::default()
+    }
+
+    /// This method sets the maximum depth to descend into `base_path`.
+    /// A depth of `Some(1)` only lists the files directly inside `base_path`,
+    /// and `None` (the default) recurses without limit.
+    ///
+    /// # Arguments
+    ///
+    /// * max_depth - maximum directory depth to scan
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// This method sets how paths listed more than once are handled by
+    /// `get_from_list_with_options()`. The default is
+    /// `DuplicatePolicy::Error`.
+    ///
+    /// # Arguments
+    ///
+    /// * duplicate_policy - what to do with duplicate paths
+    pub fn duplicate_policy(mut self, duplicate_policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = duplicate_policy;
+        self
+    }
+
+    /// This method sets whether the name of `base_path` itself is part of
+    /// the name of every file. The default is `RootPolicy::StripToContents`.
+    ///
+    /// # Arguments
+    ///
+    /// * root_policy - how names relate to `base_path`
+    pub fn root_policy(mut self, root_policy: RootPolicy) -> Self {
+        self.root_policy = root_policy;
+        self
+    }
+
+    /// This method adds a glob pattern that files must match to be scanned
+    /// by `get_with_options()`. It can be called more than once, in which
+    /// case a file only has to match one of the patterns. With no include
+    /// patterns (the default), every file is included.
+    ///
+    /// Patterns match against the path of a file relative to `base_path`,
+    /// regardless of the root policy. Note that `*` also matches `/`, so
+    /// `*.html` matches `.html` files in subdirectories as well. An invalid
+    /// pattern causes scanning to fail with `Error::Pattern`.
+    ///
+    /// # Arguments
+    ///
+    /// * pattern - glob pattern of files to include
+    pub fn include<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    /// This method adds a glob pattern of files to skip when scanning with
+    /// `get_with_options()` (e.g. `.git/*`). It can be called more than
+    /// once. Exclude patterns take precedence over include patterns, so a
+    /// file matching both is skipped. Patterns match the same way as those
+    /// passed to `include()`.
+    ///
+    /// # Arguments
+    ///
+    /// * pattern - glob pattern of files to exclude
+    pub fn exclude<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
 }
 
 /// This struct contains information on all the normal files in a given location.
@@ -98,17 +620,51 @@ pub struct FileData {
 }
 
 impl FileData {
-    // This is needed for unit tests in v1.rs so the fields of
-    // `FileData` do not have to be public.
-    #[cfg(test)]
-    pub fn new(base_path: PathBuf, data: Vec<FileDatum>) -> Self {
+    /// This method creates a `FileData` from information gathered by the
+    /// caller (e.g. from a database) instead of by scanning a directory.
+    /// Nothing is checked: the caller is responsible for every file
+    /// existing under `base_path` with the length and checksum given in
+    /// its `FileDatum` (see `FileDatum::from_parts`). Archiving a file whose
+    /// contents do not match fails with `FileArcoV1Error::SourceChanged`.
+    ///
+    /// # Arguments
+    ///
+    /// * base_path - directory the names of the files are relative to
+    ///
+    /// * data - information on each file
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate crc;
+    /// extern crate filearco;
+    ///
+    /// use std::fs;
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// use filearco::{FileData, FileDatum};
+    ///
+    /// let base_path = Path::new("testarchives/simple");
+    /// let contents = fs::read(base_path.join("Cargo.toml")).unwrap();
+    ///
+    /// let datum = FileDatum::from_parts(String::from("Cargo.toml"),
+    ///                                   contents.len() as u64,
+    ///                                   crc::crc64::checksum_iso(&contents));
+    /// let file_data = FileData::from_parts(base_path.to_path_buf(), vec![datum]);
+    ///
+    /// filearco::v1::FileArco::make(file_data, io::sink()).ok().unwrap();
+    /// ```
+    pub fn from_parts(base_path: PathBuf, data: Vec<FileDatum>) -> Self {
         FileData {
             base_path: base_path,
             data: data,
         }
     }
 
-    /// This method returns the path of the indexed directory.
+    /// This method returns the path of the directory that the names of the
+    /// indexed files are relative to. This is the indexed directory itself
+    /// unless `RootPolicy::IncludeBasename` was used.
     pub fn path(&self) -> PathBuf {
         self.base_path.clone()
     }
@@ -118,6 +674,45 @@ impl FileData {
         self.data.len()
     }
 
+    /// This method returns the total length (in bytes) of all files indexed.
+    pub fn total_len(&self) -> u64 {
+        self.data.iter().map(|datum| datum.len()).sum()
+    }
+
+    /// This method returns the total length (in bytes) of all files indexed
+    /// once each is padded to a multiple of the memory page size. This is
+    /// the size of the file contents section of an archive made from them
+    /// (or an upper bound, if some files have identical contents).
+    pub fn total_aligned_len(&self) -> u64 {
+        let page_size = get_page_size() as u64;
+
+        self.data.iter().map(|datum| get_aligned_length(datum.len(), page_size)).sum()
+    }
+
+    /// This method removes every indexed file for which `f` returns
+    /// `false`, e.g. to leave large files out of an archive. The remaining
+    /// files keep their order and base path.
+    ///
+    /// # Arguments
+    ///
+    /// * f - returns whether to keep a file
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let base_path = Path::new("testarchives/simple");
+    /// let mut file_data = filearco::get_file_data(base_path).ok().unwrap();
+    ///
+    /// file_data.retain(|datum| datum.len() < 4096);
+    /// ```
+    pub fn retain<F: FnMut(&FileDatum) -> bool>(&mut self, f: F) {
+        self.data.retain(f);
+    }
+
     /// This method consumes this struct and returns a Vec of its contents.
     pub fn into_vec(self) -> Vec<FileDatum> {
         self.data
@@ -127,37 +722,73 @@ impl FileData {
 /// Errors retrieving information on files
 #[derive(Debug)]
 pub enum FileDataError {
+    /// Input path does not exist
+    BasePathMissing,
     /// Input path is not a directory
     BasePathNotDirectory,
-    /// Non UTF-8 filename detected
+    /// Path was listed more than once
+    DuplicatePath(String),
+    /// Listed path is not inside the base path
+    InvalidPath(String),
+    /// Non UTF-8 filename detected on a platform without byte paths
     NonUtf8Filepath(String),
+    /// Worker thread computing checksums panicked
+    WorkerPanicked,
 }
 
 impl fmt::Display for FileDataError {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match *self {
+            FileDataError::BasePathMissing => {
+                write!(fmt, "Base path does not exist")
+            },
             FileDataError::BasePathNotDirectory => {
                 write!(fmt, "Base path is not a directory")
             },
+            FileDataError::DuplicatePath(ref file_path) => {
+                write!(fmt, "Duplicate path: {}", file_path)
+            },
+            FileDataError::InvalidPath(ref file_path) => {
+                write!(fmt, "Path is not inside the base path: {}", file_path)
+            },
             FileDataError::NonUtf8Filepath(ref file_path) => {
                 write!(fmt, "{}", file_path)
             },
+            FileDataError::WorkerPanicked => {
+                write!(fmt, "Checksum worker thread panicked")
+            },
         }
     }
 }
 
 impl error::Error for FileDataError {
     fn description(&self) -> &str {
+        static BASE_PATH_MISSING: &'static str = "Base path does not exist";
         static BASE_PATH_NOT_DIRECTORY: &'static str = "Base path is not a directory";
+        static DUPLICATE_PATH: &'static str = "Duplicate path";
+        static INVALID_PATH: &'static str = "Path is not inside the base path";
         static NON_UTF8_FILE_PATH: &'static str = "Non-Utf8 file path detected";
+        static WORKER_PANICKED: &'static str = "Checksum worker thread panicked";
 
         match *self {
+            FileDataError::BasePathMissing => {
+                BASE_PATH_MISSING
+            },
             FileDataError::BasePathNotDirectory => {
                 BASE_PATH_NOT_DIRECTORY
             },
+            FileDataError::DuplicatePath(_) => {
+                DUPLICATE_PATH
+            },
+            FileDataError::InvalidPath(_) => {
+                INVALID_PATH
+            },
             FileDataError::NonUtf8Filepath(_) => {
                 NON_UTF8_FILE_PATH
             },
+            FileDataError::WorkerPanicked => {
+                WORKER_PANICKED
+            },
         }
     }
 
@@ -167,25 +798,46 @@ impl error::Error for FileDataError {
 /// This struct contains basic information about a file.
 #[derive(Clone)]
 pub struct FileDatum {
-    name: String,
+    name: Vec<u8>,
     length: u64,
     checksum: u64,
+    mode: u32,
+    modified: u64,
 }
 
 impl FileDatum {
-    // This is needed for unit tests in v1.rs so the fields of
-    // `FileDatum` do not have to be public.
-    #[cfg(test)]
-    pub fn new(name: String, length: u64, checksum: u64) -> Self {
+    /// This method creates a `FileDatum` for `FileData::from_parts`. The
+    /// caller is responsible for `length` being the length of the file and
+    /// `checksum` the CRC-64/ISO checksum of its contents. The file gets
+    /// the default mode (0o644) and an unknown modification time.
+    ///
+    /// # Arguments
+    ///
+    /// * name - path of the file relative to the base path, with `/`
+    ///   separators
+    ///
+    /// * length - length of the file (in bytes)
+    ///
+    /// * checksum - checksum of the contents of the file
+    pub fn from_parts(name: String, length: u64, checksum: u64) -> Self {
         FileDatum {
-            name: name,
+            name: name.into_bytes(),
             length: length,
             checksum: checksum,
+            mode: DEFAULT_MODE,
+            modified: 0,
         }
     }
 
+    /// This method returns the relative path of the file. Any bytes that
+    /// are not valid UTF-8 are replaced with `U+FFFD`.
     pub fn name(&self) -> String {
-        self.name.clone()
+        String::from_utf8_lossy(&self.name).into_owned()
+    }
+
+    /// This method returns the exact bytes of the relative path of the file.
+    pub fn name_bytes(&self) -> &[u8] {
+        &self.name
     }
 
     pub fn len(&self) -> u64 {
@@ -195,61 +847,27 @@ impl FileDatum {
     pub fn checksum(&self) -> u64 {
         self.checksum
     }
+
+    /// This method returns the permissions of the file as Unix mode bits.
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    /// This method returns the modification time of the file in seconds
+    /// since the Unix epoch, or 0 if it is unknown.
+    pub fn modified(&self) -> u64 {
+        self.modified
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use std::sync::Arc;
 
-    #[cfg(windows)]
-    fn get_reqchan_docs() -> Vec<String> {
-        let mut v = Vec::<String>::new();
 
-        v.push(String::from("implementors\\core\\clone\\trait.Clone.js"));
-        v.push(String::from("implementors\\core\\fmt\\trait.Debug.js"));
-        v.push(String::from("implementors\\core\\ops\\trait.Drop.js"));
-        v.push(String::from("reqchan\\RequestContract.t.html"));
-        v.push(String::from("reqchan\\Requester.t.html"));
-        v.push(String::from("reqchan\\Responder.t.html"));
-        v.push(String::from("reqchan\\ResponseContract.t.html"));
-        v.push(String::from("reqchan\\TryReceiveError.t.html"));
-        v.push(String::from("reqchan\\TryRequestError.t.html"));
-        v.push(String::from("reqchan\\TryRespondError.t.html"));
-        v.push(String::from("reqchan\\channel.v.html"));
-        v.push(String::from("reqchan\\enum.TryReceiveError.html"));
-        v.push(String::from("reqchan\\enum.TryRequestError.html"));
-        v.push(String::from("reqchan\\enum.TryRespondError.html"));
-        v.push(String::from("reqchan\\fn.channel.html"));
-        v.push(String::from("reqchan\\index.html"));
-        v.push(String::from("reqchan\\sidebar-items.js"));
-        v.push(String::from("reqchan\\struct.RequestContract.html"));
-        v.push(String::from("reqchan\\struct.Requester.html"));
-        v.push(String::from("reqchan\\struct.Responder.html"));
-        v.push(String::from("reqchan\\struct.ResponseContract.html"));
-        v.push(String::from("src\\reqchan\\lib.rs.html"));
-        v.push(String::from("COPYRIGHT.txt"));
-        v.push(String::from("FiraSans-LICENSE.txt"));
-        v.push(String::from("FiraSans-Medium.woff"));
-        v.push(String::from("FiraSans-Regular.woff"));
-        v.push(String::from("Heuristica-Italic.woff"));
-        v.push(String::from("Heuristica-LICENSE.txt"));
-        v.push(String::from("LICENSE-APACHE.txt"));
-        v.push(String::from("LICENSE-MIT.txt"));
-        v.push(String::from("SourceCodePro-LICENSE.txt"));
-        v.push(String::from("SourceCodePro-Regular.woff"));
-        v.push(String::from("SourceCodePro-Semibold.woff"));
-        v.push(String::from("SourceSerifPro-Bold.woff"));
-        v.push(String::from("SourceSerifPro-LICENSE.txt"));
-        v.push(String::from("SourceSerifPro-Regular.woff"));
-        v.push(String::from("main.css"));
-        v.push(String::from("main.js"));
-        v.push(String::from("normalize.css"));
-        v.push(String::from("rustdoc.css"));
-        v.push(String::from("search-index.js"));
+    use super::*;
 
-        v
-    }
-    #[cfg(not(windows))]
+    // Names use `/` as the separator on every platform.
     fn get_reqchan_docs() -> Vec<String> {
         let mut v = Vec::<String>::new();
 
@@ -298,6 +916,235 @@ mod tests {
         v
     }
 
+    #[test]
+    fn test_v1_get_file_data_max_depth() {
+        let path = Path::new("testarchives/reqchandocs");
+        let options = GetOptions::new().max_depth(Some(1));
+
+        let file_data = get_with_options(path, &options).ok().unwrap();
+        let top_level = get_reqchan_docs().into_iter()
+            .filter(|name| !name.contains('/') && !name.contains('\\'))
+            .collect::<Vec<_>>();
+
+        assert_eq!(file_data.len(), top_level.len());
+
+        for datum in file_data.into_vec().iter() {
+            assert!(top_level.contains(&datum.name()));
+        }
+    }
+
+    #[test]
+    fn test_v1_get_file_data_include_exclude() {
+        let path = Path::new("testarchives/reqchandocs");
+
+        let names = |options: GetOptions| {
+            get_with_options(path, &options).ok().unwrap()
+                .into_vec().iter().map(|datum| datum.name()).collect::<Vec<_>>()
+        };
+
+        let excluded = names(GetOptions::new().exclude("implementors/*").exclude("*.woff"));
+        let expected = get_reqchan_docs().into_iter()
+            .filter(|name| !name.starts_with("implementors/") && !name.ends_with(".woff"))
+            .collect::<Vec<_>>();
+        assert_eq!(excluded.len(), expected.len());
+        assert!(excluded.iter().all(|name| expected.contains(name)));
+
+        let included = names(GetOptions::new().include("*.css").include("*.js"));
+        let expected = get_reqchan_docs().into_iter()
+            .filter(|name| name.ends_with(".css") || name.ends_with(".js"))
+            .collect::<Vec<_>>();
+        assert_eq!(included.len(), expected.len());
+        assert!(included.iter().all(|name| expected.contains(name)));
+
+        // Exclude patterns win over include patterns.
+        let both = names(GetOptions::new().include("*.css").exclude("main.css"));
+        assert!(!both.is_empty());
+        assert!(both.iter().all(|name| name.ends_with(".css") && name != "main.css"));
+
+        // Patterns are relative to the base path, not the names.
+        let options = GetOptions::new()
+            .root_policy(RootPolicy::IncludeBasename)
+            .include("reqchan/*");
+        let prefixed = names(options);
+        assert!(!prefixed.is_empty());
+        assert!(prefixed.iter().all(|name| name.starts_with("reqchandocs/reqchan/")));
+
+        match get_with_options(path, &GetOptions::new().exclude("[")) {
+            Err(Error::Pattern(_)) => {},
+            _ => panic!("invalid pattern was not detected"),
+        }
+    }
+
+    #[test]
+    fn test_v1_get_file_data_base_path() {
+        let missing = Path::new("testarchives/does-not-exist");
+        let file = Path::new("testarchives/simple/Cargo.toml");
+
+        match get(missing) {
+            Err(Error::FileData(FileDataError::BasePathMissing)) => {},
+            _ => panic!("missing base path was not detected"),
+        }
+
+        match get(file) {
+            Err(Error::FileData(FileDataError::BasePathNotDirectory)) => {},
+            _ => panic!("base path that is a file was not detected"),
+        }
+
+        match get_from_list(missing, &["Cargo.toml"]) {
+            Err(Error::FileData(ref err @ FileDataError::BasePathMissing)) => {
+                assert_eq!(err.to_string(), "Base path does not exist");
+            },
+            _ => panic!("missing base path was not detected"),
+        }
+
+        match get_from_list(file, &["Cargo.toml"]) {
+            Err(Error::FileData(ref err @ FileDataError::BasePathNotDirectory)) => {
+                assert_eq!(err.to_string(), "Base path is not a directory");
+            },
+            _ => panic!("base path that is a file was not detected"),
+        }
+    }
+
+    #[test]
+    fn test_v1_get_file_data_from_list_duplicates() {
+        let path = Path::new("testarchives/simple");
+        let list = ["Cargo.toml", "LICENSE-MIT", "./Cargo.toml"];
+
+        let names = |policy| {
+            let options = GetOptions::new().duplicate_policy(policy);
+            get_from_list_with_options(path, &list, &options).ok().unwrap()
+                .into_vec().iter().map(|datum| datum.name()).collect::<Vec<_>>()
+        };
+
+        match get_from_list(path, &list) {
+            Err(Error::FileData(FileDataError::DuplicatePath(ref name))) => {
+                assert_eq!(name, "Cargo.toml");
+            },
+            _ => panic!("duplicate path was not detected"),
+        }
+
+        assert_eq!(names(DuplicatePolicy::First), vec!["Cargo.toml", "LICENSE-MIT"]);
+        assert_eq!(names(DuplicatePolicy::Last), vec!["LICENSE-MIT", "Cargo.toml"]);
+    }
+
+    #[test]
+    fn test_v1_get_file_data_from_list_invalid_paths() {
+        let path = Path::new("testarchives/simple");
+        let outside = Path::new("Cargo.toml").canonicalize().ok().unwrap();
+
+        match get_from_list(path, &[outside]) {
+            Err(Error::FileData(FileDataError::InvalidPath(_))) => {},
+            _ => panic!("absolute path was not detected"),
+        }
+
+        for list in [["../simple_v1.fac"], ["./../simple/Cargo.toml"], ["a/../Cargo.toml"]].iter() {
+            match get_from_list(path, list) {
+                Err(Error::FileData(FileDataError::InvalidPath(_))) => {},
+                _ => panic!("path outside base path was not detected"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_v1_get_file_data_parallel() {
+        let path = Path::new("testarchives/reqchandocs");
+
+        let serial = get(path).ok().unwrap();
+        let parallel = get_parallel(path, 4).ok().unwrap();
+
+        assert_eq!(serial.path(), parallel.path());
+
+        let svec = serial.into_vec();
+        let pvec = parallel.into_vec();
+
+        assert_eq!(svec.len(), pvec.len());
+
+        for (s, p) in svec.iter().zip(pvec.iter()) {
+            assert_eq!(s.name(), p.name());
+            assert_eq!(s.len(), p.len());
+            assert_eq!(s.checksum(), p.checksum());
+        }
+
+        // Options are honored like in `get_with_options()`.
+        let options = GetOptions::new().exclude("implementors/*").max_depth(Some(2));
+        let serial = get_with_options(path, &options).ok().unwrap().into_vec();
+        let parallel = get_parallel_with_options(path, 3, &options).ok().unwrap().into_vec();
+
+        assert!(parallel.len() < svec.len());
+        assert_eq!(serial.iter().map(|datum| datum.name()).collect::<Vec<_>>(),
+                   parallel.iter().map(|datum| datum.name()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_v1_get_file_data_parallel_worker_panicked() {
+        let handles = vec![thread::spawn(|| Ok(Vec::new())),
+                           thread::spawn(|| panic!("worker failed"))];
+
+        match join_workers(handles) {
+            Err(Error::FileData(FileDataError::WorkerPanicked)) => {},
+            _ => panic!("worker panic was not reported"),
+        }
+    }
+
+    #[test]
+    fn test_v1_get_file_data_root_policy() {
+        let path = Path::new("testarchives/simple");
+        let full_path = path.canonicalize().ok().unwrap();
+
+        let options = GetOptions::new().root_policy(RootPolicy::StripToContents);
+        let file_data = get_with_options(path, &options).ok().unwrap();
+        assert_eq!(file_data.path(), full_path);
+        assert!(file_data.into_vec().iter().any(|datum| datum.name() == "Cargo.toml"));
+
+        let options = GetOptions::new().root_policy(RootPolicy::IncludeBasename);
+        let file_data = get_with_options(path, &options).ok().unwrap();
+        assert_eq!(file_data.path(), full_path.parent().unwrap());
+        assert!(file_data.clone().into_vec().iter()
+                .all(|datum| datum.name().starts_with("simple/")));
+
+        let list = ["Cargo.toml"];
+        let listed = get_from_list_with_options(path, &list, &options).ok().unwrap();
+        assert_eq!(listed.into_vec()[0].name(), "simple/Cargo.toml");
+
+        let mut archive_bytes = Vec::new();
+        ::v1::FileArco::make(file_data, &mut archive_bytes).ok().unwrap();
+
+        let archive = ::v1::FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+        assert!(archive.get("Cargo.toml").is_none());
+        assert_eq!(archive.get("simple/Cargo.toml").unwrap().len(),
+                   path.join("Cargo.toml").metadata().ok().unwrap().len());
+        assert!(archive.get("simple/Cargo.toml").unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_v1_file_data_total_len() {
+        let path = Path::new("testarchives/simple");
+        let file_data = get(path).ok().unwrap();
+
+        let expected = file_data.clone().into_vec().iter()
+            .map(|datum| path.join(datum.name()).metadata().ok().unwrap().len())
+            .sum::<u64>();
+
+        assert_eq!(file_data.total_len(), expected);
+        assert!(file_data.total_aligned_len() >= file_data.total_len());
+        assert_eq!(file_data.total_aligned_len() % get_page_size() as u64, 0);
+    }
+
+    #[test]
+    fn test_v1_file_data_retain() {
+        let path = Path::new("testarchives/reqchandocs");
+        let mut file_data = get(path).ok().unwrap();
+        let total = file_data.len();
+        let base_path = file_data.path();
+
+        file_data.retain(|datum| datum.name().ends_with(".txt"));
+
+        assert_eq!(file_data.len(), 7);
+        assert!(file_data.len() < total);
+        assert_eq!(file_data.path(), base_path);
+        assert!(file_data.into_vec().iter().all(|datum| datum.name().ends_with(".txt")));
+    }
+
     #[test]
     fn test_v1_get_file_data() {
         let reqchan_docs = get_reqchan_docs();
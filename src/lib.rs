@@ -36,22 +36,35 @@
 
 extern crate bincode;
 extern crate crc;
+extern crate flate2;
+extern crate glob;
+#[cfg(unix)]
+extern crate libc;
 extern crate memmap;
 extern crate page_size;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+extern crate sha2;
+extern crate hmac;
 extern crate walkdir;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "prefetch"))]
 extern crate memadvise;
 
 const FILEARCO_ID: &'static [u8; 8] = b"FILEARCO";
 
 mod file_data;
 pub mod v1;
+pub mod v2;
 
-pub use file_data::{get as get_file_data, FileData, FileDataError};
+pub use file_data::{get as get_file_data, get_parallel as get_file_data_parallel,
+                    get_parallel_with_options as get_file_data_parallel_with_options,
+                    get_with_options as get_file_data_with_options,
+                    get_from_list as get_file_data_from_list,
+                    get_from_list_with_options as get_file_data_from_list_with_options,
+                    DuplicatePolicy, FileData, FileDataError, FileDatum, GetOptions,
+                    RootPolicy};
 
 use std::error;
 use std::fmt;
@@ -66,7 +79,10 @@ pub enum Error {
     Utf8(str::Utf8Error),
     Walkdir(walkdir::Error),
     FileArcoV1(v1::FileArcoV1Error),
+    FileArcoV2(v2::FileArcoV2Error),
     FileData(FileDataError),
+    Pattern(glob::PatternError),
+    Bincode(bincode::Error),
 }
 
 impl fmt::Display for Error {
@@ -76,7 +92,10 @@ impl fmt::Display for Error {
             &Error::Utf8(ref err) => err.fmt(fmt),
             &Error::Walkdir(ref err) => err.fmt(fmt),
             &Error::FileArcoV1(ref err) => err.fmt(fmt),
+            &Error::FileArcoV2(ref err) => err.fmt(fmt),
             &Error::FileData(ref err) => err.fmt(fmt),
+            &Error::Pattern(ref err) => err.fmt(fmt),
+            &Error::Bincode(ref err) => err.fmt(fmt),
         }
     }
 }
@@ -88,7 +107,10 @@ impl error::Error for Error {
             &Error::Utf8(ref err) => err.description(),
             &Error::Walkdir(ref err) => err.description(),
             &Error::FileArcoV1(ref err) => err.description(),
+            &Error::FileArcoV2(ref err) => err.description(),
             &Error::FileData(ref err) => err.description(),
+            &Error::Pattern(ref err) => err.description(),
+            &Error::Bincode(ref err) => err.description(),
         }
     }
 
@@ -98,7 +120,10 @@ impl error::Error for Error {
             &Error::Utf8(ref err) => err.cause(),
             &Error::Walkdir(ref err) => err.cause(),
             &Error::FileArcoV1(ref err) => err.cause(),
+            &Error::FileArcoV2(ref err) => err.cause(),
             &Error::FileData(ref err) => err.cause(),
+            &Error::Pattern(ref err) => err.cause(),
+            &Error::Bincode(ref err) => err.cause(),
         }
     }
 }
@@ -121,12 +146,30 @@ impl From<walkdir::Error> for Error {
     }
 }
 
+impl From<glob::PatternError> for Error {
+    fn from(err: glob::PatternError) -> Error {
+        Error::Pattern(err)
+    }
+}
+
+impl From<bincode::Error> for Error {
+    fn from(err: bincode::Error) -> Error {
+        Error::Bincode(err)
+    }
+}
+
 impl From<v1::FileArcoV1Error> for Error {
     fn from(err: v1::FileArcoV1Error) -> Error {
         Error::FileArcoV1(err)
     }
 }
 
+impl From<v2::FileArcoV2Error> for Error {
+    fn from(err: v2::FileArcoV2Error) -> Error {
+        Error::FileArcoV2(err)
+    }
+}
+
 /// This is the result type.
 pub type Result<T> = result::Result<T, Error>;
 
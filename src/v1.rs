@@ -20,29 +20,111 @@
 //! println!("{}", license_apache.as_str().ok().unwrap());
 //! ```
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cell::UnsafeCell;
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::AsRef;
 use std::error;
+#[cfg(unix)]
+use std::ffi::OsStr;
+use std::ffi::OsString;
 use std::fmt;
-use std::fs::File;
+use std::fs::{create_dir_all, metadata, remove_file, set_permissions, File, OpenOptions};
+use std::io;
 use std::io::prelude::*;
+use std::io::{BufReader, Cursor, SeekFrom};
 use std::mem;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+#[cfg(unix)]
+use std::os::unix::fs::FileExt;
+#[cfg(unix)]
+use std::os::unix::io::{FromRawFd, RawFd};
 use std::slice;
 use std::str;
-use std::sync::Arc;
-use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, Once};
+use std::sync::atomic::{AtomicU8, Ordering};
+#[cfg(test)]
+use std::sync::atomic::AtomicUsize;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use bincode::{serialize, deserialize, Bounded, Infinite};
-use crc::crc64::checksum_iso as checksum;
+use bincode::{serialize, deserialize, deserialize_from, Bounded, Infinite};
+use crc::crc32::{update as update_crc32, IEEE_TABLE};
+use crc::crc64::{update as update_checksum, ISO_TABLE};
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use glob::Pattern;
+use hmac::{Hmac, Mac};
 use memmap::{Mmap, Protection};
 use page_size::get as get_page_size;
+use serde::{Deserialize, Serialize, Serializer};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
 
 use super::{Error, FILEARCO_ID, Result};
-use file_data::FileData;
+use file_data::{FileData, DEFAULT_MODE};
 
 const VERSION_NUMBER: u64 = 1;
 
+// Bits 32 to 47 of `version_number` hold the revision of the entries table
+// layout. Revision 0 is the original layout, and revision 1 adds file modes,
+// modification times, non-UTF-8 names, compression, the producer, content
+// types, SHA-256 digests and inline contents.
+const REVISION: u64 = 1;
+const REVISION_SHIFT: u64 = 32;
+
+// The high 16 bits of `version_number` hold flags for optional features.
+const FLAGS_SHIFT: u64 = 48;
+
+// Set if any file in the archive is compressed.
+const FLAG_COMPRESSED: u64 = 1;
+
+// Set if the file checksums were not computed and are all 0.
+const FLAG_NO_CHECKSUMS: u64 = 2;
+
+// Set if the file checksums are CRC-32 rather than CRC-64 checksums.
+const FLAG_CRC32: u64 = 4;
+
+// Set if every file has a SHA-256 digest, and the entries table is
+// followed by its own digest.
+const FLAG_SHA256: u64 = 8;
+
+// Every flag this crate knows how to read.
+const KNOWN_FLAGS: u64 = FLAG_COMPRESSED | FLAG_NO_CHECKSUMS | FLAG_CRC32 | FLAG_SHA256;
+
+// Size of the buffer used when copying file contents.
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+// Number of decompression chunks each archive keeps for reuse.
+const DECOMPRESS_BUFFERS: usize = 8;
+
+// `header_checksum` is bounded to the size of a u64 (probably 8 bytes).
+const CHECKSUM_SIZE: usize = 8;
+
+// Size of a SHA-256 digest.
+const DIGEST_SIZE: usize = 32;
+
+// Identifies the signature block after a signed archive.
+const SIGNATURE_ID: &'static [u8; 8] = b"ARCOSIGN";
+
+// Size of the signature block: its ID followed by an HMAC-SHA256.
+const SIGNATURE_SIZE: usize = 8 + DIGEST_SIZE;
+
+// Identifier at the very end of an archive using the trailer layout.
+const TRAILER_ID: &'static [u8; 8] = b"ARCOTAIL";
+
+// Size of an encoded `Footer`.
+const FOOTER_SIZE: usize = 16;
+
 /// This represents an open, memory-mapped FileArco v1 archive file.
+///
+/// The mapping (or buffer) is shared by every `FileRef`, `ArchiveHandle` and
+/// `RerootedArchive` created from the archive, and it is only released once
+/// the last of them and the `FileArco` itself are dropped. Use `try_close()`
+/// to release it at a known point.
 pub struct FileArco {
     inner: Arc<Inner>,
 }
@@ -51,6 +133,10 @@ impl FileArco {
     /// This method tries to map a file specified by `path` into memory
     /// and process it as a FileArco V1 archive file.
     ///
+    /// **NOTE:** On 32-bit targets, every file (and the archive itself)
+    /// must be smaller than 4 GiB, or `FileArcoV1Error::FileTooLarge` is
+    /// returned.
+    ///
     /// # Arguments
     ///
     /// * path - file path of archive file
@@ -66,95 +152,17 @@ impl FileArco {
     /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
     /// ```
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let map = Mmap::open_path(path.as_ref(), Protection::Read)?;
-
-        // Create test Header to determine size of encoded header.
-        let test_header = Header::new(
-            get_page_size() as u64,
-            0,
-            0,
-            0
-        );
-        let test_header_encoded = serialize(&test_header, Infinite).unwrap();
-
-        // `header_checksum` is bounded to the size of a u64 (probably 8 bytes).
-        let checksum_size = mem::size_of::<u64>();
-
-        // Make sure file is large enough to contain a FileArco v1 header.
-        if map.len() < test_header_encoded.len() + checksum_size {
-            return Err(Error::FileArcoV1(FileArcoV1Error::FileTooSmall));
-        }
-
-        // Read in header.
-        let (header, checksum1): (Header, u64) = unsafe {
-            let ptr = map.ptr().offset(0);
-            let sl = slice::from_raw_parts(
-                ptr,
-                test_header_encoded.len()
-            );
-
-            (
-                deserialize(sl).unwrap(),
-                checksum(&sl)
-            )
-        };
-
-        // Read in header checksum.
-        let header_checksum: u64 = unsafe {
-            let ptr = map.ptr().offset(test_header_encoded.len() as isize);
-            let sl = slice::from_raw_parts(ptr, checksum_size);
-            deserialize(sl).unwrap()
-        };
-
-        // Ensure header is valid.
-        if header.id != *FILEARCO_ID {
-            return Err(Error::FileArcoV1(FileArcoV1Error::NotArchive));
-        }
-
-        if header.version_number != 1 {
-            return Err(Error::FileArcoV1(FileArcoV1Error::NotV1Archive));
-        }
-
-        if checksum1 != header_checksum {
-            return Err(Error::FileArcoV1(FileArcoV1Error::CorruptedHeader));
-        }
-
-        if (map.len() as u64) < header.file_length {
-            return Err(Error::FileArcoV1(FileArcoV1Error::FileTruncated));
-        }
-
-        // Read in entries data.
-        let (entries, checksum2) = unsafe {
-            let offset = checksum_size + test_header_encoded.len();
-            let ptr = map.ptr().offset(offset as isize);
-            let sl = slice::from_raw_parts(ptr, header.entries_length as usize);
-
-            (
-                deserialize(sl).unwrap(),
-                checksum(&sl)
-            )
-        };
-
-        // Ensure entries table is valid.
-        if checksum2 != header.entries_checksum {
-            return Err(Error::FileArcoV1(FileArcoV1Error::CorruptedEntriesTable));
-        }
-
-        Ok(FileArco {
-            inner: Arc::new(Inner {
-                file_offset: header.file_offset,
-                page_size: header.page_size,
-                entries: entries,
-                map: map,
-            })
-        })
+        FileArco::new_with_seed(path, 0)
     }
 
-    /// This method retrieves a file from the archive, if it exists.
+    /// This method opens an archive created with `MakeOptions::seed`. The
+    /// same `seed` must be given or the archive will fail to validate.
     ///
     /// # Arguments
     ///
-    /// * file_path - name of file to retrieve
+    /// * path - file path of archive file
+    ///
+    /// * seed - secret value mixed into every checksum
     ///
     /// # Example
     ///
@@ -164,30 +172,19 @@ impl FileArco {
     /// use std::path::Path;
     ///
     /// let path = Path::new("testarchives/simple_v1.fac");
-    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
-    /// 
-    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
+    /// let file_data = filearco::v1::FileArco::new_with_seed(path, 0).ok().unwrap(); 
     /// ```
-    pub fn get<P: AsRef<str>>(&self, file_path: P) -> Option<FileRef> {
-        if let Some(entry) = self.inner.entries.files.get(file_path.as_ref()) {
-            let offset = (self.inner.file_offset + entry.offset) as isize;
-            let address = unsafe { self.inner.map.ptr().offset(offset) };
-
-            Some(FileRef {
-                address: address,
-                length: entry.length,
-                aligned_length: entry.aligned_length,
-                checksum: entry.checksum,
-                inner: self.inner.clone(),
-            })
-        }
-        else {
-            None
-        }
+    pub fn new_with_seed<P: AsRef<Path>>(path: P, seed: u64) -> Result<Self> {
+        FileArco::open(path.as_ref(), seed, false, &mut OpenMetrics::default())
     }
 
-    /// This method returns the memory page size of the system used to create
-    /// the archive file.
+    /// This method works like `new` but also builds an index of lowercased
+    /// names, so `get_ci()` takes constant rather than linear time. This
+    /// costs one extra `String` per file.
+    ///
+    /// # Arguments
+    ///
+    /// * path - file path of archive file
     ///
     /// # Example
     ///
@@ -197,153 +194,167 @@ impl FileArco {
     /// use std::path::Path;
     ///
     /// let path = Path::new("testarchives/simple_v1.fac");
-    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
-    /// println!("{}", file_data.page_size());
+    /// let archive = filearco::v1::FileArco::new_case_insensitive(path).ok().unwrap(); 
+    ///
+    /// let cargo_toml = archive.get_ci("cargo.TOML").unwrap();
     /// ```
-    pub fn page_size(&self) -> u64 {
-        self.inner.page_size
+    pub fn new_case_insensitive<P: AsRef<Path>>(path: P) -> Result<Self> {
+        FileArco::open(path.as_ref(), 0, true, &mut OpenMetrics::default())
     }
-    
-    /// This method creates a FileArco v1 archive file, populates it with
-    /// the specified files, and writes the result to the standard output.
+
+    /// This method works like `new` but sets the size (in bytes) of the
+    /// chunks `FileRef::decompress_to()` inflates compressed files in. The
+    /// default is 64 KiB. Larger chunks speed up decompressing big files,
+    /// while smaller ones bound the memory used by many concurrent
+    /// decompressions. Sizes below 1 byte are rounded up to 1.
     ///
     /// # Arguments
     ///
-    /// * file_data - file paths and other metadata of the input files
+    /// * path - file path of archive file
     ///
-    /// * out_path - file path for archive file
+    /// * size - size of decompression chunks
     ///
     /// # Example
     ///
     /// ```rust
     /// extern crate filearco;
     ///
-    /// use std::fs::File;
-    /// use std::io;
     /// use std::path::Path;
     ///
-    /// let base_path = Path::new("testarchives/reqchandocs");
-    /// let file_data = filearco::get_file_data(base_path).ok().unwrap();
-    ///
-    /// filearco::v1::FileArco::make(file_data, io::stdout()).ok().unwrap();
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new_with_decompress_buffer(path, 4096)
+    ///     .ok().unwrap(); 
     /// ```
-    pub fn make<H: Write>(file_data: FileData, mut out_file: H) -> Result<()> {
-        let base_path = file_data.path();
-   
-        // Create entries table and serialize it.
-        let entries = Entries::new(file_data);
-        let entries_encoded: Vec<u8> = serialize(&entries, Infinite).unwrap();
+    pub fn new_with_decompress_buffer<P: AsRef<Path>>(path: P, size: usize) -> Result<Self> {
+        let mut archive = FileArco::open(path.as_ref(), 0, false, &mut OpenMetrics::default())?;
 
-        // Create header, serialize it, and write it to archive.
-        let header = Header::new(get_page_size() as u64,
-                                 entries_encoded.len() as u64,
-                                 entries.total_aligned_length(),
-                                 checksum(&entries_encoded));
-        let header_encoded = serialize(&header, Infinite).unwrap();
-        out_file.write_all(&header_encoded)?;
-
-        // Compute header checksum, serialize it, and write it to archive.
-        let header_checksum = checksum(&header_encoded);
-        let header_checksum_encoded = serialize(
-            &header_checksum,
-            Bounded(mem::size_of::<u64>() as u64)
-        ).unwrap();
-        out_file.write_all(&header_checksum_encoded)?;
-        
-        // Write serialized entries table to archive.
-        out_file.write_all(&entries_encoded)?;
-
-        // Pad archive with zeros to ensure files begin at a multiple of `page_size`.
-        let start_length = header_encoded.len() + header_checksum_encoded.len() +
-            entries_encoded.len();
-        let padding_length = (header.file_offset as usize) - start_length;
-        let padding: Vec<u8> = vec![0u8; padding_length];
-        out_file.write_all(&padding)?;
-
-        // Began writing files to archive.
-        for (path, entry) in &entries.files {
-            let full_path = base_path.to_path_buf().join(Path::new(&path));
-
-            // Read in input file contents and write it to archive.
-            let mut in_file = File::open(full_path)?;
-            let mut buffer = Vec::<u8>::with_capacity(entry.length as usize); 
-            in_file.read_to_end(&mut buffer)?;
-            out_file.write_all(&buffer)?;
-            
-            // Pad archive with zeros to ensure next file begins at a multiple of 4096.
-            let padding_length = entry.aligned_length - entry.length;
-            let padding: Vec<u8> = vec![0u8; padding_length as usize];
-            out_file.write_all(&padding)?;
+        // Nothing else refers to an archive that was just opened.
+        if let Some(inner) = Arc::get_mut(&mut archive.inner) {
+            inner.decompress_buffer_size = cmp::max(size, 1);
         }
-        
-        Ok(())
-    }
-}
 
-/// This struct represents a reference to a slice of memory containing
-/// a requested file from the archive.
-#[allow(dead_code)]
-pub struct FileRef {
-    address: *const u8,
-    length: u64,
-    aligned_length: u64,
-    checksum: u64,
-    // Holding a reference to the memory mapped file ensures it will not be
-    // unmapped until we finish using it.
-    inner: Arc<Inner>,
-}
+        Ok(archive)
+    }
 
-impl FileRef {
-    /// This method ensures the file contents have not been corrupted.
+    /// This method works like `new` but refuses archives whose major
+    /// version (the low 32 bits of `version_number()`) is not in
+    /// `allowed_versions`, returning `FileArcoV1Error::IncompatibleVersion`
+    /// with the full version number. This lets callers reject archives they
+    /// cannot fully support. The revision and flags are not compared; `new`
+    /// already rejects ones this crate cannot read.
+    ///
+    /// # Arguments
+    ///
+    /// * path - file path of archive file
+    ///
+    /// * allowed_versions - major versions to accept
     ///
     /// # Example
     ///
     /// ```rust
     /// extern crate filearco;
     ///
-    /// use std::mem;
     /// use std::path::Path;
     ///
     /// let path = Path::new("testarchives/simple_v1.fac");
-    /// let file_data = filearco::v1::FileArco::new(path).unwrap(); 
-    /// 
-    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
-    /// assert!(cargo_toml.is_valid());
+    /// let archive = filearco::v1::FileArco::open_if_compatible(path, &[1]).ok().unwrap(); 
     /// ```
-    pub fn is_valid(&self) -> bool {
-        let sl = self.as_slice();
-        let checksum_computed = checksum(sl);
+    pub fn open_if_compatible<P: AsRef<Path>>(path: P, allowed_versions: &[u64]) -> Result<Self> {
+        let archive = FileArco::new(path)?;
+        let major_version = archive.version_number() & ((1 << REVISION_SHIFT) - 1);
+
+        if !allowed_versions.contains(&major_version) {
+            return Err(Error::FileArcoV1(
+                FileArcoV1Error::IncompatibleVersion(archive.version_number())
+            ));
+        }
 
-        self.checksum == checksum_computed
+        Ok(archive)
     }
- 
-    /// This method retrieves a byte array representing the contents of a `FileRef`.
+
+    /// This method works like `new` but also reports how long each step of
+    /// opening the archive took, which helps when profiling large archives.
+    ///
+    /// # Arguments
+    ///
+    /// * path - file path of archive file
     ///
     /// # Example
     ///
     /// ```rust
     /// extern crate filearco;
     ///
-    /// use std::mem;
     /// use std::path::Path;
     ///
     /// let path = Path::new("testarchives/simple_v1.fac");
-    /// let file_data = filearco::v1::FileArco::new(path).unwrap(); 
-    /// 
-    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
-    /// let cargo_toml_slice = cargo_toml.as_slice();
-    /// let cargo_toml_text = unsafe { mem::transmute::<&[u8], &str>(cargo_toml_slice) };
-    /// println!("{}", cargo_toml_text);
+    /// let (archive, metrics) = filearco::v1::FileArco::open_timed(path).ok().unwrap(); 
+    /// println!("{:?}", metrics);
     /// ```
-    pub fn as_slice(&self) -> &[u8] {
-        unsafe {
-            slice::from_raw_parts(self.address, self.length as usize)
-        }
+    pub fn open_timed<P: AsRef<Path>>(path: P) -> Result<(Self, OpenMetrics)> {
+        let mut metrics = OpenMetrics::default();
+        let archive = FileArco::open(path.as_ref(), 0, false, &mut metrics)?;
+
+        Ok((archive, metrics))
     }
- 
-    /// This method retrieves a string representing the contents of a `FileRef`.
-    /// It returns an error if the file contents do not represent a valid
-    /// UTF-8 string.
+
+    /// This method maps and validates the archive file open as `fd`, which
+    /// is useful when the file was inherited from another process or cannot
+    /// be reopened by path (e.g. it has been unlinked).
+    ///
+    /// The returned `FileArco` takes ownership of `fd` and closes it once the
+    /// file is mapped (or if opening fails). The mapping stays valid after
+    /// that, so the caller must not use or close `fd` again.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be an open file descriptor that is not owned by anything
+    /// else (e.g. another `File`).
+    ///
+    /// # Arguments
+    ///
+    /// * fd - open file descriptor of archive file
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::fs::File;
+    /// use std::os::unix::io::IntoRawFd;
+    ///
+    /// let fd = File::open("testarchives/simple_v1.fac").unwrap().into_raw_fd();
+    /// let archive = unsafe { filearco::v1::FileArco::from_raw_fd(fd) }.ok().unwrap();
+    /// ```
+    #[cfg(unix)]
+    pub unsafe fn from_raw_fd(fd: RawFd) -> Result<Self> {
+        let file = File::from_raw_fd(fd);
+        let mut metrics = OpenMetrics::default();
+
+        let start = Instant::now();
+        let map = Mmap::open(&file, Protection::Read)?;
+        metrics.map_time = start.elapsed();
+
+        FileArco::from_storage(Storage::Mmap(map), 0, false, &mut metrics)
+    }
+
+    fn open(path: &Path,
+            seed: u64,
+            case_insensitive: bool,
+            metrics: &mut OpenMetrics) -> Result<Self> {
+        let start = Instant::now();
+        let map = Mmap::open_path(path, Protection::Read)?;
+        metrics.map_time = start.elapsed();
+
+        FileArco::from_storage(Storage::Mmap(map), seed, case_insensitive, metrics)
+    }
+
+    /// This method works like `new` but falls back to reading the whole
+    /// archive into memory if the file cannot be mapped (e.g. on some
+    /// network filesystems). `backend()` reports which one was used.
+    ///
+    /// # Arguments
+    ///
+    /// * path - file path of archive file
     ///
     /// # Example
     ///
@@ -353,440 +364,10197 @@ impl FileRef {
     /// use std::path::Path;
     ///
     /// let path = Path::new("testarchives/simple_v1.fac");
-    /// let file_data = filearco::v1::FileArco::new(&path).unwrap(); 
-    /// 
-    /// let license = file_data.get("LICENSE-APACHE").unwrap();
-    /// let license_text = license.as_str().ok().unwrap();
-    /// println!("{}", license_text);
+    /// let archive = filearco::v1::FileArco::open_best(path).ok().unwrap(); 
+    /// println!("{:?}", archive.backend());
     /// ```
-    pub fn as_str(&self) -> Result<&str> {
-        let sl = unsafe {
-            slice::from_raw_parts(self.address, self.length as usize)
-        };
+    pub fn open_best<P: AsRef<Path>>(path: P) -> Result<Self> {
+        FileArco::open_best_with(path.as_ref(), |path| Mmap::open_path(path, Protection::Read))
+    }
 
-        let s = str::from_utf8(sl)?;
+    // The mapping function is a parameter so tests can simulate files that
+    // cannot be mapped.
+    fn open_best_with<F>(path: &Path, map: F) -> Result<Self>
+        where F: FnOnce(&Path) -> io::Result<Mmap> {
+        let mut metrics = OpenMetrics::default();
 
-        Ok(s)
+        let start = Instant::now();
+        let storage = match map(path) {
+            Ok(map) => Storage::Mmap(map),
+            Err(_) => {
+                let mut data = Vec::new();
+                File::open(path)?.read_to_end(&mut data)?;
+                Storage::Bytes(Arc::new(data))
+            },
+        };
+        metrics.map_time = start.elapsed();
+
+        FileArco::from_storage(storage, 0, false, &mut metrics)
     }
 
-    /// This method returns a tuple with a raw pointer to the beginning
-    /// of the file and the page-aligned length of the file.
+    /// This method processes an archive held in memory (e.g. one received
+    /// over the network) instead of mapping a file. It performs the same
+    /// validation as `new`, and the returned `FileRef`s point into `data`.
     ///
-    /// # Unsafety
+    /// # Arguments
     ///
-    /// Callers should not use this method to modify the contents
-    /// of the file. Undefined Behavior will result.
+    /// * data - contents of archive file
     ///
     /// # Example
     ///
-    /// This example uses the `memadvise` crate to advise the operating system
-    /// to load the entire file into physical RAM.
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::fs::File;
+    /// use std::io::Read;
+    /// use std::sync::Arc;
+    ///
+    /// let mut data = Vec::new();
+    /// File::open("testarchives/simple_v1.fac").unwrap().read_to_end(&mut data).unwrap();
+    ///
+    /// let archive = filearco::v1::FileArco::from_bytes(Arc::new(data)).ok().unwrap();
+    /// let cargo_toml = archive.get("Cargo.toml").unwrap();
+    /// ```
+    pub fn from_bytes(data: Arc<Vec<u8>>) -> Result<Self> {
+        FileArco::from_storage(Storage::Bytes(data), 0, false, &mut OpenMetrics::default())
+    }
+
+    /// This method opens an archive without mapping it into memory. Only
+    /// the header and entries table are read up front, and each call to
+    /// `PagedArchive::get()` reads the requested file into an owned
+    /// `FileBuf`. This keeps the address space used by huge archives small.
+    ///
+    /// The tradeoff is latency: every lookup costs a read (and a copy of
+    /// the file contents), whereas `new` serves files straight from the
+    /// page cache. Prefer `new` unless address space is scarce. On Unix,
+    /// concurrent lookups read at their own offsets without taking turns;
+    /// elsewhere they share one file position behind a lock.
+    ///
+    /// # Arguments
+    ///
+    /// * path - file path of archive file
+    ///
+    /// # Example
     ///
     /// ```rust
     /// extern crate filearco;
-    /// extern crate memadvise;
     ///
     /// use std::path::Path;
     ///
     /// let path = Path::new("testarchives/simple_v1.fac");
-    /// let file_data = filearco::v1::FileArco::new(&path).unwrap(); 
-    /// 
-    /// let license = file_data.get("LICENSE-APACHE").unwrap();
-    /// let (ptr, len) = license.as_raw();
-    /// 
-    /// memadvise::advise(ptr, len, memadvise::Advice::WillNeed).ok().unwrap();
+    /// let archive = filearco::v1::FileArco::open_paged(path).ok().unwrap(); 
+    ///
+    /// let cargo_toml = archive.get("Cargo.toml").ok().unwrap().unwrap();
+    /// assert!(cargo_toml.is_valid());
     /// ```
-    pub fn as_raw(&self) -> (*mut (), usize) {
-        (self.address as *mut (), self.aligned_length as usize)
+    pub fn open_paged<P: AsRef<Path>>(path: P) -> Result<PagedArchive> {
+        FileArco::open_paged_with_pool(path, 0)
     }
 
-    /// This method retrieves the length of the file.
+    /// This method works like `open_paged` but keeps up to `buffers`
+    /// buffers released by earlier reads, so `PagedArchive::get()` can reuse
+    /// them instead of allocating new ones. A `FileBuf` returns its buffer
+    /// to the pool when it is dropped. This helps servers that read many
+    /// files concurrently. Pooled buffers keep their capacity, so the pool
+    /// can hold on to up to `buffers` times the largest file read.
+    ///
+    /// # Arguments
+    ///
+    /// * path - file path of archive file
+    ///
+    /// * buffers - maximum number of buffers kept for reuse
     ///
     /// # Example
     ///
     /// ```rust
     /// extern crate filearco;
     ///
-    /// use std::mem;
     /// use std::path::Path;
     ///
     /// let path = Path::new("testarchives/simple_v1.fac");
-    /// let file_data = filearco::v1::FileArco::new(path).unwrap(); 
-    /// 
-    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
-    /// println!("File length: {}", cargo_toml.len());
+    /// let archive = filearco::v1::FileArco::open_paged_with_pool(path, 16).ok().unwrap();
+    ///
+    /// let cargo_toml = archive.get("Cargo.toml").ok().unwrap().unwrap();
+    /// assert!(cargo_toml.is_valid());
     /// ```
-    pub fn len(&self) -> u64 {
-        self.length
+    pub fn open_paged_with_pool<P: AsRef<Path>>(path: P, buffers: usize) -> Result<PagedArchive> {
+        let mut file = File::open(path)?;
+        let archive_length = file.seek(SeekFrom::End(0))?;
+
+        let mut metrics = OpenMetrics::default();
+        let (header, entries) = read_index_with(archive_length, 0, &mut metrics, |offset, length| {
+            let mut buffer = vec![0u8; length];
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut buffer)?;
+
+            Ok(Cow::Owned(buffer))
+        })?;
+        check_addressable(&entries, header.file_offset, usize::MAX as u64)?;
+        check_in_bounds(&entries, header.file_offset, archive_length)?;
+
+        Ok(PagedArchive {
+            file: PagedFile::new(file),
+            file_offset: header.file_offset,
+            revision: header.revision(),
+            version_number: header.version_number,
+            compressed: header.flags() & FLAG_COMPRESSED != 0,
+            checksum_kind: ChecksumKind::from_flags(header.flags()),
+            entries: entries,
+            pool: Arc::new(BufferPool::new(buffers)),
+        })
     }
-}
 
-/// Error container for handling FileArco v1 archives
-#[derive(Debug)]
-pub enum FileArcoV1Error {
-    /// Entry table's computed checksum did not match the one stored in the file.
-    CorruptedEntriesTable,
-    /// Header's computed checksum did not match the one stored in the file.
-    CorruptedHeader,
-    /// File is too small for the header of a FileArco v1 archive.
-    FileTooSmall,
-    /// File is a valid FileArco v1 archive but it has been truncated.
-    FileTruncated,
-    /// File does not have a valid identifier.
-    NotArchive,
-    /// File has a valid identifier but an incorrect version number.
-    NotV1Archive,
-    /// Something weird happened.
-    Other,
-}
+    fn from_storage(storage: Storage,
+                    seed: u64,
+                    case_insensitive: bool,
+                    metrics: &mut OpenMetrics) -> Result<Self> {
+        let (header, entries) = read_index(storage.as_slice(), seed, metrics)?;
+        check_addressable(&entries, header.file_offset, usize::MAX as u64)?;
+        check_in_bounds(&entries, header.file_offset, storage.len() as u64)?;
 
-impl fmt::Display for FileArcoV1Error {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            FileArcoV1Error::CorruptedEntriesTable => {
-                write!(fmt, "Corrupted entries table")
-            },
-            FileArcoV1Error::CorruptedHeader => {
-                write!(fmt, "Corrupted header")
-            },
-            FileArcoV1Error::FileTooSmall => {
-                write!(fmt, "File either too small for FileArco v1 archive or truncated")
-            },
-            FileArcoV1Error::FileTruncated => {
-                write!(fmt, "File truncated")
-            },
-            FileArcoV1Error::NotArchive => {
-                write!(fmt, "Not FileArco archive")
-            },
-            FileArcoV1Error::NotV1Archive => {
-                write!(fmt, "Not FileArco v1 archive")
+        Ok(FileArco {
+            inner: Arc::new(Inner {
+                file_offset: header.file_offset,
+                page_size: header.page_size,
+                revision: header.revision(),
+                version_number: header.version_number,
+                file_length: header.file_length,
+                entries_length: header.entries_length,
+                entries_checksum: header.entries_checksum,
+                compressed: header.flags() & FLAG_COMPRESSED != 0,
+                checksum_kind: ChecksumKind::from_flags(header.flags()),
+                seed: seed,
+                raw_names: entries.raw_names(),
+                lowercase_names: if case_insensitive {
+                    Some(entries.lowercase_names())
+                }
+                else {
+                    None
+                },
+                decompress_buffer_size: COPY_BUFFER_SIZE,
+                decompress_buffers: BufferPool::new(DECOMPRESS_BUFFERS),
+                entries: entries,
+                storage: storage,
+                #[cfg(test)]
+                lookups: AtomicUsize::new(0),
+            })
+        })
+    }
+
+    /// This method closes the archive, unmapping it immediately. If any
+    /// `FileRef`, `ArchiveHandle` or `RerootedArchive` created from the
+    /// archive is still alive, it gives the archive back along with the
+    /// number of outstanding references, like `Arc::try_unwrap`. The
+    /// archive can then be used as before, or closed again later. Dropping
+    /// it releases the mapping when the last reference is dropped, as usual.
+    ///
+    /// Files are closed as soon as they are mapped, so releasing the mapping
+    /// releases everything the archive holds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// {
+    ///     let cargo_toml = archive.get("Cargo.toml").unwrap();
+    ///     assert!(cargo_toml.is_valid());
+    /// }
+    ///
+    /// archive.try_close().ok().unwrap();
+    /// ```
+    pub fn try_close(self) -> ::std::result::Result<(), (FileArco, usize)> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => {
+                drop(inner);
+                Ok(())
             },
-            FileArcoV1Error::Other => {
-                write!(fmt, "Something weird happened")
+            Err(inner) => {
+                let count = Arc::strong_count(&inner) - 1;
+                Err((FileArco { inner: inner }, count))
             },
         }
     }
-}
 
-impl error::Error for FileArcoV1Error {
-    fn description(&self) -> &str {
-        static CORRUPTED_ENTRIES_TABLE: &'static str = "Corrupted entries table";
-        static CORRUPTED_HEADER: &'static str = "Corrupted header";
-        static FILE_TOO_SMALL: &'static str = "File either too small for FileArco v1 archive or truncated";
-        static FILE_TRUNCATED: &'static str = "File truncated";
-        static NOT_ARCHIVE: &'static str = "Not FileArco archive";
-        static NOT_V1_ARCHIVE: &'static str = "Not FileArco v1 archive";
-        static OTHER: &'static str = "Something weird happened";
+    /// This method retrieves a file from the archive, if it exists. Entry
+    /// names always use `/` as the path separator, but `\\` is accepted too,
+    /// so archives can be shared between platforms.
+    ///
+    /// # Arguments
+    ///
+    /// * file_path - name of file to retrieve
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
+    /// ```
+    pub fn get<P: AsRef<str>>(&self, file_path: P) -> Option<FileRef> {
+        let file_path = normalize_separators(file_path.as_ref());
 
-        match *self {
-            FileArcoV1Error::CorruptedEntriesTable => {
-                CORRUPTED_ENTRIES_TABLE
-            },
-            FileArcoV1Error::CorruptedHeader => {
-                CORRUPTED_HEADER
-            },
-            FileArcoV1Error::FileTooSmall => {
-                FILE_TOO_SMALL
-            },
-            FileArcoV1Error::FileTruncated => {
-                FILE_TRUNCATED
+        #[cfg(test)]
+        self.inner.lookups.fetch_add(1, Ordering::Relaxed);
+
+        self.inner.entries.files.get(file_path.as_ref())
+            .map(|entry| self.file_ref(entry))
+    }
+
+    /// This method retrieves several files from the archive at once. The
+    /// results are in the same order as `names`, and hold `None` for files
+    /// that do not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * names - names of files to retrieve
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// for (name, fileref) in archive.get_many(&["Cargo.toml", "missing.txt"]) {
+    ///     println!("{}: {}", name, fileref.is_some());
+    /// }
+    /// ```
+    pub fn get_many<'a>(&self, names: &[&'a str]) -> Vec<(&'a str, Option<FileRef>)> {
+        names.iter()
+            .map(|&name| (name, self.get(name)))
+            .collect()
+    }
+
+    // This method creates a `FileRef` for an entry of this archive. Callers
+    // that already hold the entry should use this instead of `get()` to
+    // avoid looking it up again.
+    fn file_ref(&self, entry: &Entry) -> FileRef {
+        // Inline contents are owned by `inner`, like the storage.
+        let address = self.inner.stored_bytes(entry).as_ptr();
+
+        FileRef {
+            address: address,
+            compressed: self.inner.is_compressed(entry),
+            inflated: Inflated::new(),
+            length: entry.length,
+            stored_length: entry.stored_length,
+            aligned_length: entry.aligned_length,
+            checksum: entry.checksum,
+            mode: self.inner.mode_of(entry),
+            modified: entry.modified,
+            content_type: entry.content_type.clone(),
+            digest: entry.digest,
+            validated: AtomicU8::new(UNCHECKED),
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// This method retrieves a file from the archive, ignoring the case of
+    /// its name. This helps on case-insensitive filesystems, where a file
+    /// stored as `Cargo.toml` may be requested as `cargo.toml`.
+    ///
+    /// If several names differ only in case, which one is retrieved is
+    /// unspecified but always the same for a given archive. Lookups take
+    /// linear time unless the archive was opened with
+    /// `new_case_insensitive()`.
+    ///
+    /// # Arguments
+    ///
+    /// * file_path - name of file to retrieve, in any case
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get_ci("cargo.toml").unwrap();
+    /// ```
+    pub fn get_ci<P: AsRef<str>>(&self, file_path: P) -> Option<FileRef> {
+        let lowercase = normalize_separators(file_path.as_ref()).to_lowercase();
+
+        let name = match self.inner.lowercase_names {
+            Some(ref lowercase_names) => lowercase_names.get(&lowercase).cloned(),
+            None => {
+                self.inner.entries.files.keys()
+                    .filter(|name| name.to_lowercase() == lowercase)
+                    .min()
+                    .cloned()
             },
-            FileArcoV1Error::NotArchive => {
-                NOT_ARCHIVE
+        };
+
+        match name {
+            Some(name) => self.get(name),
+            None => None,
+        }
+    }
+
+    /// This method retrieves a file from the archive by the exact bytes of
+    /// its name, if it exists. Unlike `get()`, this can retrieve files whose
+    /// names are not valid UTF-8 (which `get()` only knows by their lossy
+    /// names).
+    ///
+    /// # Arguments
+    ///
+    /// * file_path - exact bytes of the name of the file to retrieve
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get_bytes(b"Cargo.toml").unwrap();
+    /// ```
+    pub fn get_bytes(&self, file_path: &[u8]) -> Option<FileRef> {
+        match str::from_utf8(file_path) {
+            Ok(name) => {
+                // Do not match a lossy name with its replacement characters.
+                match self.inner.entries.files.get(name) {
+                    Some(entry) if entry.raw_name.is_none() => self.get(name),
+                    _ => None,
+                }
             },
-            FileArcoV1Error::NotV1Archive => {
-                NOT_V1_ARCHIVE
+            Err(_) => {
+                match self.inner.raw_names.get(file_path) {
+                    Some(name) => self.get(name),
+                    None => None,
+                }
             },
-            FileArcoV1Error::Other => {
-                OTHER
+        }
+    }
+
+    /// This method returns a handle to the archive that can be cloned
+    /// cheaply and moved into other threads or tasks. Every clone shares the
+    /// same mapping, which stays open until the archive and all handles and
+    /// `FileRef`s are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    /// use std::thread;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// let handle = archive.handle();
+    ///
+    /// thread::spawn(move || {
+    ///     let cargo_toml = handle.get("Cargo.toml").unwrap();
+    ///     assert!(cargo_toml.is_valid());
+    /// }).join().unwrap();
+    /// ```
+    pub fn handle(&self) -> ArchiveHandle {
+        ArchiveHandle {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// This method checks whether a file is stored in the archive without
+    /// creating a `FileRef`. It only performs a lookup in the entries table.
+    ///
+    /// # Arguments
+    ///
+    /// * file_path - name of file to look for
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// assert!(archive.contains("Cargo.toml"));
+    /// ```
+    pub fn contains<P: AsRef<str>>(&self, file_path: P) -> bool {
+        let file_path = normalize_separators(file_path.as_ref());
+
+        self.inner.entries.files.contains_key(file_path.as_ref())
+    }
+
+    /// This method returns the metadata of a file stored in the archive
+    /// without creating a `FileRef`, so the mapping is never touched. The
+    /// offset of a file is relative to `file_offset()`, so its contents
+    /// start at `file_offset() + offset()` bytes into the archive.
+    ///
+    /// # Arguments
+    ///
+    /// * file_path - name of file to look up
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// let info = archive.entry_info("Cargo.toml").unwrap();
+    /// println!("{} bytes at {}", info.len(), archive.file_offset() + info.offset());
+    /// ```
+    pub fn entry_info<P: AsRef<str>>(&self, file_path: P) -> Option<EntryInfo> {
+        let file_path = normalize_separators(file_path.as_ref());
+
+        self.inner.entries.files.get(file_path.as_ref()).map(EntryInfo::from)
+    }
+
+    /// This method returns the number of files stored in the archive.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// assert_eq!(archive.file_count(), 3);
+    /// ```
+    pub fn file_count(&self) -> usize {
+        self.inner.entries.files.len()
+    }
+
+    /// This method concatenates the contents of the files `names` (in order)
+    /// into a single buffer, e.g. to serve many small files as one response.
+    /// It also returns the name, offset in the buffer, and length of each
+    /// file. It fails with `io::ErrorKind::NotFound` if a file is not in the
+    /// archive.
+    ///
+    /// # Arguments
+    ///
+    /// * names - names of the files to concatenate
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// let (body, index) = archive.pack_response(&["LICENSE-MIT", "Cargo.toml"]).ok().unwrap();
+    /// for (name, offset, length) in index {
+    ///     println!("{}: {} bytes at {}", name, length, offset);
+    /// }
+    /// ```
+    pub fn pack_response<P: AsRef<str>>(&self,
+                                        names: &[P])
+                                        -> Result<(Vec<u8>, PackIndex)> {
+        let mut body = Vec::<u8>::new();
+        let mut index = Vec::with_capacity(names.len());
+
+        for name in names.iter() {
+            let name = name.as_ref();
+            let fileref = match self.get(name) {
+                Some(fileref) => fileref,
+                None => {
+                    return Err(Error::Io(io::Error::new(io::ErrorKind::NotFound,
+                                                        format!("{} not in archive", name))));
+                },
+            };
+
+            index.push((name.to_string(), body.len() as u64, fileref.len()));
+            body.extend_from_slice(fileref.as_slice());
+        }
+
+        Ok((body, index))
+    }
+
+    /// This method checks that every name in the archive is canonical: it is
+    /// not empty, does not start with `/`, has no `.` or `..` components,
+    /// and contains no NUL bytes. It also checks that no two names are the
+    /// same once empty components (e.g. from `a//b` or a trailing `/`) are
+    /// removed. It returns `FileArcoV1Error::InvalidName` with the first
+    /// offending name (in sorted order).
+    ///
+    /// Archives from untrusted sources should be checked with this before
+    /// their names are used to address the filesystem.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// assert!(archive.validate_names().is_ok());
+    /// ```
+    pub fn validate_names(&self) -> Result<()> {
+        let mut names = self.inner.entries.files.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let mut normalized_names = HashMap::<String, &String>::new();
+
+        for name in names.into_iter() {
+            let components = name.split('/').collect::<Vec<_>>();
+
+            let canonical = !name.is_empty() &&
+                !name.starts_with('/') &&
+                !name.contains('\0') &&
+                components.iter().all(|&c| c != "." && c != "..");
+
+            let normalized = components.into_iter()
+                .filter(|c| !c.is_empty())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if !canonical || normalized_names.insert(normalized, name).is_some() {
+                return Err(Error::FileArcoV1(FileArcoV1Error::InvalidName(name.clone())));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// This method returns the name of every file in the archive, sorted by
+    /// where its contents are stored (files at the same offset are sorted by
+    /// name). Reading files in this order touches the mapping sequentially.
+    /// Only the entries table is read.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// for name in archive.names_by_offset() {
+    ///     println!("{}", name);
+    /// }
+    /// ```
+    pub fn names_by_offset(&self) -> Vec<&str> {
+        let mut files = self.inner.entries.files.iter()
+            .map(|(name, entry)| (entry.offset, name.as_str()))
+            .collect::<Vec<_>>();
+        files.sort();
+
+        files.into_iter().map(|(_, name)| name).collect()
+    }
+
+    /// This method returns the name of every file in the archive (sorted)
+    /// as an `OsString`. On Unix, names that are not valid UTF-8 are
+    /// returned exactly as they were stored, so they can be used to address
+    /// the filesystem. Elsewhere, such names are returned in their lossy
+    /// form.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// for name in archive.names_os() {
+    ///     println!("{:?}", name);
+    /// }
+    /// ```
+    pub fn names_os(&self) -> Vec<OsString> {
+        let mut names = self.inner.entries.files.iter()
+            .map(|(name, entry)| entry_path(name, entry).as_os_str().to_os_string())
+            .collect::<Vec<_>>();
+        names.sort();
+
+        names
+    }
+
+    /// This method returns the number of bytes of file contents actually
+    /// stored in the archive, counting regions shared by identical files
+    /// only once. Comparing it with the sum of `EntryInfo::aligned_len()`
+    /// over all entries shows how much deduplication saved.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// println!("{} bytes of file contents", archive.unique_payload_bytes());
+    /// ```
+    pub fn unique_payload_bytes(&self) -> u64 {
+        // Identical files share the same offset and aligned length. Empty
+        // files may share the offset of a different file, so the region is
+        // identified by both.
+        let regions = self.inner.entries.files.values()
+            .map(|entry| (entry.offset, entry.aligned_length))
+            .collect::<HashSet<_>>();
+
+        regions.into_iter().map(|(_, aligned_length)| aligned_length).sum()
+    }
+
+    /// This method returns a checksum of the name and checksum of every file
+    /// in the archive. Unlike `entries_checksum()`, it does not depend on
+    /// the order, offsets, padding, or compression of the files, so archives
+    /// with the same files have the same fingerprint even if they were made
+    /// with different options.
+    ///
+    /// **NOTE:** File checksums depend on the seed and checksum kind the
+    /// archive was made with, and archives made without checksums only
+    /// fingerprint their names.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// println!("{:#018x}", archive.content_fingerprint());
+    /// ```
+    pub fn content_fingerprint(&self) -> u64 {
+        let mut files = self.inner.entries.files.iter().collect::<Vec<_>>();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+
+        // Names are prefixed with their length so the concatenation of
+        // every name and checksum cannot be ambiguous.
+        let mut bytes = Vec::<u8>::new();
+
+        for (name, entry) in files.into_iter() {
+            bytes.extend_from_slice(&(name.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.extend_from_slice(&entry.checksum.to_le_bytes());
+        }
+
+        seeded_checksum(0, &bytes)
+    }
+
+    /// This method validates every file in the archive and returns the name
+    /// of each file (sorted by name) along with whether it is valid. Unlike
+    /// `FileRef::is_valid()`, it does not stop at the first corrupted file.
+    /// Files without a checksum cannot be validated and are left out.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// for (name, valid) in archive.verify_all() {
+    ///     if !valid {
+    ///         println!("{} is corrupted", name);
+    ///     }
+    /// }
+    /// ```
+    pub fn verify_all(&self) -> Vec<(String, bool)> {
+        let mut files = self.inner.entries.files.iter().collect::<Vec<_>>();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+
+        files.into_iter()
+            .map(|(name, entry)| (name, self.file_ref(entry)))
+            .filter(|&(_, ref fileref)| fileref.has_checksum())
+            .map(|(name, fileref)| (name.clone(), fileref.is_valid()))
+            .collect()
+    }
+
+    /// This method writes a manifest listing the SHA-256 digest and name of
+    /// every file (sorted by name) to `w`, in the format of `sha256sum`.
+    /// It can be distributed alongside the archive and checked with
+    /// `verify_against_manifest()`, or with `sha256sum -c` once the archive
+    /// is extracted. The digests are computed from the file contents, so
+    /// they do not depend on the archive's `ChecksumKind`.
+    ///
+    /// # Arguments
+    ///
+    /// * w - destination of manifest
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// archive.write_checksum_manifest(&mut io::stdout()).ok().unwrap();
+    /// ```
+    pub fn write_checksum_manifest<W: Write>(&self, w: &mut W) -> Result<()> {
+        let mut files = self.inner.entries.files.iter().collect::<Vec<_>>();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (name, entry) in files.into_iter() {
+            let mut digest = [0u8; DIGEST_SIZE];
+            digest.copy_from_slice(&Sha256::digest(self.file_ref(entry).as_slice()));
+
+            w.write_all(manifest_line(name, &digest).as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// This method checks the files in this archive against a manifest
+    /// written by `write_checksum_manifest()` (or `sha256sum`). Blank lines
+    /// are skipped, and any other line that is not a digest followed by a
+    /// name gives `FileArcoV1Error::InvalidManifest`. Files in the archive
+    /// but not in the manifest are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * r - source of manifest
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// let mut manifest = Vec::new();
+    /// archive.write_checksum_manifest(&mut manifest).ok().unwrap();
+    ///
+    /// let report = archive.verify_against_manifest(&manifest[..]).ok().unwrap();
+    /// assert!(report.is_ok());
+    /// ```
+    pub fn verify_against_manifest<R: Read>(&self, r: R) -> Result<VerifyReport> {
+        let mut report = VerifyReport::default();
+
+        for line in BufReader::new(r).lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (digest, name) = match parse_manifest_line(&line) {
+                Some(parsed) => parsed,
+                None => {
+                    return Err(Error::FileArcoV1(FileArcoV1Error::InvalidManifest(line)));
+                },
+            };
+
+            match self.get(&name) {
+                Some(fileref) => {
+                    if Sha256::digest(fileref.as_slice())[..] == digest[..] {
+                        report.valid.push(name);
+                    }
+                    else {
+                        report.corrupted.push(name);
+                    }
+                },
+                None => report.missing.push(name),
+            }
+        }
+
+        report.valid.sort();
+        report.corrupted.sort();
+        report.missing.sort();
+
+        Ok(report)
+    }
+
+    /// This method returns the name and version of the tool that created
+    /// the archive, if it was recorded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// if let Some(producer) = archive.producer() {
+    ///     println!("Created by {}", producer);
+    /// }
+    /// ```
+    pub fn producer(&self) -> Option<&str> {
+        self.inner.entries.producer.as_ref().map(|producer| producer.as_str())
+    }
+
+    /// This method returns an iterator over the name and metadata of every
+    /// file in the archive, without reading any file contents.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// for (name, info) in archive.entries() {
+    ///     println!("{}: {} bytes", name, info.len());
+    /// }
+    /// ```
+    pub fn entries<'a>(&'a self) -> impl Iterator<Item = (&'a str, EntryInfo)> + 'a {
+        self.inner.entries.files.iter().map(|(name, entry)| {
+            (name.as_str(), EntryInfo::from(entry))
+        })
+    }
+
+    /// This method returns the files in the archive as a tree of
+    /// directories, built by splitting every name on `/`. This is handy for
+    /// rendering the archive like a file explorer would.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// let tree = archive.tree();
+    /// for (name, length) in tree.files() {
+    ///     println!("{}: {} bytes", name, length);
+    /// }
+    /// for (name, _) in tree.directories() {
+    ///     println!("{}/", name);
+    /// }
+    /// ```
+    pub fn tree(&self) -> DirNode {
+        let mut root = DirNode::default();
+
+        for (name, entry) in self.inner.entries.files.iter() {
+            let mut components = name.split('/')
+                .filter(|c| !c.is_empty())
+                .collect::<Vec<_>>();
+            let file_name = match components.pop() {
+                Some(file_name) => file_name,
+                None => continue,
+            };
+
+            let node = components.into_iter().fold(&mut root, |node, component| {
+                node.directories.entry(component.to_string()).or_default()
+            });
+            node.files.insert(file_name.to_string(), entry.length);
+        }
+
+        root
+    }
+
+    /// This method returns an iterator over the name and contents of every
+    /// file in the archive. It is faster than calling `get()` with every
+    /// name, since no file is looked up by name.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// for (name, fileref) in archive.iter() {
+    ///     println!("{}: valid: {}", name, fileref.is_valid());
+    /// }
+    /// ```
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a str, FileRef)> + 'a {
+        self.inner.entries.files.iter().map(move |(name, entry)| {
+            (name.as_str(), self.file_ref(entry))
+        })
+    }
+
+    /// This method returns an iterator over the name, stored bytes, and
+    /// whether those bytes are compressed for every file in the archive.
+    /// The bytes are returned exactly as stored, which is useful for copying
+    /// files verbatim into another archive.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// for (name, bytes, compressed) in archive.raw_iter() {
+    ///     println!("{}: {} bytes (compressed: {})", name, bytes.len(), compressed);
+    /// }
+    /// ```
+    pub fn raw_iter<'a>(&'a self) -> impl Iterator<Item = (&'a str, &'a [u8], bool)> + 'a {
+        let inner = &*self.inner;
+
+        inner.entries.files.iter().map(move |(name, entry)| {
+            (name.as_str(), inner.stored_bytes(entry), inner.is_compressed(entry))
+        })
+    }
+
+    /// This method writes the name, length, aligned length, offset, and
+    /// checksum of every file in the archive as CSV (RFC 4180), preceded
+    /// by a header row. Rows are sorted by name.
+    ///
+    /// **NOTE:** Offsets are relative to the start of the file contents
+    /// section, not the start of the archive.
+    ///
+    /// # Arguments
+    ///
+    /// * w - destination of the CSV
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// archive.write_csv(&mut io::stdout()).ok().unwrap();
+    /// ```
+    pub fn write_csv<W: Write>(&self, w: &mut W) -> Result<()> {
+        let mut files = self.inner.entries.files.iter().collect::<Vec<_>>();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+
+        write!(w, "name,length,aligned_length,offset,checksum\r\n")?;
+
+        for (name, entry) in files.into_iter() {
+            write!(w, "{},{},{},{},{}\r\n",
+                   escape_csv(name),
+                   entry.length,
+                   entry.aligned_length,
+                   entry.offset,
+                   entry.checksum)?;
+        }
+
+        Ok(())
+    }
+
+    /// This method returns the raw version number stored in the header. The
+    /// low 32 bits hold the major version (1), bits 32 to 47 hold the format
+    /// revision, and the high 16 bits hold flags for optional features.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// println!("{}", file_data.version_number());
+    /// ```
+    pub fn version_number(&self) -> u64 {
+        self.inner.version_number
+    }
+
+    /// This method returns the format revision of the entries table.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// println!("{}", file_data.revision());
+    /// ```
+    pub fn revision(&self) -> u64 {
+        self.inner.revision
+    }
+
+    /// This method returns the length of the archive (in bytes) as recorded
+    /// in the header. Archives using the trailer layout record only the length
+    /// of the file contents section.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// println!("{}", file_data.file_length());
+    /// ```
+    pub fn file_length(&self) -> u64 {
+        self.inner.file_length
+    }
+
+    /// This method checks the signature block written by
+    /// `FileArco::make_signed()` against `key`. It returns `false` if the
+    /// archive is not signed, was signed with another key, or was modified
+    /// after it was signed.
+    ///
+    /// # Arguments
+    ///
+    /// * key - secret key the archive was signed with
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// assert!(!archive.verify_signature(b"secret"));
+    /// ```
+    pub fn verify_signature(&self, key: &[u8]) -> bool {
+        let bytes = self.inner.storage.as_slice();
+        let signed_length = self.inner.file_length as usize;
+
+        if bytes.len() != signed_length + SIGNATURE_SIZE {
+            return false;
+        }
+
+        let (signed, signature) = bytes.split_at(signed_length);
+        if &signature[..SIGNATURE_ID.len()] != SIGNATURE_ID {
+            return false;
+        }
+
+        let mut mac = new_mac(key);
+        mac.update(signed);
+        mac.verify_slice(&signature[SIGNATURE_ID.len()..]).is_ok()
+    }
+
+    /// This method returns the offset of the file contents section from the
+    /// start of the archive.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// println!("{}", file_data.file_offset());
+    /// ```
+    pub fn file_offset(&self) -> u64 {
+        self.inner.file_offset
+    }
+
+    /// This method returns the length (in bytes) of the entries table.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// println!("{}", file_data.entries_length());
+    /// ```
+    pub fn entries_length(&self) -> u64 {
+        self.inner.entries_length
+    }
+
+    /// This method returns the checksum of the entries table stored in the
+    /// header. It has already been validated when the archive was opened.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// println!("{}", file_data.entries_checksum());
+    /// ```
+    pub fn entries_checksum(&self) -> u64 {
+        self.inner.entries_checksum
+    }
+
+    /// This method returns how the contents of the archive are accessed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// assert_eq!(file_data.backend(), filearco::v1::Backend::Mmap);
+    /// ```
+    pub fn backend(&self) -> Backend {
+        match self.inner.storage {
+            Storage::Mmap(_) => Backend::Mmap,
+            Storage::Bytes(_) => Backend::Buffered,
+        }
+    }
+
+    /// This method returns the memory page size of the system used to create
+    /// the archive file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// println!("{}", file_data.page_size());
+    /// ```
+    pub fn page_size(&self) -> u64 {
+        self.inner.page_size
+    }
+
+    /// This method compares the page size the archive was made with against
+    /// the memory page size of this system, to find archives built on a host
+    /// with smaller pages. Files in such archives are not page aligned here,
+    /// so `FileRef::as_raw()` and the prefetch methods cover parts of
+    /// neighbouring files.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// let report = archive.alignment_report();
+    /// if !report.is_aligned() {
+    ///     println!("{} byte pages, but this system uses {} byte pages",
+    ///              report.archive_page_size(), report.host_page_size());
+    /// }
+    /// ```
+    pub fn alignment_report(&self) -> AlignmentReport {
+        self.alignment_report_for(get_page_size() as u64)
+    }
+
+    // This method works like `alignment_report` for a system with pages of
+    // `host_page_size` bytes.
+    fn alignment_report_for(&self, host_page_size: u64) -> AlignmentReport {
+        AlignmentReport {
+            archive_page_size: self.inner.page_size,
+            host_page_size: host_page_size,
+            backend: self.backend(),
+        }
+    }
+    
+    /// This method returns how many bytes of the archive are currently
+    /// resident in physical memory (i.e. in the page cache), rounded to whole
+    /// memory pages. It returns `None` on platforms that cannot tell.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// if let Some(resident) = archive.resident_len() {
+    ///     println!("{} bytes resident", resident);
+    /// }
+    /// ```
+    pub fn resident_len(&self) -> Option<usize> {
+        match self.inner.storage {
+            Storage::Mmap(ref map) => resident_len(map.ptr(), map.len()),
+            Storage::Bytes(ref data) => Some(data.len()),
+        }
+    }
+
+    /// This method checks whether every page of the archive is currently
+    /// resident in physical memory. It returns `None` on platforms that
+    /// cannot tell.
+    ///
+    /// **NOTE:** The answer may be stale as soon as it is returned, since
+    /// the operating system can evict pages at any time.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// if archive.is_fully_resident() == Some(false) {
+    ///     println!("Archive is not fully cached");
+    /// }
+    /// ```
+    pub fn is_fully_resident(&self) -> Option<bool> {
+        if let Storage::Bytes(_) = self.inner.storage {
+            return Some(true);
+        }
+
+        let length = self.inner.storage.len();
+        let page_size = get_page_size();
+        let mapped_length = (length + (page_size - 1)) & !(page_size - 1);
+
+        self.resident_len().map(|resident| resident >= mapped_length)
+    }
+
+    /// This method advises the operating system that the named files will be
+    /// needed soon, so it can start loading them into physical memory.
+    /// Adjacent files are coalesced so the fewest possible `madvise()` calls
+    /// are issued. Names not in the archive are ignored.
+    ///
+    /// **NOTE:** This does nothing unless the `prefetch` feature is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * names - names of files to prefetch
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// archive.prefetch_many(&["Cargo.toml", "LICENSE-MIT"]).ok().unwrap();
+    /// ```
+    pub fn prefetch_many<P: AsRef<str>>(&self, names: &[P]) -> Result<()> {
+        // Archives held in memory are already loaded.
+        if let Storage::Bytes(_) = self.inner.storage {
+            return Ok(());
+        }
+
+        let ptr = self.inner.storage.ptr();
+
+        self.prefetch_many_with(names, |offset, length| {
+            advise_will_need(unsafe { ptr.offset(offset as isize) }, length)
+        })
+    }
+
+    /// This method advises the operating system that the whole archive will
+    /// be needed soon, so it can start loading it into physical memory. It
+    /// issues a single `madvise()` call, which is cheaper than prefetching
+    /// every file when all of them will be used.
+    ///
+    /// **NOTE:** This does nothing unless the `prefetch` feature is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// archive.prefetch_all().ok().unwrap();
+    /// ```
+    pub fn prefetch_all(&self) -> Result<()> {
+        // Archives held in memory are already loaded.
+        if let Storage::Bytes(_) = self.inner.storage {
+            return Ok(());
+        }
+
+        Ok(advise_will_need(self.inner.storage.ptr(), self.inner.storage.len())?)
+    }
+
+    /// This method advises the operating system that the archive will not
+    /// be needed for a while, so it can reclaim the physical memory holding
+    /// it. The archive can still be used afterwards; its pages are read
+    /// from disk again when accessed.
+    ///
+    /// **NOTE:** This does nothing unless the `prefetch` feature is enabled,
+    /// or for archives opened with `FileArco::from_bytes`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// archive.release().ok().unwrap();
+    /// ```
+    pub fn release(&self) -> Result<()> {
+        if let Storage::Bytes(_) = self.inner.storage {
+            return Ok(());
+        }
+
+        Ok(advise_dont_need(self.inner.storage.ptr(), self.inner.storage.len())?)
+    }
+
+    // This method calls `advise` once per coalesced page range (given as an
+    // offset into the mapping and a length) covering the named files.
+    fn prefetch_many_with<P, F>(&self, names: &[P], mut advise: F) -> Result<()>
+        where P: AsRef<str>,
+              F: FnMut(usize, usize) -> io::Result<()> {
+        let ranges = self.page_ranges(names);
+        let mut coalesced: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+
+        for (start, end) in ranges.into_iter() {
+            if let Some(last) = coalesced.last_mut() {
+                if start <= last.1 {
+                    last.1 = cmp::max(last.1, end);
+                    continue;
+                }
+            }
+
+            coalesced.push((start, end));
+        }
+
+        for (start, end) in coalesced.into_iter() {
+            advise(start, end - start)?;
+        }
+
+        Ok(())
+    }
+
+    /// This method returns the indices of the memory pages (of this system,
+    /// counted from the start of the archive) covered by the named files.
+    /// Pages shared by several files are only listed once, so a prefetch
+    /// scheduler can compare the result with the pages it has already
+    /// warmed. Names not in the archive are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * names - names of files
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// let pages = archive.pages_for_names(&["Cargo.toml", "LICENSE-MIT"]);
+    /// println!("{} pages", pages.len());
+    /// ```
+    pub fn pages_for_names<P: AsRef<str>>(&self, names: &[P]) -> BTreeSet<usize> {
+        let page_size = get_page_size();
+
+        self.page_ranges(names).into_iter()
+            .flat_map(|(start, end)| {
+                (start / page_size)..end.div_ceil(page_size)
+            })
+            .collect()
+    }
+
+    // This method returns the sorted ranges of the mapping (given as start
+    // and end offsets aligned to the pages of this system) covering the
+    // named files. Ranges may overlap.
+    fn page_ranges<P: AsRef<str>>(&self, names: &[P]) -> Vec<(usize, usize)> {
+        let page_size = get_page_size();
+        let map_length = self.inner.storage.len();
+
+        let mut ranges = names.iter()
+            .filter_map(|name| {
+                self.inner.entries.files.get(normalize_separators(name.as_ref()).as_ref())
+            })
+            .filter(|entry| entry.length > 0 && entry.inline.is_none())
+            .map(|entry| {
+                let start = (self.inner.file_offset + entry.offset) as usize;
+                let end = start + entry.aligned_length as usize;
+
+                // Align range to the pages of this system.
+                (start & !(page_size - 1),
+                 cmp::min((end + (page_size - 1)) & !(page_size - 1), map_length))
+            })
+            .collect::<Vec<_>>();
+        ranges.sort();
+
+        ranges
+    }
+
+    /// This method writes every file in the archive to `dest`, recreating
+    /// the relative path structure and creating directories as needed. Each
+    /// file is validated before it is written, and on Unix its mode is
+    /// restored if the archive stores one.
+    ///
+    /// # Arguments
+    ///
+    /// * dest - directory to extract files into
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// archive.extract_to("tmptest/doc_extract_to").ok().unwrap();
+    /// ```
+    pub fn extract_to<P: AsRef<Path>>(&self, dest: P) -> Result<()> {
+        self.extract_with(dest.as_ref(), &ExtractOptions::new(), |_| true).map(|_| ())
+    }
+
+    /// This method works like `extract_to` but uses the specified `options`
+    /// to decide whether files are validated and what happens to corrupted
+    /// ones.
+    ///
+    /// It returns the number of files written.
+    ///
+    /// # Arguments
+    ///
+    /// * dest - directory to extract files into
+    ///
+    /// * options - settings such as the policy for corrupted files
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// use filearco::v1::{ExtractOptions, FileArco, OnCorrupt};
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = FileArco::new(path).ok().unwrap(); 
+    /// let options = ExtractOptions::new().on_corrupt(OnCorrupt::Skip);
+    ///
+    /// archive.extract_to_with("tmptest/doc_extract_to_with", &options).ok().unwrap();
+    /// ```
+    pub fn extract_to_with<P: AsRef<Path>>(&self,
+                                           dest: P,
+                                           options: &ExtractOptions) -> Result<usize> {
+        self.extract_with(dest.as_ref(), options, |_| true)
+    }
+
+    /// This method works like `extract_to` but only writes the files whose
+    /// names match the glob `pattern`. Note that `*` also matches `/`, so
+    /// `*.html` selects HTML files in every directory.
+    ///
+    /// It returns the number of files written.
+    ///
+    /// # Arguments
+    ///
+    /// * pattern - glob pattern that names of extracted files must match
+    ///
+    /// * dest - directory to extract files into
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// let count = archive.extract_matching("LICENSE-*", "tmptest/doc_extract_matching")
+    ///     .ok().unwrap();
+    /// assert_eq!(count, 2);
+    /// ```
+    pub fn extract_matching<P, Q>(&self, pattern: P, dest: Q) -> Result<usize>
+        where P: AsRef<str>, Q: AsRef<Path>
+    {
+        let pattern = Pattern::new(pattern.as_ref())?;
+
+        self.extract_with(dest.as_ref(), &ExtractOptions::new(), |name| pattern.matches(name))
+    }
+
+    fn extract_with<F>(&self,
+                       dest: &Path,
+                       options: &ExtractOptions,
+                       mut filter: F) -> Result<usize>
+        where F: FnMut(&str) -> bool
+    {
+        let mut files = self.inner.entries.files.iter()
+            .filter(|&(name, _)| filter(name))
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+        let mut count = 0;
+
+        for (name, entry) in files.into_iter() {
+            let relative_path = entry_path(name, entry);
+
+            // Never write outside of `dest`.
+            if !relative_path.components().all(|c| matches!(c, Component::CurDir |
+                                                                 Component::Normal(_))) {
+                return Err(Error::FileArcoV1(
+                    FileArcoV1Error::UnsafePath(name.clone())
+                ));
+            }
+
+            let fileref = self.file_ref(entry);
+
+            if options.verify && !fileref.is_valid() {
+                match options.on_corrupt {
+                    OnCorrupt::Skip => continue,
+                    OnCorrupt::Abort => {
+                        return Err(Error::FileArcoV1(
+                            FileArcoV1Error::CorruptedFile(name.clone())
+                        ));
+                    },
+                    OnCorrupt::WriteAnyway => {},
+                }
+            }
+
+            let full_path = dest.join(relative_path);
+
+            if let Some(parent) = full_path.parent() {
+                create_dir_all(parent)?;
+            }
+
+            File::create(&full_path)?.write_all(fileref.as_slice())?;
+
+            if let Some(mode) = fileref.mode() {
+                set_mode(&full_path, mode)?;
+            }
+
+            count = count + 1;
+        }
+
+        Ok(count)
+    }
+
+    /// This method writes a single file from the archive to `dest`,
+    /// creating its parent directories if necessary. Unlike `extract_to`,
+    /// `dest` is the path of the file itself, not a directory.
+    ///
+    /// It fails with `io::ErrorKind::NotFound` if `name` is not in the
+    /// archive, and with `FileArcoV1Error::CorruptedFile` (before writing
+    /// anything) if its contents are corrupted.
+    ///
+    /// # Arguments
+    ///
+    /// * name - name of file to extract
+    ///
+    /// * dest - file path to write the file to
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// archive.extract_file("LICENSE-MIT", "tmptest/doc_extract_file/LICENSE-MIT")
+    ///     .ok().unwrap();
+    /// ```
+    pub fn extract_file<P: AsRef<Path>>(&self, name: &str, dest: P) -> Result<()> {
+        let dest = dest.as_ref();
+
+        let fileref = match self.get(name) {
+            Some(fileref) => fileref,
+            None => {
+                return Err(Error::Io(io::Error::new(io::ErrorKind::NotFound,
+                                                    format!("{} not in archive", name))));
+            },
+        };
+
+        if !fileref.is_valid() {
+            return Err(Error::FileArcoV1(FileArcoV1Error::CorruptedFile(String::from(name))));
+        }
+
+        if let Some(parent) = dest.parent() {
+            create_dir_all(parent)?;
+        }
+
+        File::create(dest)?.write_all(fileref.as_slice())?;
+
+        if let Some(mode) = fileref.mode() {
+            set_mode(dest, mode)?;
+        }
+
+        Ok(())
+    }
+
+    /// This method writes a byte-identical copy of the archive to `dest`
+    /// without parsing or recreating anything.
+    ///
+    /// # Arguments
+    ///
+    /// * dest - file path of the copy
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// archive.copy_to("tmptest/doc_copy_to.fac").ok().unwrap();
+    /// ```
+    pub fn copy_to<P: AsRef<Path>>(&self, dest: P) -> Result<()> {
+        self.copy_to_with(dest.as_ref(), false)
+    }
+
+    /// This method works like `copy_to` but also flushes the copy to disk
+    /// before returning.
+    ///
+    /// # Arguments
+    ///
+    /// * dest - file path of the copy
+    pub fn copy_to_synced<P: AsRef<Path>>(&self, dest: P) -> Result<()> {
+        self.copy_to_with(dest.as_ref(), true)
+    }
+
+    fn copy_to_with(&self, dest: &Path, sync: bool) -> Result<()> {
+        let mut out_file = File::create(dest)?;
+        out_file.write_all(self.inner.storage.as_slice())?;
+
+        if sync {
+            out_file.sync_all()?;
+        }
+
+        Ok(())
+    }
+
+    /// This method writes a copy of the archive without the files named in
+    /// `names` to `out_file`. The remaining contents are moved up so no
+    /// space is left where the removed files were. The copy keeps the page
+    /// size, flags, and producer of the archive.
+    ///
+    /// It returns `FileArcoV1Error::EntryNotFound` (before writing
+    /// anything) if a name is not in the archive. Names given more than once
+    /// are only removed once.
+    ///
+    /// # Arguments
+    ///
+    /// * names - names of the files to leave out
+    ///
+    /// * out_file - destination of the new archive
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// archive.remove_to(&["LICENSE-APACHE"], io::sink()).ok().unwrap();
+    /// ```
+    pub fn remove_to<H: Write>(&self, names: &[&str], mut out_file: H) -> Result<()> {
+        let mut entries = self.inner.entries.clone();
+        let names = names.iter()
+            .map(|name| normalize_separators(name))
+            .collect::<BTreeSet<_>>();
+
+        for name in names.into_iter() {
+            if entries.files.remove(name.as_ref()).is_none() {
+                return Err(Error::FileArcoV1(FileArcoV1Error::EntryNotFound(name.into_owned())));
+            }
+        }
+
+        // Files that shared a region still share its new location.
+        let mut files = entries.files.values_mut().collect::<Vec<_>>();
+        files.sort_by_key(|entry| (entry.offset, entry.aligned_length));
+
+        let mut regions = Vec::<(&[u8], u64)>::new();
+        let mut last_region = None;
+        let mut offset = 0;
+
+        for entry in files.into_iter() {
+            // Inline files stay in the entries table.
+            if entry.inline.is_some() {
+                entry.offset = 0;
+                continue;
+            }
+
+            let region = (entry.offset, entry.aligned_length);
+
+            if last_region != Some(region) {
+                regions.push((self.inner.payload_bytes(entry), entry.aligned_length));
+                last_region = Some(region);
+                offset = offset + entry.aligned_length;
+            }
+
+            entry.offset = offset - entry.aligned_length;
+        }
+
+        let flags = self.inner.version_number >> FLAGS_SHIFT;
+        write_prelude(&mut out_file, &entries, flags, self.inner.page_size, self.inner.seed)?;
+
+        for (contents, aligned_length) in regions.into_iter() {
+            out_file.write_all(contents)?;
+            out_file.write_all(&vec![0u8; aligned_length as usize - contents.len()])?;
+        }
+
+        Ok(())
+    }
+
+    /// This method works like `remove_to` but keeps only the files for
+    /// which `pred` returns `true`, given the name and metadata of each.
+    ///
+    /// # Arguments
+    ///
+    /// * pred - returns whether to keep a file
+    ///
+    /// * out_file - destination of the new archive
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// archive.filter_to(|name, _| name.starts_with("LICENSE"), io::sink()).ok().unwrap();
+    /// ```
+    pub fn filter_to<H, F>(&self, pred: F, out_file: H) -> Result<()>
+        where H: Write, F: Fn(&str, &EntryInfo) -> bool
+    {
+        let removed = self.inner.entries.files.iter()
+            .filter(|&(name, entry)| !pred(name, &EntryInfo::from(entry)))
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+
+        self.remove_to(&removed, out_file)
+    }
+
+    /// This method creates a FileArco v1 archive file, populates it with
+    /// the specified files, and writes the result to the standard output.
+    ///
+    /// Files with identical contents are stored only once.
+    ///
+    /// # Arguments
+    ///
+    /// * file_data - file paths and other metadata of the input files
+    ///
+    /// * out_path - file path for archive file
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::fs::File;
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// let base_path = Path::new("testarchives/reqchandocs");
+    /// let file_data = filearco::get_file_data(base_path).ok().unwrap();
+    ///
+    /// filearco::v1::FileArco::make(file_data, io::stdout()).ok().unwrap();
+    /// ```
+    pub fn make<H: Write>(file_data: FileData, out_file: H) -> Result<()> {
+        FileArco::make_with(file_data, out_file, &MakeOptions::new())
+    }
+
+    /// This method works like `make` but returns the total number of bytes
+    /// written to `out_file`.
+    ///
+    /// # Arguments
+    ///
+    /// * file_data - file paths and other metadata of the input files
+    ///
+    /// * out_file - destination of archive file
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// let base_path = Path::new("testarchives/simple");
+    /// let file_data = filearco::get_file_data(base_path).ok().unwrap();
+    ///
+    /// let length = filearco::v1::FileArco::make_counted(file_data, io::sink()).ok().unwrap();
+    /// println!("{} bytes", length);
+    /// ```
+    pub fn make_counted<H: Write>(file_data: FileData, out_file: H) -> Result<u64> {
+        let mut counter = CountingWriter {
+            inner: out_file,
+            count: 0,
+        };
+        FileArco::make(file_data, &mut counter)?;
+
+        Ok(counter.count)
+    }
+
+    /// This method works like `make` but follows the archive with a
+    /// signature block holding an HMAC-SHA256 of the whole archive (header,
+    /// entries table and file contents) computed with `key`. Anyone with
+    /// the key can then check that the archive was not tampered with using
+    /// `verify_signature()`.
+    ///
+    /// # Arguments
+    ///
+    /// * file_data - file paths and other metadata of the input files
+    ///
+    /// * out_file - destination of archive file
+    ///
+    /// * key - secret key to sign the archive with
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// let base_path = Path::new("testarchives/simple");
+    /// let file_data = filearco::get_file_data(base_path).ok().unwrap();
+    ///
+    /// filearco::v1::FileArco::make_signed(file_data, io::sink(), b"secret").ok().unwrap();
+    /// ```
+    pub fn make_signed<H: Write>(file_data: FileData, out_file: H, key: &[u8]) -> Result<()> {
+        FileArco::make_with(file_data, out_file, &MakeOptions::new().signing_key(Some(key)))
+    }
+
+    /// This method creates a FileArco v1 archive file from files held in
+    /// memory instead of read from the filesystem, and writes the result to
+    /// `out_file`. Files with identical contents are stored only once.
+    ///
+    /// # Arguments
+    ///
+    /// * entries - name and contents of every file
+    ///
+    /// * out_file - destination of archive file
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    ///
+    /// let entries = vec![(String::from("hello.txt"), b"Hello, World!".to_vec())];
+    ///
+    /// filearco::v1::FileArco::make_from_entries(entries, io::sink()).ok().unwrap();
+    /// ```
+    pub fn make_from_entries<H: Write>(entries: Vec<(String, Vec<u8>)>,
+                                       out_file: H) -> Result<()> {
+        let entries = entries.into_iter()
+            .map(|(name, contents)| {
+                let content_type = guess_content_type(&name).map(String::from);
+                (name, contents, content_type)
+            })
+            .collect();
+
+        FileArco::make_from_typed_entries(entries, out_file)
+    }
+
+    /// This method works like `make_from_entries` but also stores the
+    /// specified content type (i.e. MIME type) of every file, as reported by
+    /// `FileRef::content_type()`. `make_from_entries` guesses content types
+    /// from file extensions instead.
+    ///
+    /// # Arguments
+    ///
+    /// * entries - name, contents, and content type of every file
+    ///
+    /// * out_file - destination of archive file
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    ///
+    /// let entries = vec![(String::from("index"),
+    ///                     b"<p>Hello, World!</p>".to_vec(),
+    ///                     Some(String::from("text/html")))];
+    ///
+    /// filearco::v1::FileArco::make_from_typed_entries(entries, io::sink()).ok().unwrap();
+    /// ```
+    pub fn make_from_typed_entries<H: Write>(entries: Vec<(String, Vec<u8>, Option<String>)>,
+                                             mut out_file: H) -> Result<()> {
+        let page_size = get_page_size() as u64;
+        let mut files = HashMap::with_capacity(entries.len());
+
+        for &(ref name, ref contents, ref content_type) in entries.iter() {
+            if files.contains_key(name) {
+                return Err(Error::FileArcoV1(FileArcoV1Error::DuplicateEntry(name.clone())));
+            }
+
+            let length = contents.len() as u64;
+
+            files.insert(name.clone(),
+                         Entry {
+                             offset: 0,
+                             length: length,
+                             aligned_length: get_aligned_length(length, page_size),
+                             checksum: seeded_checksum(0, contents),
+                             mode: DEFAULT_MODE,
+                             modified: 0,
+                             raw_name: None,
+                             compressed: false,
+                             stored_length: length,
+                             content_type: content_type.clone(),
+                             digest: None,
+                             inline: None,
+                         }
+            );
+        }
+
+        let mut table = Entries {
+            files: files,
+            producer: Some(default_producer()),
+        };
+
+        let contents_by_name = entries.iter()
+            .map(|&(ref name, ref contents, _)| (name.as_str(), &contents[..]))
+            .collect::<HashMap<_, _>>();
+        table.assign_offsets(|(a, _), (b, _)| Ok(contents_by_name[a] == contents_by_name[b]))?;
+
+        write_prelude(&mut out_file, &table, table.flags(), page_size, 0)?;
+
+        let mut sorted = entries.iter()
+            .map(|&(ref name, ref contents, _)| (&table.files[name], name, contents))
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|&(entry, _, _)| entry.offset);
+
+        let mut next_offset = 0;
+
+        for (entry, _, contents) in sorted.into_iter() {
+            if entry.length == 0 {
+                continue;
+            }
+
+            // Files with identical contents share the region of the first
+            // one, which was the last file written.
+            if entry.offset < next_offset {
+                continue;
+            }
+
+            out_file.write_all(contents)?;
+
+            // Pad archive with zeros to ensure next file begins at a multiple of 4096.
+            let padding: Vec<u8> = vec![0u8; (entry.aligned_length - entry.length) as usize];
+            out_file.write_all(&padding)?;
+
+            next_offset = entry.offset + entry.aligned_length;
+        }
+
+        Ok(())
+    }
+
+    /// This method wraps the archive in an `OverlayArchive`, which serves
+    /// the files in `overlay` in place of (or in addition to) those in the
+    /// archive. Files whose overlay contents equal `OVERLAY_MASK` are hidden
+    /// instead. The archive itself is not modified.
+    ///
+    /// # Arguments
+    ///
+    /// * overlay - names and contents of files to override
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::collections::HashMap;
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// let mut overlay = HashMap::new();
+    /// overlay.insert(String::from("Cargo.toml"), b"[package]".to_vec());
+    ///
+    /// let overlaid = archive.with_overlay(overlay);
+    /// assert_eq!(overlaid.get("Cargo.toml").unwrap().as_slice(), b"[package]");
+    /// ```
+    pub fn with_overlay(self, overlay: HashMap<String, Vec<u8>>) -> OverlayArchive {
+        let overlay = overlay.into_iter()
+            .map(|(name, contents)| {
+                if contents == OVERLAY_MASK {
+                    (name, None)
+                }
+                else {
+                    (name, Some(contents))
+                }
+            })
+            .collect();
+
+        OverlayArchive {
+            archive: self,
+            overlay: overlay,
+        }
+    }
+
+    /// This method returns a `RerootedArchive`, which serves the files of
+    /// this archive as if they were stored under the directory `prefix`.
+    /// The file contents are shared, not copied.
+    ///
+    /// # Arguments
+    ///
+    /// * prefix - directory to place every file under
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    ///
+    /// let rerooted = archive.rerooted("assets");
+    /// assert!(rerooted.get("assets/Cargo.toml").is_some());
+    /// assert!(rerooted.get("Cargo.toml").is_none());
+    /// ```
+    pub fn rerooted(&self, prefix: &str) -> RerootedArchive {
+        let prefix = normalize_separators(prefix);
+        let prefix = prefix.trim_matches('/');
+
+        RerootedArchive {
+            archive: FileArco { inner: self.inner.clone() },
+            prefix: if prefix.is_empty() {
+                String::new()
+            }
+            else {
+                format!("{}/", prefix)
+            },
+        }
+    }
+
+    /// This method works like `make` but aligns every file to a multiple of
+    /// `alignment` instead of this machine's page size.
+    ///
+    /// # Arguments
+    ///
+    /// * file_data - file paths and other metadata of the input files
+    ///
+    /// * out_file - destination of archive file
+    ///
+    /// * alignment - alignment of each file (must be a power of two)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// use filearco::v1::FileArco;
+    ///
+    /// let base_path = Path::new("testarchives/simple");
+    /// let file_data = filearco::get_file_data(base_path).ok().unwrap();
+    ///
+    /// FileArco::make_with_alignment(file_data, io::stdout(), 16384).ok().unwrap();
+    /// ```
+    pub fn make_with_alignment<H: Write>(file_data: FileData,
+                                         out_file: H,
+                                         alignment: u64) -> Result<()> {
+        FileArco::make_with(file_data, out_file, &MakeOptions::new().alignment(alignment))
+    }
+
+    /// This method works like `make` but uses the specified `options`.
+    ///
+    /// # Arguments
+    ///
+    /// * file_data - file paths and other metadata of the input files
+    ///
+    /// * out_file - destination of archive file
+    ///
+    /// * options - settings such as the checksum seed
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// use filearco::v1::{FileArco, MakeOptions};
+    ///
+    /// let base_path = Path::new("testarchives/reqchandocs");
+    /// let file_data = filearco::get_file_data(base_path).ok().unwrap();
+    /// let options = MakeOptions::new().seed(0x5eed);
+    ///
+    /// FileArco::make_with(file_data, io::stdout(), &options).ok().unwrap();
+    /// ```
+    pub fn make_with<H: Write>(file_data: FileData,
+                               out_file: H,
+                               options: &MakeOptions) -> Result<()> {
+        FileArco::make_reporting(file_data, out_file, options, |_| {})
+    }
+
+    /// This method adds the files in `file_data` to the existing archive at
+    /// `path` without copying the contents already stored in it. New
+    /// contents are written after the existing ones, followed by a new
+    /// entries table and header in the trailer layout (see `Layout`).
+    ///
+    /// It returns `FileArcoV1Error::DuplicateEntry` (before changing
+    /// anything) if a file is already in the archive. Use `append_with` to
+    /// replace such files instead.
+    ///
+    /// Archives created with `MakeOptions::seed` fail to validate (with
+    /// `FileArcoV1Error::CorruptedHeader`); use `append_with_seed` for those.
+    ///
+    /// **NOTE:** The archive is modified in place. If appending fails partway
+    /// through, the archive may be left unreadable.
+    ///
+    /// # Arguments
+    ///
+    /// * path - file path of archive file
+    ///
+    /// * file_data - file paths and other metadata of the files to add
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// archive.copy_to("tmptest/doc_append.fac").ok().unwrap();
+    ///
+    /// let base_path = Path::new("testarchives/reqchandocs");
+    /// let file_data = filearco::get_file_data(base_path).ok().unwrap();
+    ///
+    /// filearco::v1::FileArco::append("tmptest/doc_append.fac", file_data).ok().unwrap();
+    /// ```
+    pub fn append<P: AsRef<Path>>(path: P, file_data: FileData) -> Result<()> {
+        FileArco::append_with(path, file_data, false)
+    }
+
+    /// This method works like `append` but, if `overwrite` is true, files
+    /// already in the archive are replaced by the files in `file_data`
+    /// with the same names. The contents of replaced files are left in the
+    /// archive but are no longer referenced.
+    ///
+    /// Files are streamed into the archive, which fails with
+    /// `FileArcoV1Error::SourceChanged` if one no longer matches `file_data`.
+    /// The archive is modified in place, so it may be left unusable after
+    /// any error; append to a copy if that matters. Like `append`, it only
+    /// opens archives made without a seed.
+    ///
+    /// # Arguments
+    ///
+    /// * path - file path of archive file
+    ///
+    /// * file_data - file paths and other metadata of the files to add
+    ///
+    /// * overwrite - whether to replace files already in the archive
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let archive = filearco::v1::FileArco::new(path).ok().unwrap(); 
+    /// archive.copy_to("tmptest/doc_append_with.fac").ok().unwrap();
+    ///
+    /// let base_path = Path::new("testarchives/simple");
+    /// let file_data = filearco::get_file_data(base_path).ok().unwrap();
+    ///
+    /// filearco::v1::FileArco::append_with("tmptest/doc_append_with.fac", file_data, true)
+    ///     .ok().unwrap();
+    /// ```
+    pub fn append_with<P: AsRef<Path>>(path: P,
+                                       file_data: FileData,
+                                       overwrite: bool) -> Result<()> {
+        FileArco::append_with_seed(path, file_data, overwrite, 0)
+    }
+
+    /// This method works like `append_with` for an archive created with
+    /// `MakeOptions::seed`. The same `seed` must be given or the archive
+    /// fails to validate with `FileArcoV1Error::CorruptedHeader` before
+    /// anything is changed. The checksums of the added files and the new
+    /// trailer are computed with `seed` too.
+    ///
+    /// # Arguments
+    ///
+    /// * path - file path of archive file
+    ///
+    /// * file_data - file paths and other metadata of the files to add
+    ///
+    /// * overwrite - whether to replace files already in the archive
+    ///
+    /// * seed - secret value mixed into every checksum
+    pub fn append_with_seed<P: AsRef<Path>>(path: P,
+                                            file_data: FileData,
+                                            overwrite: bool,
+                                            seed: u64) -> Result<()> {
+        let mut archive_file = OpenOptions::new().read(true).write(true).open(path)?;
+        let archive_length = archive_file.seek(SeekFrom::End(0))?;
+
+        let (header, mut entries) = read_index_with(
+            archive_length,
+            seed,
+            &mut OpenMetrics::default(),
+            |offset, length| {
+                let mut buffer = vec![0u8; length];
+                archive_file.seek(SeekFrom::Start(offset))?;
+                archive_file.read_exact(&mut buffer)?;
+
+                Ok(Cow::Owned(buffer))
+            }
+        )?;
+
+        let base_path = file_data.path().to_path_buf();
+        let mut added = Entries::new(file_data, header.page_size)?;
+
+        // Non-UTF-8 names are matched with existing files by their exact
+        // bytes, and get a new key if theirs belongs to a different name.
+        let existing_raw_names = entries.raw_names();
+        let rekeyed = added.files.iter()
+            .filter_map(|(name, entry)| entry.raw_name.as_ref().map(|raw_name| (name, raw_name)))
+            .filter(|&(name, raw_name)| {
+                match existing_raw_names.get(raw_name) {
+                    Some(key) => key != name,
+                    None => entries.files.contains_key(name),
+                }
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        for name in rekeyed.into_iter() {
+            let entry = added.files.remove(&name).unwrap();
+            let key = match existing_raw_names.get(entry.raw_name.as_ref().unwrap()) {
+                Some(key) => key.clone(),
+                None => unique_name(name, |name| {
+                    entries.files.contains_key(name) || added.files.contains_key(name)
+                }),
+            };
+
+            added.files.insert(key, entry);
+        }
+
+        if !overwrite {
+            if let Some(name) = added.files.keys().find(|name| entries.files.contains_key(*name)) {
+                return Err(Error::FileArcoV1(FileArcoV1Error::DuplicateEntry(name.clone())));
+            }
+        }
+
+        // New contents start after the region of the last existing file.
+        let page_size = header.page_size;
+        let checksum_kind = ChecksumKind::from_flags(header.flags());
+        let mut offset = align_to(entries.total_aligned_length(), page_size);
+
+        let mut added = added.files.into_iter().collect::<Vec<_>>();
+        added.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (name, mut entry) in added.into_iter() {
+            entry.offset = offset;
+            entry.aligned_length = align_to(entry.stored_length, page_size);
+
+            // Copy the file into the archive. If it changed since it was
+            // scanned, its padding and every later offset would be wrong.
+            let full_path = base_path.join(entry_path(&name, &entry));
+            archive_file.seek(SeekFrom::Start(header.file_offset + offset))?;
+            let (checksum, digest, length, _) = stream_contents(&full_path,
+                                                                checksum_kind,
+                                                                seed,
+                                                                false,
+                                                                &mut archive_file)?;
+
+            // The scanned checksum is an unseeded CRC-64 checksum, so it can
+            // only be compared with one computed the same way.
+            let recomputed = checksum_kind == ChecksumKind::Crc64 && seed == 0;
+
+            if length != entry.length || (recomputed && checksum != entry.checksum) {
+                return Err(Error::FileArcoV1(FileArcoV1Error::SourceChanged(name)));
+            }
+
+            entry.checksum = checksum;
+            entry.digest = digest;
+
+            archive_file.write_all(&vec![0u8; (entry.aligned_length - entry.stored_length) as usize])?;
+
+            offset = offset + entry.aligned_length;
+            entries.files.insert(name, entry);
+        }
+
+        // Anything after the contents (e.g. an old trailer) is replaced.
+        let contents_end = header.file_offset + offset;
+        archive_file.seek(SeekFrom::Start(contents_end))?;
+        write_trailer(&mut archive_file,
+                      &entries,
+                      header.flags(),
+                      page_size,
+                      header.file_offset,
+                      contents_end,
+                      seed)?;
+
+        let end = archive_file.stream_position()?;
+        archive_file.set_len(end)?;
+
+        Ok(())
+    }
+
+    /// This method works like `make` but calls `callback` as the archive is
+    /// written, so callers can report progress. For every file (including
+    /// empty files and files that share the contents of another file),
+    /// `callback` first receives `ProgressEvent::StartedFile`, followed by
+    /// `ProgressEvent::BytesWritten` once its contents are written. The
+    /// archive is identical to the one `make` writes.
+    ///
+    /// `callback` is not called again after an error.
+    ///
+    /// # Arguments
+    ///
+    /// * file_data - file paths and other metadata of the input files
+    ///
+    /// * out_file - destination of archive file
+    ///
+    /// * callback - function that receives progress events
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// use filearco::v1::{FileArco, ProgressEvent};
+    ///
+    /// let base_path = Path::new("testarchives/simple");
+    /// let file_data = filearco::get_file_data(base_path).ok().unwrap();
+    ///
+    /// FileArco::make_with_progress(file_data, io::sink(), |event| {
+    ///     if let ProgressEvent::StartedFile { name, index, total } = event {
+    ///         eprintln!("[{}/{}] {}", index + 1, total, name);
+    ///     }
+    /// }).ok().unwrap();
+    /// ```
+    pub fn make_with_progress<H, F>(file_data: FileData,
+                                    out_file: H,
+                                    callback: F) -> Result<()>
+        where H: Write, F: FnMut(ProgressEvent)
+    {
+        FileArco::make_reporting(file_data, out_file, &MakeOptions::new(), callback)
+    }
+
+    fn make_reporting<H, F>(file_data: FileData,
+                            mut out_file: H,
+                            options: &MakeOptions,
+                            callback: F) -> Result<()>
+        where H: Write, F: FnMut(ProgressEvent)
+    {
+        let key = match options.signing_key {
+            Some(ref key) => key,
+            None => return FileArco::write_archive(file_data, out_file, options, callback),
+        };
+
+        let mut signer = SigningWriter {
+            inner: &mut out_file,
+            mac: new_mac(key),
+        };
+        FileArco::write_archive(file_data, &mut signer, options, callback)?;
+
+        let mac = signer.mac.finalize().into_bytes();
+        signer.inner.write_all(SIGNATURE_ID)?;
+        signer.inner.write_all(&mac)?;
+        signer.inner.flush()?;
+
+        Ok(())
+    }
+
+    fn write_archive<H, F>(file_data: FileData,
+                           mut out_file: H,
+                           options: &MakeOptions,
+                           mut callback: F) -> Result<()>
+        where H: Write, F: FnMut(ProgressEvent)
+    {
+        let base_path = file_data.path();
+        let alignment = options.alignment;
+
+        if !alignment.is_power_of_two() {
+            return Err(Error::FileArcoV1(FileArcoV1Error::InvalidAlignment(alignment)));
+        }
+   
+        // Create entries table and write it to the archive along with the header.
+        let mut entries = Entries::new(file_data, alignment)?;
+        entries.producer = options.producer.clone();
+
+        // The checksums in `file_data` are unseeded CRC-64 checksums, and the
+        // length of each compressed file must be known before the entries
+        // table is written, so any of these options needs an extra pass over
+        // the input files. It computes each checksum while compressing the
+        // file, but only keeps the compressed length, so memory use does not
+        // grow with the size of the input. Compressed files are compressed
+        // again when they are written.
+        let compress = options.compression != CompressionMode::None;
+        let crc32 = options.checksum == ChecksumKind::Crc32;
+        let sha256 = options.checksum == ChecksumKind::Sha256;
+
+        if options.seed != 0 || compress || crc32 || sha256 {
+            for (path, entry) in entries.files.iter_mut() {
+                // Even empty files have a digest.
+                if entry.length == 0 && !sha256 {
+                    continue;
+                }
+
+                let full_path = base_path.to_path_buf().join(entry_path(path, entry));
+                let (checksum, digest, length, stored_length) =
+                    stream_contents(&full_path, options.checksum, options.seed, compress, io::sink())?;
+
+                // The recorded checksum can only be compared if it was
+                // computed the same way.
+                let recomputed = options.checksum == ChecksumKind::Crc64 && options.seed == 0;
+
+                if length != entry.length || (recomputed && checksum != entry.checksum) {
+                    return Err(Error::FileArcoV1(FileArcoV1Error::SourceChanged(path.clone())));
+                }
+
+                entry.checksum = checksum;
+                entry.digest = digest;
+
+                // Empty files are never written, so they stay uncompressed.
+                if compress && entry.length > 0 &&
+                    (options.compression == CompressionMode::Deflate ||
+                     worth_compressing(entry.length, stored_length)) {
+                    entry.compressed = true;
+                    entry.stored_length = stored_length;
+                }
+            }
+        }
+
+        // Small files are moved into the entries table. They are read again,
+        // so make sure they did not change since their checksum was recorded.
+        if options.inline_threshold > 0 {
+            for (path, entry) in entries.files.iter_mut() {
+                if entry.length == 0 || entry.length >= options.inline_threshold {
+                    continue;
+                }
+
+                let full_path = base_path.to_path_buf().join(entry_path(path, entry));
+                let mut contents = Vec::<u8>::with_capacity(entry.length as usize);
+                File::open(full_path)?.read_to_end(&mut contents)?;
+
+                let changed = contents.len() as u64 != entry.length ||
+                    (options.checksum != ChecksumKind::None &&
+                     options.checksum.compute(options.seed, &contents) != (entry.checksum, entry.digest));
+
+                if changed {
+                    return Err(Error::FileArcoV1(FileArcoV1Error::SourceChanged(path.clone())));
+                }
+
+                entry.inline = Some(contents);
+                entry.compressed = false;
+                entry.stored_length = 0;
+            }
+        }
+
+        // Compressed or inline files may have shrunk since `Entries::new`
+        // laid out the files.
+        for entry in entries.files.values_mut() {
+            entry.aligned_length = align_to(entry.stored_length, alignment);
+        }
+        entries.assign_offsets(|(a, a_entry), (b, b_entry)| {
+            let a_path = base_path.join(entry_path(a, a_entry));
+            let b_path = base_path.join(entry_path(b, b_entry));
+
+            Ok(same_contents(&a_path, &b_path)?)
+        })?;
+
+        // Checksums are still used above to find identical files, so they
+        // are only cleared once the layout is fixed.
+        let mut flags = entries.flags();
+
+        if options.checksum == ChecksumKind::None {
+            for entry in entries.files.values_mut() {
+                entry.checksum = 0;
+            }
+
+            flags = flags | FLAG_NO_CHECKSUMS;
+        }
+
+        if crc32 {
+            flags = flags | FLAG_CRC32;
+        }
+
+        if sha256 {
+            flags = flags | FLAG_SHA256;
+        }
+
+        write_prelude(&mut out_file, &entries, flags, alignment, options.seed)?;
+
+        // Began writing files to archive in the order they are stored.
+        let mut files = entries.files.iter().collect::<Vec<_>>();
+        files.sort_by_key(|&(_, entry)| entry.offset);
+
+        let mut next_offset = 0;
+        let mut last_path = PathBuf::new();
+        let total = files.len();
+
+        for (index, (path, entry)) in files.into_iter().enumerate() {
+            callback(ProgressEvent::StartedFile {
+                name: path,
+                index: index,
+                total: total,
+            });
+
+            if entry.inline.is_some() {
+                callback(ProgressEvent::BytesWritten(0));
+                continue;
+            }
+
+            let full_path = base_path.to_path_buf().join(entry_path(path, entry));
+
+            if entry.length == 0 {
+                if metadata(&full_path)?.len() != 0 {
+                    return Err(Error::FileArcoV1(FileArcoV1Error::SourceChanged(path.clone())));
+                }
+
+                callback(ProgressEvent::BytesWritten(0));
+                continue;
+            }
+
+            // Files with identical contents share the region of the first
+            // one, which was the last file written. They were compared when
+            // the files were laid out, so a difference now means one changed.
+            if entry.offset < next_offset {
+                if !same_contents(&full_path, &last_path)? {
+                    return Err(Error::FileArcoV1(FileArcoV1Error::SourceChanged(path.clone())));
+                }
+
+                callback(ProgressEvent::BytesWritten(0));
+                continue;
+            }
+
+            // Copy input file contents to archive, compressing them again if
+            // needed. If the file changed since the entries table was
+            // written, the archive would be corrupted.
+            let (checksum, digest, length, written) = stream_contents(&full_path,
+                                                                      options.checksum,
+                                                                      options.seed,
+                                                                      entry.compressed,
+                                                                      &mut out_file)?;
+
+            if checksum != entry.checksum || digest != entry.digest ||
+                length != entry.length || written != entry.stored_length {
+                return Err(Error::FileArcoV1(FileArcoV1Error::SourceChanged(path.clone())));
+            }
+            
+            // Pad archive with zeros to ensure next file begins at a multiple of 4096.
+            let padding_length = entry.aligned_length - entry.stored_length;
+            let padding: Vec<u8> = vec![0u8; padding_length as usize];
+            out_file.write_all(&padding)?;
+            callback(ProgressEvent::BytesWritten(entry.aligned_length));
+
+            next_offset = entry.offset + entry.aligned_length;
+            last_path = full_path;
+        }
+        
+        Ok(())
+    }
+}
+
+/// This function compares the file named `name` in archives `a` and `b` and
+/// returns the position of every differing byte along with the byte in `a`
+/// and the byte in `b`. If one file is longer, all of its extra bytes are
+/// reported with 0 standing in for the missing byte of the shorter file. It
+/// returns `None` if either archive does not contain the file.
+///
+/// Files with the same length and checksum are assumed to be identical, so
+/// an empty vector is returned without comparing their contents, unless the
+/// archives use different checksums (or no checksums).
+///
+/// # Arguments
+///
+/// * a - archive with the old version of the file
+///
+/// * b - archive with the new version of the file
+///
+/// * name - name of file to compare
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::path::Path;
+///
+/// let path = Path::new("testarchives/simple_v1.fac");
+/// let a = filearco::v1::FileArco::new(path).ok().unwrap(); 
+/// let b = filearco::v1::FileArco::new(path).ok().unwrap(); 
+///
+/// let diff = filearco::v1::file_byte_diff(&a, &b, "Cargo.toml").unwrap();
+/// assert!(diff.is_empty());
+/// ```
+pub fn file_byte_diff(a: &FileArco, b: &FileArco, name: &str) -> Option<Vec<(u64, u8, u8)>> {
+    let (old, new) = match (a.get(name), b.get(name)) {
+        (Some(old), Some(new)) => (old, new),
+        _ => return None,
+    };
+
+    // Checksums can only be compared if they were computed the same way.
+    let comparable = a.inner.checksum_kind == b.inner.checksum_kind &&
+        a.inner.checksum_kind != ChecksumKind::None &&
+        a.inner.seed == b.inner.seed;
+
+    if comparable && old.length == new.length && old.checksum == new.checksum &&
+        old.digest == new.digest {
+        return Some(Vec::new());
+    }
+
+    let old = old.as_slice();
+    let new = new.as_slice();
+    let length = cmp::max(old.len(), new.len());
+
+    let diff = (0..length)
+        .map(|i| (i as u64,
+                  old.get(i).cloned().unwrap_or(0),
+                  new.get(i).cloned().unwrap_or(0)))
+        .filter(|&(i, old_byte, new_byte)| {
+            old_byte != new_byte || i as usize >= cmp::min(old.len(), new.len())
+        })
+        .collect();
+
+    Some(diff)
+}
+
+/// This function lists the files that were added, removed, or changed
+/// between archive `a` and archive `b`. Files are compared by length and
+/// checksum, so only the entries tables are read, unless the archives use
+/// different checksums (or no checksums), in which case the contents of
+/// files present in both are compared.
+///
+/// # Arguments
+///
+/// * a - old archive
+///
+/// * b - new archive
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::path::Path;
+///
+/// let path = Path::new("testarchives/simple_v1.fac");
+/// let a = filearco::v1::FileArco::new(path).ok().unwrap(); 
+/// let b = filearco::v1::FileArco::new(path).ok().unwrap(); 
+///
+/// let diff = filearco::v1::diff(&a, &b);
+/// assert!(diff.is_empty());
+/// ```
+pub fn diff(a: &FileArco, b: &FileArco) -> ArchiveDiff {
+    let old = &a.inner.entries.files;
+    let new = &b.inner.entries.files;
+
+    // Checksums can only be compared if they were computed the same way.
+    let comparable = a.inner.checksum_kind == b.inner.checksum_kind &&
+        a.inner.checksum_kind != ChecksumKind::None &&
+        a.inner.seed == b.inner.seed;
+
+    let mut added = new.keys()
+        .filter(|name| !old.contains_key(*name))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut removed = old.keys()
+        .filter(|name| !new.contains_key(*name))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut changed = old.iter()
+        .filter_map(|(name, old_entry)| new.get(name).map(|new_entry| (name, old_entry, new_entry)))
+        .filter(|&(_, old_entry, new_entry)| {
+            if old_entry.length != new_entry.length {
+                true
+            }
+            else if comparable {
+                old_entry.checksum != new_entry.checksum || old_entry.digest != new_entry.digest
+            }
+            else {
+                a.file_ref(old_entry).as_slice() != b.file_ref(new_entry).as_slice()
+            }
+        })
+        .map(|(name, _, _)| name.clone())
+        .collect::<Vec<_>>();
+
+    added.sort();
+    removed.sort();
+    changed.sort();
+
+    ArchiveDiff {
+        added: added,
+        removed: removed,
+        changed: changed,
+    }
+}
+
+/// This function estimates the size (in bytes) of an archive made with
+/// `FileArco::make` from files with the given names and lengths, without
+/// reading any file contents. The estimate is exact unless some files have
+/// identical contents, in which case it is an upper bound. Like
+/// `MakeOptions::alignment`, `alignment` must be a power of two, or this
+/// returns `FileArcoV1Error::InvalidAlignment`.
+///
+/// # Arguments
+///
+/// * names_and_lengths - name and length of every file
+///
+/// * alignment - alignment of file contents (i.e. the memory page size)
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// let files = vec![(String::from("index.html"), 1000),
+///                  (String::from("main.css"), 5000)];
+///
+/// let size = filearco::v1::estimated_size(&files, 4096).ok().unwrap();
+/// assert!(size >= 2 * 4096);
+/// ```
+pub fn estimated_size(names_and_lengths: &[(String, u64)], alignment: u64) -> Result<u64> {
+    if !alignment.is_power_of_two() {
+        return Err(Error::FileArcoV1(FileArcoV1Error::InvalidAlignment(alignment)));
+    }
+
+    let entries = Entries::from_metadata(names_and_lengths, alignment);
+    let entries_length = serialize(&entries, Infinite)?.len() as u64;
+
+    // This mirrors the computation of `file_offset` in `Header::new()`.
+    let file_offset = align_to(header_size() as u64 + entries_length, alignment);
+
+    Ok(file_offset + entries.total_aligned_length())
+}
+
+/// This function validates a single file of the archive at `path` without
+/// memory mapping it. Only the header, the entries table, and the file itself
+/// are read, which helps on slow storage. It returns an error if the archive
+/// does not contain a file named `name`.
+///
+/// # Arguments
+///
+/// * path - file path of archive file
+///
+/// * name - name of file to validate
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::path::Path;
+///
+/// let path = Path::new("testarchives/simple_v1.fac");
+/// assert!(filearco::v1::verify_file_at(path, "Cargo.toml").ok().unwrap());
+/// ```
+pub fn verify_file_at<P: AsRef<Path>>(path: P, name: &str) -> Result<bool> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let archive_length = reader.seek(SeekFrom::End(0))?;
+
+    let mut metrics = OpenMetrics::default();
+    let (header, entries) = read_index_with(archive_length, 0, &mut metrics, |offset, length| {
+        let mut buffer = vec![0u8; length];
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(&mut buffer)?;
+
+        Ok(Cow::Owned(buffer))
+    })?;
+
+    let entry = match entries.files.get(normalize_separators(name).as_ref()) {
+        Some(entry) => entry,
+        None => {
+            return Err(Error::Io(io::Error::new(io::ErrorKind::NotFound,
+                                                format!("{} not in archive", name))));
+        },
+    };
+
+    let stored: Box<Read> = match entry.inline {
+        Some(ref inline) => Box::new(Cursor::new(inline.clone())),
+        None => {
+            reader.seek(SeekFrom::Start(header.file_offset + entry.offset))?;
+            Box::new(reader.take(entry.stored_length))
+        },
+    };
+
+    // Compressed files are validated against their decompressed contents.
+    let mut contents: Box<Read> = if header.flags() & FLAG_COMPRESSED != 0 && entry.compressed {
+        Box::new(DeflateDecoder::new(stored))
+    }
+    else {
+        Box::new(stored)
+    };
+
+    let checksum_kind = ChecksumKind::from_flags(header.flags());
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    let mut length = 0;
+    let mut contents_checksum = 0;
+    let mut hasher = Sha256::new();
+
+    loop {
+        let read = match contents.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(ref e) if e.kind() == io::ErrorKind::InvalidInput ||
+                e.kind() == io::ErrorKind::InvalidData => return Ok(false),
+            Err(e) => return Err(Error::Io(e)),
+        };
+
+        contents_checksum = checksum_kind.update(contents_checksum, &buffer[..read]);
+        hasher.update(&buffer[..read]);
+        length = length + read as u64;
+    }
+
+    // Without a checksum, only the length can be validated.
+    let valid = match checksum_kind {
+        ChecksumKind::Sha256 => entry.digest.map(|digest| digest[..] == hasher.finalize()[..]),
+        ChecksumKind::None => Some(true),
+        _ => Some(contents_checksum == entry.checksum),
+    };
+
+    Ok(length == entry.length && valid == Some(true))
+}
+
+/// This function returns how many bytes the archive file at `path` holds
+/// beyond the length declared in its header, which can reveal accidental
+/// concatenation or an incomplete overwrite. Only the header is read.
+///
+/// Archives using the trailer layout (including archives that were appended
+/// to) end with their footer, so this returns 0 for them. The signature
+/// block of a signed archive does not count as trailing bytes either.
+///
+/// # Arguments
+///
+/// * path - file path of archive file
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::path::Path;
+///
+/// let path = Path::new("testarchives/simple_v1.fac");
+/// assert_eq!(filearco::v1::check_trailing_bytes(path).ok().unwrap(), 0);
+/// ```
+pub fn check_trailing_bytes<P: AsRef<Path>>(path: P) -> Result<u64> {
+    let mut file = File::open(path)?;
+    let archive_length = file.seek(SeekFrom::End(0))?;
+    let header_length = header_size() + CHECKSUM_SIZE;
+
+    // Like `FileArco::new`, prefer the trailer layout if a footer is present.
+    if archive_length >= FOOTER_SIZE as u64 {
+        let mut footer = vec![0u8; FOOTER_SIZE];
+        file.seek(SeekFrom::End(-(FOOTER_SIZE as i64)))?;
+        file.read_exact(&mut footer)?;
+
+        if read_footer(&footer).is_some() {
+            return Ok(0);
+        }
+    }
+
+    if archive_length < header_length as u64 {
+        return Err(Error::FileArcoV1(FileArcoV1Error::FileTooSmall));
+    }
+
+    let mut header_bytes = vec![0u8; header_length];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut header_bytes)?;
+
+    let header = read_header(&header_bytes, 0, 0, &mut OpenMetrics::default())?;
+
+    if archive_length < header.file_length {
+        return Err(Error::FileArcoV1(FileArcoV1Error::FileTruncated));
+    }
+
+    let trailing = archive_length - header.file_length;
+
+    if trailing == SIGNATURE_SIZE as u64 {
+        let mut id = [0u8; 8];
+        file.seek(SeekFrom::Start(header.file_length))?;
+        file.read_exact(&mut id)?;
+
+        if &id == SIGNATURE_ID {
+            return Ok(0);
+        }
+    }
+
+    Ok(trailing)
+}
+
+/// This function reads the header of the archive file at `path` without
+/// validating it, which helps to find out why an archive cannot be opened.
+/// Archives using the trailer layout are read from the header their footer
+/// points to. Only the header and footer are read.
+///
+/// # Arguments
+///
+/// * path - file path of archive file
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::path::Path;
+///
+/// let path = Path::new("testarchives/simple_v1.fac");
+/// let header = filearco::v1::read_raw_header(path).ok().unwrap();
+/// assert!(header.is_checksum_valid());
+/// println!("{:?}", header);
+/// ```
+pub fn read_raw_header<P: AsRef<Path>>(path: P) -> Result<RawHeader> {
+    let mut file = File::open(path)?;
+    let archive_length = file.seek(SeekFrom::End(0))?;
+    let header_length = header_size() + CHECKSUM_SIZE;
+    let mut header_offset = 0;
+
+    if archive_length >= FOOTER_SIZE as u64 {
+        let mut footer = vec![0u8; FOOTER_SIZE];
+        file.seek(SeekFrom::End(-(FOOTER_SIZE as i64)))?;
+        file.read_exact(&mut footer)?;
+
+        if let Some(offset) = read_footer(&footer) {
+            if offset.checked_add(header_length as u64).is_some_and(|end| end <= archive_length) {
+                header_offset = offset;
+            }
+        }
+    }
+
+    if archive_length < header_offset + header_length as u64 {
+        return Err(Error::FileArcoV1(FileArcoV1Error::FileTooSmall));
+    }
+
+    let mut header_bytes = vec![0u8; header_length];
+    file.seek(SeekFrom::Start(header_offset))?;
+    file.read_exact(&mut header_bytes)?;
+
+    let (sl, checksum_bytes) = header_bytes.split_at(header_size());
+    let header: Header = decode(sl)?;
+    let header_checksum: u64 = decode(checksum_bytes)?;
+
+    Ok(RawHeader {
+        header_offset: header_offset,
+        id: header.id,
+        version_number: header.version_number,
+        file_length: header.file_length,
+        file_offset: header.file_offset,
+        page_size: header.page_size,
+        entries_length: header.entries_length,
+        entries_checksum: header.entries_checksum,
+        header_checksum: header_checksum,
+        checksum_valid: seeded_checksum(0, sl) == header_checksum,
+    })
+}
+
+/// This function returns an iterator over the name and metadata of every
+/// file in the archive at `path`, like `FileArco::entries()`, without
+/// loading the whole entries table into memory. Entries are read from the
+/// file one at a time (in the order they are stored, not sorted), so
+/// listing an archive with millions of files uses constant memory.
+///
+/// The entries table checksum can only be verified once the whole table has
+/// been read, so a corrupted table is reported by a final
+/// `FileArcoV1Error::CorruptedEntriesTable` error, after the entries that
+/// were read. Callers should discard the listing if any item is an error.
+///
+/// # Arguments
+///
+/// * path - file path of archive file
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::path::Path;
+///
+/// let path = Path::new("testarchives/simple_v1.fac");
+///
+/// for entry in filearco::v1::stream_entries(path).ok().unwrap() {
+///     let (name, info) = entry.ok().unwrap();
+///     println!("{}: {} bytes", name, info.len());
+/// }
+/// ```
+pub fn stream_entries<P: AsRef<Path>>(path: P)
+                                      -> Result<impl Iterator<Item = Result<(String, EntryInfo)>>> {
+    let mut file = File::open(path)?;
+    let archive_length = file.seek(SeekFrom::End(0))?;
+
+    let (header, entries_offset) = locate_index_with(
+        archive_length,
+        0,
+        &mut OpenMetrics::default(),
+        &mut |offset, length| {
+            let mut buffer = vec![0u8; length];
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut buffer)?;
+
+            Ok(Cow::Owned(buffer))
+        }
+    )?;
+
+    file.seek(SeekFrom::Start(entries_offset))?;
+    let mut reader = ChecksumReader {
+        inner: BufReader::new(file.take(header.entries_length)),
+        checksum: 0,
+    };
+
+    // The entries table starts with the number of entries.
+    let remaining: u64 = deserialize_from(&mut reader, Bounded(header.entries_length))
+        .map_err(|_| Error::FileArcoV1(FileArcoV1Error::DeserializeFailed))?;
+
+    Ok(EntryStream {
+        reader: reader,
+        remaining: remaining,
+        header: header,
+        done: false,
+    })
+}
+
+/// This struct contains settings for `FileArco::make_with`.
+#[derive(Clone, Debug)]
+pub struct MakeOptions {
+    seed: u64,
+    compression: CompressionMode,
+    checksum: ChecksumKind,
+    alignment: u64,
+    producer: Option<String>,
+    signing_key: Option<Vec<u8>>,
+    inline_threshold: u64,
+}
+
+impl Default for MakeOptions {
+    fn default() -> Self {
+        MakeOptions {
+            seed: 0,
+            compression: CompressionMode::default(),
+            checksum: ChecksumKind::default(),
+            alignment: get_page_size() as u64,
+            producer: Some(default_producer()),
+            signing_key: None,
+            inline_threshold: 0,
+        }
+    }
+}
+
+impl MakeOptions {
+    /// This method returns the default options (i.e. CRC-64 checksums with
+    /// no seed, no compression, files aligned to this machine's page size,
+    /// this crate as the producer, no signature, and no inline files).
+    pub fn new() -> Self {
+        MakeOptions::default()
+    }
+
+    /// This method sets a secret value mixed into the file, header and
+    /// entries checksums. It is not stored in the archive, so it must be
+    /// passed to `FileArco::new_with_seed` to open the archive.
+    ///
+    /// Note that this only offers lightweight tamper evidence for closed
+    /// systems. CRC-64 is not a cryptographic hash, so a determined attacker
+    /// can still forge an archive.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// This method sets how the contents of each file are stored. With
+    /// `CompressionMode::Deflate` or `CompressionMode::Auto`, `make_with`
+    /// compresses each file twice: once to find its compressed length before
+    /// the entries table is written, and again while writing it, so
+    /// compressed contents are never held in memory.
+    pub fn compression(mut self, compression: CompressionMode) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// This method sets which checksum is stored for each file. With
+    /// `ChecksumKind::None`, `FileRef::is_valid()` cannot detect corrupted
+    /// files. The header and entries table are always checksummed with
+    /// CRC-64.
+    pub fn checksum(mut self, checksum: ChecksumKind) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// This method sets the alignment (in bytes) of every file in the
+    /// archive. It must be a power of two, and it is stored as the archive's
+    /// page size. Use a multiple of the largest page size of the machines
+    /// that will read the archive (e.g. 16384 for Apple Silicon).
+    pub fn alignment(mut self, alignment: u64) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// This method sets the name of the tool that created the archive, as
+    /// reported by `FileArco::producer()`. `None` leaves it out.
+    pub fn producer<S: Into<String>>(mut self, producer: Option<S>) -> Self {
+        self.producer = producer.map(Into::into);
+        self
+    }
+
+    /// This method sets the secret key used to sign the archive, as with
+    /// `FileArco::make_signed`. `None` leaves the archive unsigned.
+    pub fn signing_key<K: Into<Vec<u8>>>(mut self, key: Option<K>) -> Self {
+        self.signing_key = key.map(Into::into);
+        self
+    }
+
+    /// This method stores the contents of non-empty files shorter than
+    /// `threshold` bytes in the entries table instead of giving each one
+    /// its own aligned region, which saves space in archives with many tiny
+    /// files. Inline files are never compressed. 0 (the default) disables
+    /// this.
+    pub fn inline_threshold(mut self, threshold: u64) -> Self {
+        self.inline_threshold = threshold;
+        self
+    }
+}
+
+/// This enum describes the progress of `FileArco::make_with_progress`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressEvent<'a> {
+    /// The file `name` is about to be written. `index` counts from 0 up to
+    /// `total - 1`.
+    StartedFile {
+        name: &'a str,
+        index: usize,
+        total: usize,
+    },
+    /// The contents (and padding) of the current file were written. Files
+    /// that are empty or share the contents of an earlier file write 0
+    /// bytes.
+    BytesWritten(u64),
+}
+
+/// This enum specifies how an open archive accesses its contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// The archive file is mapped into memory.
+    Mmap,
+    /// The archive is held in a buffer in memory (see `FileArco::from_bytes`
+    /// and `FileArco::open_best`).
+    Buffered,
+}
+
+/// This enum specifies how `FileArco::make_with` stores file contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressionMode {
+    /// Files are stored as is and can be accessed without copying.
+    None,
+    /// Files are compressed with deflate and decompressed by `get`.
+    Deflate,
+    /// Files are compressed with deflate only if that makes them smaller
+    /// than 95% of their length. Others (e.g. fonts or images, which are
+    /// usually compressed already) are stored as is.
+    Auto,
+}
+
+impl Default for CompressionMode {
+    fn default() -> Self {
+        CompressionMode::None
+    }
+}
+
+/// This enum specifies which checksum `FileArco::make_with` stores for each
+/// file. Readers use the kind recorded in the header, so archives with any
+/// kind are opened the same way. The header and entries table always use
+/// CRC-64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChecksumKind {
+    /// Files are checksummed with CRC-64/ISO (mixed with the seed, if any).
+    Crc64,
+    /// Files are checksummed with CRC-32/IEEE, as used by zip and gzip
+    /// (mixed with the low 32 bits of the seed, if any).
+    Crc32,
+    /// Files (and the entries table) are hashed with SHA-256 (mixed with the
+    /// seed, if any), which detects deliberate changes as well as
+    /// corruption. This only provides integrity, not authentication: anyone
+    /// able to change an archive can also recompute its digests.
+    Sha256,
+    /// Files are not checksummed.
+    None,
+}
+
+impl Default for ChecksumKind {
+    fn default() -> Self {
+        ChecksumKind::Crc64
+    }
+}
+
+impl ChecksumKind {
+    fn from_flags(flags: u64) -> Self {
+        if flags & FLAG_NO_CHECKSUMS != 0 {
+            ChecksumKind::None
+        }
+        else if flags & FLAG_CRC32 != 0 {
+            ChecksumKind::Crc32
+        }
+        else if flags & FLAG_SHA256 != 0 {
+            ChecksumKind::Sha256
+        }
+        else {
+            ChecksumKind::Crc64
+        }
+    }
+
+    // This method continues computing `checksum` over `bytes`. Passing a
+    // seed as `checksum` computes a seeded checksum. A SHA-256 digest cannot
+    // be continued from a u64, so those are computed with `compute()`.
+    fn update(&self, checksum: u64, bytes: &[u8]) -> u64 {
+        match *self {
+            ChecksumKind::Crc64 => update_checksum(checksum, &ISO_TABLE, bytes),
+            ChecksumKind::Crc32 => update_crc32(checksum as u32, &IEEE_TABLE, bytes) as u64,
+            ChecksumKind::Sha256 | ChecksumKind::None => 0,
+        }
+    }
+
+    // This method returns the checksum and digest stored for a file with
+    // contents `bytes`. The checksum of a file with a digest is the first 8
+    // bytes of it, so identical files can still be found by checksum.
+    fn compute(&self, seed: u64, bytes: &[u8]) -> (u64, Option<[u8; DIGEST_SIZE]>) {
+        let mut hasher = ContentsHasher::new(*self, seed);
+        hasher.update(bytes);
+
+        hasher.finish()
+    }
+}
+
+// This struct computes the checksum and digest of file contents that are
+// read in chunks, like `ChecksumKind::compute()` does for whole files.
+struct ContentsHasher {
+    kind: ChecksumKind,
+    checksum: u64,
+    digest: Option<Sha256>,
+}
+
+impl ContentsHasher {
+    fn new(kind: ChecksumKind, seed: u64) -> Self {
+        let digest = if kind == ChecksumKind::Sha256 {
+            let mut digest = Sha256::new();
+
+            if seed != 0 {
+                digest.update(seed.to_le_bytes());
+            }
+
+            Some(digest)
+        }
+        else {
+            None
+        };
+
+        ContentsHasher {
+            kind: kind,
+            checksum: seed,
+            digest: digest,
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self.digest {
+            Some(ref mut digest) => digest.update(bytes),
+            None => self.checksum = self.kind.update(self.checksum, bytes),
+        }
+    }
+
+    fn finish(self) -> (u64, Option<[u8; DIGEST_SIZE]>) {
+        match self.digest {
+            Some(digest) => {
+                let mut bytes = [0u8; DIGEST_SIZE];
+                bytes.copy_from_slice(&digest.finalize());
+
+                let mut checksum = [0u8; 8];
+                checksum.copy_from_slice(&bytes[..8]);
+
+                (u64::from_le_bytes(checksum), Some(bytes))
+            },
+            None => (self.checksum, None),
+        }
+    }
+}
+
+/// This is the name, offset, and length of each file concatenated by
+/// `FileArco::pack_response`.
+pub type PackIndex = Vec<(String, u64, u64)>;
+
+/// This struct contains settings for `FileArco::extract_to_with`.
+#[derive(Clone, Debug)]
+pub struct ExtractOptions {
+    verify: bool,
+    on_corrupt: OnCorrupt,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        ExtractOptions {
+            verify: true,
+            on_corrupt: OnCorrupt::default(),
+        }
+    }
+}
+
+impl ExtractOptions {
+    /// This method returns the default options (i.e. validate every file
+    /// and abort at the first corrupted one).
+    pub fn new() -> Self {
+        ExtractOptions::default()
+    }
+
+    /// This method sets whether each file is validated against its checksum
+    /// before it is written. If `false`, `on_corrupt` is ignored.
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// This method sets what happens when a file fails validation.
+    pub fn on_corrupt(mut self, on_corrupt: OnCorrupt) -> Self {
+        self.on_corrupt = on_corrupt;
+        self
+    }
+}
+
+/// This enum specifies what `FileArco::extract_to_with` does with a file
+/// that fails validation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnCorrupt {
+    /// The file is not written and extraction continues.
+    Skip,
+    /// Extraction stops with `FileArcoV1Error::CorruptedFile`.
+    Abort,
+    /// The file is written as is.
+    WriteAnyway,
+}
+
+impl Default for OnCorrupt {
+    fn default() -> Self {
+        OnCorrupt::Abort
+    }
+}
+
+/// This struct is a cloneable, `Send` and `Sync` handle to an open archive.
+/// It is created with `FileArco::handle()`.
+#[derive(Clone)]
+pub struct ArchiveHandle {
+    inner: Arc<Inner>,
+}
+
+impl ArchiveHandle {
+    // `FileArco` holds nothing but the shared state, so a handle can lend
+    // out an equivalent archive to reuse its methods.
+    fn archive(&self) -> FileArco {
+        FileArco {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// This method retrieves a file from the archive, if it exists.
+    ///
+    /// # Arguments
+    ///
+    /// * file_path - name of file to retrieve
+    pub fn get<P: AsRef<str>>(&self, file_path: P) -> Option<FileRef> {
+        self.archive().get(file_path)
+    }
+
+    /// This method checks whether a file is stored in the archive.
+    ///
+    /// # Arguments
+    ///
+    /// * file_path - name of file to look up
+    pub fn contains<P: AsRef<str>>(&self, file_path: P) -> bool {
+        self.archive().contains(file_path)
+    }
+}
+
+/// Overlay contents that hide a file of the underlying archive.
+pub const OVERLAY_MASK: &'static [u8] = b"\0FILEARCO-OVERLAY-MASK\0";
+
+/// This struct serves files from an in-memory overlay on top of a
+/// `FileArco` archive. It is created with `FileArco::with_overlay()`.
+pub struct OverlayArchive {
+    archive: FileArco,
+    // `None` hides the file in the archive.
+    overlay: HashMap<String, Option<Vec<u8>>>,
+}
+
+impl OverlayArchive {
+    /// This method retrieves a file from the overlay if present, and
+    /// otherwise from the underlying archive.
+    ///
+    /// # Arguments
+    ///
+    /// * file_path - name of file to retrieve
+    pub fn get<P: AsRef<str>>(&self, file_path: P) -> Option<OverlayRef<'_>> {
+        match self.overlay.get(file_path.as_ref()) {
+            Some(&Some(ref contents)) => Some(OverlayRef::Overlay(contents)),
+            Some(&None) => None,
+            None => self.archive.get(file_path).map(OverlayRef::Archive),
+        }
+    }
+
+    /// This method returns the sorted names of every visible file in either
+    /// the overlay or the underlying archive.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self.archive.inner.entries.files.keys()
+            .filter(|name| !self.overlay.contains_key(*name))
+            .chain(self.overlay.iter()
+                   .filter(|&(_, contents)| contents.is_some())
+                   .map(|(name, _)| name))
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+
+        names
+    }
+
+    /// This method returns the underlying archive.
+    pub fn archive(&self) -> &FileArco {
+        &self.archive
+    }
+}
+
+/// This enum holds a file retrieved from an `OverlayArchive`.
+pub enum OverlayRef<'a> {
+    /// File from the overlay
+    Overlay(&'a [u8]),
+    /// File from the underlying archive
+    Archive(FileRef),
+}
+
+impl<'a> OverlayRef<'a> {
+    /// This method retrieves a byte array representing the contents of the
+    /// file.
+    pub fn as_slice(&self) -> &[u8] {
+        match *self {
+            OverlayRef::Overlay(contents) => contents,
+            OverlayRef::Archive(ref fileref) => fileref.as_slice(),
+        }
+    }
+
+    /// This method returns the length (in bytes) of the file.
+    pub fn len(&self) -> u64 {
+        self.as_slice().len() as u64
+    }
+}
+
+/// This struct serves the files of a `FileArco` archive under a directory
+/// prefix. It is created with `FileArco::rerooted()`.
+pub struct RerootedArchive {
+    archive: FileArco,
+    // Empty, or ends with a slash.
+    prefix: String,
+}
+
+impl RerootedArchive {
+    /// This method retrieves a file from the underlying archive. Names
+    /// outside of the prefix are not found.
+    ///
+    /// # Arguments
+    ///
+    /// * file_path - name of file to retrieve, including the prefix
+    pub fn get<P: AsRef<str>>(&self, file_path: P) -> Option<FileRef> {
+        let file_path = normalize_separators(file_path.as_ref());
+
+        file_path.strip_prefix(self.prefix.as_str())
+            .and_then(|name| self.archive.get(name))
+    }
+
+    /// This method returns the sorted names of every file, including the
+    /// prefix.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.archive.inner.entries.files.keys()
+            .map(|name| format!("{}{}", self.prefix, name))
+            .collect::<Vec<_>>();
+        names.sort();
+
+        names
+    }
+
+    /// This method returns the prefix, which is empty or ends with a slash.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// This method returns the underlying archive.
+    pub fn archive(&self) -> &FileArco {
+        &self.archive
+    }
+}
+
+/// This struct represents an archive opened with `FileArco::open_paged()`,
+/// which reads files on demand instead of mapping the archive.
+pub struct PagedArchive {
+    file: PagedFile,
+    file_offset: u64,
+    revision: u64,
+    version_number: u64,
+    // Whether the header has the compressed flag set
+    compressed: bool,
+    // Algorithm of the file checksums
+    checksum_kind: ChecksumKind,
+    entries: Entries,
+    // Buffers released by earlier reads
+    pool: Arc<BufferPool>,
+}
+
+impl PagedArchive {
+    /// This method reads a file from the archive, if it exists. Compressed
+    /// files are decompressed, so the `FileBuf` holds the same bytes as
+    /// `FileRef::as_slice()` would. A compressed file that fails to
+    /// decompress returns an error.
+    ///
+    /// # Arguments
+    ///
+    /// * file_path - name of file to retrieve
+    pub fn get<P: AsRef<str>>(&self, file_path: P) -> Result<Option<FileBuf>> {
+        let file_path = normalize_separators(file_path.as_ref());
+
+        let entry = match self.entries.files.get(file_path.as_ref()) {
+            Some(entry) => entry,
+            None => {
+                return Ok(None);
+            },
+        };
+
+        let mut stored = self.pool.take();
+
+        match entry.inline {
+            Some(ref inline) => stored.extend_from_slice(inline),
+            None => {
+                stored.resize(entry.stored_length as usize, 0);
+                self.file.read_exact_at(&mut stored, self.file_offset + entry.offset)?;
+            },
+        }
+
+        let contents = if self.compressed && entry.compressed {
+            let mut contents = self.pool.take();
+            let inflated = inflate_into(&stored, entry.length, &mut contents);
+            self.pool.give(stored);
+            inflated?;
+
+            contents
+        }
+        else {
+            stored
+        };
+
+        Ok(Some(FileBuf {
+            contents: contents,
+            pool: self.pool.clone(),
+            checksum: entry.checksum,
+            digest: entry.digest,
+            checksum_kind: self.checksum_kind,
+            mode: if self.revision >= 1 { Some(entry.mode) } else { None },
+            modified: entry.modified,
+        }))
+    }
+
+    /// This method returns the sorted names of every file.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self.entries.files.keys()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+
+        names
+    }
+
+    /// This method returns the number of files in the archive.
+    pub fn file_count(&self) -> usize {
+        self.entries.files.len()
+    }
+
+    /// This method returns the version number from the header.
+    pub fn version_number(&self) -> u64 {
+        self.version_number
+    }
+}
+
+/// This struct holds a copy of a file read by `PagedArchive::get()`.
+/// Unlike a `FileRef`, it owns its contents.
+pub struct FileBuf {
+    contents: Vec<u8>,
+    // Where `contents` goes when the `FileBuf` is dropped
+    pool: Arc<BufferPool>,
+    checksum: u64,
+    digest: Option<[u8; DIGEST_SIZE]>,
+    checksum_kind: ChecksumKind,
+    mode: Option<u32>,
+    modified: u64,
+}
+
+impl FileBuf {
+    /// This method ensures the file contents have not been corrupted. It
+    /// always returns `true` for files without a checksum.
+    pub fn is_valid(&self) -> bool {
+        if self.checksum_kind == ChecksumKind::None {
+            return true;
+        }
+
+        let (checksum_computed, digest_computed) = self.checksum_kind
+            .compute(0, &self.contents);
+
+        self.checksum == checksum_computed && self.digest == digest_computed
+    }
+
+    /// This method returns the contents of the file.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.contents
+    }
+
+    /// This method returns the contents of the file, consuming it. The
+    /// buffer is not returned to the pool.
+    pub fn into_vec(mut self) -> Vec<u8> {
+        mem::replace(&mut self.contents, Vec::new())
+    }
+
+    /// This method returns the length (in bytes) of the file.
+    pub fn len(&self) -> u64 {
+        self.contents.len() as u64
+    }
+
+    /// This method returns whether the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+
+    /// This method returns the Unix mode of the file, or `None` if the
+    /// archive predates file modes.
+    pub fn mode(&self) -> Option<u32> {
+        self.mode
+    }
+
+    /// This method returns the modification time of the file, or `None` if
+    /// it is unknown.
+    pub fn modified_time(&self) -> Option<SystemTime> {
+        if self.modified == 0 {
+            None
+        }
+        else {
+            Some(UNIX_EPOCH + Duration::from_secs(self.modified))
+        }
+    }
+}
+
+impl Drop for FileBuf {
+    fn drop(&mut self) {
+        self.pool.give(mem::replace(&mut self.contents, Vec::new()));
+    }
+}
+
+// This is the archive file of a `PagedArchive`. On Unix, reads do not move
+// the file position, so concurrent lookups do not wait for each other.
+#[cfg(unix)]
+struct PagedFile(File);
+
+#[cfg(unix)]
+impl PagedFile {
+    fn new(file: File) -> Self {
+        PagedFile(file)
+    }
+
+    fn read_exact_at(&self, buffer: &mut [u8], offset: u64) -> io::Result<()> {
+        FileExt::read_exact_at(&self.0, buffer, offset)
+    }
+}
+
+// Elsewhere lookups seek, so they take turns.
+#[cfg(not(unix))]
+struct PagedFile(Mutex<File>);
+
+#[cfg(not(unix))]
+impl PagedFile {
+    fn new(file: File) -> Self {
+        PagedFile(Mutex::new(file))
+    }
+
+    fn read_exact_at(&self, buffer: &mut [u8], offset: u64) -> io::Result<()> {
+        // A panic while holding the lock cannot leave the file in a bad
+        // state, since every read seeks first.
+        let mut file = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buffer)
+    }
+}
+
+// This holds buffers released by earlier reads, so later reads can reuse
+// them instead of allocating. At most `limit` buffers are kept.
+struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    limit: usize,
+    // Number of buffers `take()` had to allocate
+    #[cfg(test)]
+    allocations: AtomicUsize,
+}
+
+impl BufferPool {
+    fn new(limit: usize) -> Self {
+        BufferPool {
+            buffers: Mutex::new(Vec::with_capacity(limit)),
+            limit: limit,
+            #[cfg(test)]
+            allocations: AtomicUsize::new(0),
+        }
+    }
+
+    // This method returns an empty buffer, reusing a pooled one if possible.
+    fn take(&self) -> Vec<u8> {
+        let pooled = if self.limit > 0 {
+            self.lock().pop()
+        }
+        else {
+            None
+        };
+
+        pooled.unwrap_or_else(|| {
+            #[cfg(test)]
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+
+            Vec::new()
+        })
+    }
+
+    // This method keeps `buffer` for a later `take()`, unless the pool is
+    // full.
+    fn give(&self, mut buffer: Vec<u8>) {
+        if self.limit == 0 || buffer.capacity() == 0 {
+            return;
+        }
+
+        buffer.clear();
+
+        let mut buffers = self.lock();
+        if buffers.len() < self.limit {
+            buffers.push(buffer);
+        }
+    }
+
+    // The pool only holds spare buffers, so it is still usable if another
+    // thread panicked while holding the lock.
+    fn lock<'a>(&'a self) -> MutexGuard<'a, Vec<Vec<u8>>> {
+        self.buffers.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// This enum specifies where the header and entries table are placed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// The header and entries table precede the file contents.
+    Standard,
+    /// The file contents come first, followed by the entries table, the
+    /// header, and a fixed-size footer pointing to the header. This allows
+    /// creating an archive in a single pass without spooling.
+    Trailer,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::Standard
+    }
+}
+
+/// This struct contains settings for an `ArchiveWriter`.
+#[derive(Clone, Debug, Default)]
+pub struct WriterOptions {
+    spool_path: Option<PathBuf>,
+    layout: Layout,
+}
+
+impl WriterOptions {
+    /// This method returns the default options (i.e. spool file contents
+    /// in memory).
+    pub fn new() -> Self {
+        WriterOptions::default()
+    }
+
+    /// This method sets the path of a temporary file used to spool file
+    /// contents until `ArchiveWriter::finish()` is called. If `None` (the
+    /// default), file contents are spooled in memory.
+    ///
+    /// **NOTE:** The spool file is truncated when the writer is created and
+    /// removed when the writer finishes.
+    ///
+    /// # Arguments
+    ///
+    /// * spool_path - path of the temporary spool file
+    pub fn spool_path<P: AsRef<Path>>(mut self, spool_path: Option<P>) -> Self {
+        self.spool_path = spool_path.map(|p| p.as_ref().to_path_buf());
+        self
+    }
+
+    /// This method sets the layout of the archive. With `Layout::Trailer`,
+    /// file contents are written straight to the output and no spool is used.
+    ///
+    /// # Arguments
+    ///
+    /// * layout - layout of the archive
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+}
+
+/// This struct incrementally creates a FileArco v1 archive from files
+/// added one at a time.
+///
+/// Since the header and entries table precede the file contents in the
+/// standard layout, the contents are spooled until `finish()` writes the
+/// complete archive. The trailer layout needs no spool.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate filearco;
+///
+/// use std::io;
+///
+/// let options = filearco::v1::WriterOptions::new();
+/// let mut writer = filearco::v1::ArchiveWriter::new(io::sink(), options).ok().unwrap();
+///
+/// writer.add_file("hello.txt", &b"Hello, World!"[..]).ok().unwrap();
+/// writer.finish().ok().unwrap();
+/// ```
+pub struct ArchiveWriter<H: Write> {
+    out_file: H,
+    spool: Option<Box<Spool>>,
+    spool_path: Option<PathBuf>,
+    entries: Entries,
+    offset: u64,
+    // Page size recorded in the header, to which every file is aligned
+    page_size: u64,
+}
+
+impl<H: Write> ArchiveWriter<H> {
+    /// This method creates a new `ArchiveWriter` that will write the finished
+    /// archive to `out_file`.
+    ///
+    /// # Arguments
+    ///
+    /// * out_file - destination of the archive
+    ///
+    /// * options - settings for the writer
+    pub fn new(out_file: H, options: WriterOptions) -> Result<Self> {
+        let spool: Option<Box<Spool>> = match (options.layout, &options.spool_path) {
+            (Layout::Trailer, _) => None,
+            (Layout::Standard, &Some(ref path)) => {
+                Some(Box::new(OpenOptions::new()
+                              .read(true)
+                              .write(true)
+                              .create(true)
+                              .truncate(true)
+                              .open(path)?))
+            },
+            (Layout::Standard, &None) => {
+                Some(Box::new(Cursor::new(Vec::<u8>::new())))
+            },
+        };
+
+        let spool_path = match spool {
+            Some(_) => options.spool_path,
+            None => None,
+        };
+
+        Ok(ArchiveWriter {
+            out_file: out_file,
+            spool: spool,
+            spool_path: spool_path,
+            entries: Entries {
+                files: HashMap::new(),
+                producer: Some(default_producer()),
+            },
+            offset: 0,
+            page_size: get_page_size() as u64,
+        })
+    }
+
+    /// This method adds the contents of `reader` to the archive as `name`.
+    ///
+    /// # Arguments
+    ///
+    /// * name - path of the file inside the archive
+    ///
+    /// * reader - source of the file contents
+    pub fn add_file<P: AsRef<str>, R: Read>(&mut self,
+                                            name: P,
+                                            mut reader: R) -> Result<()> {
+        let name = name.as_ref();
+
+        if self.entries.files.contains_key(name) {
+            return Err(Error::FileArcoV1(
+                FileArcoV1Error::DuplicateEntry(String::from(name))
+            ));
+        }
+
+        // Copy contents to spool (or straight to the output) while computing
+        // their checksum.
+        let mut buffer = [0u8; COPY_BUFFER_SIZE];
+        let mut length = 0u64;
+        let mut contents_checksum = 0u64;
+
+        loop {
+            let count = reader.read(&mut buffer)?;
+
+            if count == 0 {
+                break;
+            }
+
+            contents_checksum = update_checksum(contents_checksum,
+                                                &ISO_TABLE,
+                                                &buffer[..count]);
+            self.payload_writer().write_all(&buffer[..count])?;
+            length = length + count as u64;
+        }
+
+        // Pad with zeros to ensure next file begins at a multiple of `page_size`.
+        let aligned_length = get_aligned_length(length, self.page_size);
+        let padding: Vec<u8> = vec![0u8; (aligned_length - length) as usize];
+        self.payload_writer().write_all(&padding)?;
+
+        self.entries.files.insert(String::from(name), Entry {
+            offset: self.offset,
+            length: length,
+            aligned_length: aligned_length,
+            checksum: contents_checksum,
+            mode: DEFAULT_MODE,
+            modified: 0,
+            raw_name: None,
+            compressed: false,
+            stored_length: length,
+            content_type: guess_content_type(name).map(String::from),
+            digest: None,
+            inline: None,
+        });
+        self.offset = self.offset + aligned_length;
+
+        Ok(())
+    }
+
+    /// This method writes the header, entries table, and spooled file
+    /// contents to the output and returns it.
+    pub fn finish(mut self) -> Result<H> {
+        match self.spool {
+            Some(ref mut spool) => {
+                write_prelude(&mut self.out_file, &self.entries, self.entries.flags(),
+                              self.page_size, 0)?;
+
+                spool.seek(SeekFrom::Start(0))?;
+                io::copy(&mut Read::by_ref(spool).take(self.offset),
+                         &mut self.out_file)?;
+            },
+            None => {
+                write_trailer(&mut self.out_file,
+                              &self.entries,
+                              self.entries.flags(),
+                              self.page_size,
+                              0,
+                              self.offset,
+                              0)?;
+            },
+        }
+
+        self.out_file.flush()?;
+
+        if let Some(ref path) = self.spool_path {
+            remove_file(path)?;
+        }
+
+        Ok(self.out_file)
+    }
+
+    // This method returns where file contents should be written.
+    fn payload_writer(&mut self) -> &mut Write {
+        match self.spool {
+            Some(ref mut spool) => spool,
+            None => &mut self.out_file,
+        }
+    }
+}
+
+// This trait is implemented by any type that can hold spooled file contents.
+trait Spool: Read + Write + Seek {}
+
+impl<T: Read + Write + Seek> Spool for T {}
+
+/// This struct reads the contents of a `FileRef`. It is created with
+/// `FileRef::reader()`.
+pub struct FileRefReader<'a> {
+    contents: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Read for FileRefReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = &self.contents[self.position..];
+        let length = cmp::min(buf.len(), remaining.len());
+
+        buf[..length].copy_from_slice(&remaining[..length]);
+        self.position = self.position + length;
+
+        Ok(length)
+    }
+}
+
+// This reader computes the CRC-64 checksum of the bytes read from `inner`.
+struct ChecksumReader<R: Read> {
+    inner: R,
+    checksum: u64,
+}
+
+impl<R: Read> Read for ChecksumReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.checksum = update_checksum(self.checksum, &ISO_TABLE, &buf[..read]);
+
+        Ok(read)
+    }
+}
+
+// This iterator deserializes the entries table read by `ChecksumReader` one
+// entry at a time for `stream_entries()`.
+struct EntryStream {
+    reader: ChecksumReader<BufReader<io::Take<File>>>,
+    // Number of entries not read yet
+    remaining: u64,
+    header: Header,
+    done: bool,
+}
+
+impl EntryStream {
+    fn next_entry(&mut self) -> Result<(String, EntryInfo)> {
+        let limit = Bounded(self.header.entries_length);
+        let (name, info) = if self.header.revision() == 0 {
+            let (name, entry): (String, LegacyEntry) = deserialize_from(&mut self.reader, limit)
+                .map_err(|_| Error::FileArcoV1(FileArcoV1Error::DeserializeFailed))?;
+
+            (name, EntryInfo {
+                offset: entry.offset,
+                length: entry.length,
+                aligned_length: entry.aligned_length,
+                checksum: entry.checksum,
+            })
+        }
+        else {
+            let (name, entry): (String, Entry) = deserialize_from(&mut self.reader, limit)
+                .map_err(|_| Error::FileArcoV1(FileArcoV1Error::DeserializeFailed))?;
+
+            (name, EntryInfo::from(&entry))
+        };
+
+        Ok((normalize_separators(&name).into_owned(), info))
+    }
+
+    // This method reads the rest of the entries table (e.g. the producer)
+    // and validates its checksum.
+    fn finish(&mut self) -> Result<()> {
+        io::copy(&mut self.reader, &mut io::sink())?;
+
+        if self.reader.checksum != self.header.entries_checksum {
+            return Err(Error::FileArcoV1(FileArcoV1Error::CorruptedEntriesTable));
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for EntryStream {
+    type Item = Result<(String, EntryInfo)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if self.remaining == 0 {
+            self.done = true;
+
+            return match self.finish() {
+                Ok(()) => None,
+                Err(err) => Some(Err(err)),
+            };
+        }
+
+        self.remaining = self.remaining - 1;
+
+        let result = self.next_entry();
+        if result.is_err() {
+            self.done = true;
+        }
+
+        Some(result)
+    }
+}
+
+// This writer counts the bytes written to `inner`.
+struct CountingWriter<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count = self.count + written as u64;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// This writer computes an HMAC of the bytes written to `inner`.
+struct SigningWriter<W: Write> {
+    inner: W,
+    mac: Hmac<Sha256>,
+}
+
+impl<W: Write> Write for SigningWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.mac.update(&buf[..written]);
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// This struct reports how long each step of `FileArco::open_timed()` took.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpenMetrics {
+    map_time: Duration,
+    checksum_time: Duration,
+    deserialize_time: Duration,
+    entries_length: u64,
+}
+
+impl OpenMetrics {
+    /// This method returns the time spent memory mapping the archive.
+    pub fn map_time(&self) -> Duration {
+        self.map_time
+    }
+
+    /// This method returns the time spent validating the checksums of the
+    /// header and entries table.
+    pub fn checksum_time(&self) -> Duration {
+        self.checksum_time
+    }
+
+    /// This method returns the time spent deserializing the header and
+    /// entries table.
+    pub fn deserialize_time(&self) -> Duration {
+        self.deserialize_time
+    }
+
+    /// This method returns the length (in bytes) of the entries table.
+    pub fn entries_len(&self) -> u64 {
+        self.entries_length
+    }
+}
+
+/// This struct contains the metadata of a file stored in an archive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EntryInfo {
+    offset: u64,
+    length: u64,
+    aligned_length: u64,
+    checksum: u64,
+}
+
+impl EntryInfo {
+    /// This method returns the offset of the file relative to the start of
+    /// the file contents section (not the start of the archive).
+    ///
+    /// **NOTE:** Empty files take up no space, so they share their offset
+    /// with the next file in the archive (or the end of the file contents
+    /// section). Files with identical contents share one offset too.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// This method returns the length of the file.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// This method returns the length of the file padded to a multiple of
+    /// the archive's page size.
+    pub fn aligned_len(&self) -> u64 {
+        self.aligned_length
+    }
+
+    /// This method returns the checksum of the file contents.
+    pub fn checksum(&self) -> u64 {
+        self.checksum
+    }
+}
+
+impl<'a> From<&'a Entry> for EntryInfo {
+    fn from(entry: &'a Entry) -> Self {
+        EntryInfo {
+            offset: entry.offset,
+            length: entry.length,
+            aligned_length: entry.aligned_length,
+            checksum: entry.checksum,
+        }
+    }
+}
+
+/// This struct represents a directory in the tree returned by
+/// `FileArco::tree()`. Its directories and files are sorted by name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DirNode {
+    directories: BTreeMap<String, DirNode>,
+    files: BTreeMap<String, u64>,
+}
+
+impl DirNode {
+    /// This method returns an iterator over the name and node of every
+    /// directory directly inside this one.
+    pub fn directories(&self) -> impl Iterator<Item = (&str, &DirNode)> {
+        self.directories.iter().map(|(name, node)| (name.as_str(), node))
+    }
+
+    /// This method returns an iterator over the name and length of every
+    /// file directly inside this directory.
+    pub fn files(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.files.iter().map(|(name, &length)| (name.as_str(), length))
+    }
+
+    /// This method returns the directory called `name` directly inside this
+    /// one, if any.
+    pub fn directory(&self, name: &str) -> Option<&DirNode> {
+        self.directories.get(name)
+    }
+
+    /// This method returns the length of the file called `name` directly
+    /// inside this directory, if any.
+    pub fn file_len(&self, name: &str) -> Option<u64> {
+        self.files.get(name).cloned()
+    }
+}
+
+/// This struct compares the page size of an archive with that of this
+/// system. It is returned by `FileArco::alignment_report()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AlignmentReport {
+    archive_page_size: u64,
+    host_page_size: u64,
+    backend: Backend,
+}
+
+impl AlignmentReport {
+    /// This method returns the page size recorded in the archive.
+    pub fn archive_page_size(&self) -> u64 {
+        self.archive_page_size
+    }
+
+    /// This method returns the memory page size of this system.
+    pub fn host_page_size(&self) -> u64 {
+        self.host_page_size
+    }
+
+    /// This method returns whether the archive was made with the page size
+    /// of this system, i.e. whether `make` would lay it out the same way.
+    pub fn matches_host(&self) -> bool {
+        self.archive_page_size == self.host_page_size
+    }
+
+    /// This method returns whether every file starts on a page boundary of
+    /// this system. This requires the archive to be mapped (buffers from
+    /// `FileArco::from_bytes` may not be page aligned) and its page size to
+    /// be a multiple of this system's.
+    pub fn is_aligned(&self) -> bool {
+        self.backend == Backend::Mmap && self.archive_page_size % self.host_page_size == 0
+    }
+}
+
+/// This struct holds the unvalidated header fields returned by
+/// `read_raw_header()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawHeader {
+    header_offset: u64,
+    id: [u8; 8],
+    version_number: u64,
+    file_length: u64,
+    file_offset: u64,
+    page_size: u64,
+    entries_length: u64,
+    entries_checksum: u64,
+    header_checksum: u64,
+    checksum_valid: bool,
+}
+
+impl RawHeader {
+    /// This method returns the offset of the header from the start of the
+    /// archive (0 unless the archive uses the trailer layout).
+    pub fn header_offset(&self) -> u64 {
+        self.header_offset
+    }
+
+    /// This method returns the identifier, which is `b"FILEARCO"` for
+    /// valid archives.
+    pub fn id(&self) -> [u8; 8] {
+        self.id
+    }
+
+    /// This method returns the version number, including the revision and
+    /// flags.
+    pub fn version_number(&self) -> u64 {
+        self.version_number
+    }
+
+    /// This method returns the length of the archive recorded in the header.
+    pub fn file_length(&self) -> u64 {
+        self.file_length
+    }
+
+    /// This method returns the offset of the file contents section.
+    pub fn file_offset(&self) -> u64 {
+        self.file_offset
+    }
+
+    /// This method returns the page size the archive was made with.
+    pub fn page_size(&self) -> u64 {
+        self.page_size
+    }
+
+    /// This method returns the length of the entries table.
+    pub fn entries_length(&self) -> u64 {
+        self.entries_length
+    }
+
+    /// This method returns the checksum of the entries table.
+    pub fn entries_checksum(&self) -> u64 {
+        self.entries_checksum
+    }
+
+    /// This method returns the header checksum stored after the header.
+    pub fn header_checksum(&self) -> u64 {
+        self.header_checksum
+    }
+
+    /// This method returns whether the stored header checksum matches the
+    /// header (for archives made without a seed).
+    pub fn is_checksum_valid(&self) -> bool {
+        self.checksum_valid
+    }
+}
+
+/// This struct lists the differences between two archives found by `diff()`.
+/// Every list of names is sorted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArchiveDiff {
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<String>,
+}
+
+impl ArchiveDiff {
+    /// This method returns the names of files only in the new archive.
+    pub fn added(&self) -> &[String] {
+        &self.added
+    }
+
+    /// This method returns the names of files only in the old archive.
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    /// This method returns the names of files in both archives whose
+    /// contents differ.
+    pub fn changed(&self) -> &[String] {
+        &self.changed
+    }
+
+    /// This method returns whether both archives hold the same files.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// This struct lists the results of `FileArco::verify_against_manifest()`.
+/// Every list of names is sorted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    valid: Vec<String>,
+    corrupted: Vec<String>,
+    missing: Vec<String>,
+}
+
+impl VerifyReport {
+    /// This method returns the names of files whose contents match the
+    /// manifest.
+    pub fn valid(&self) -> &[String] {
+        &self.valid
+    }
+
+    /// This method returns the names of files whose contents do not match
+    /// the manifest.
+    pub fn corrupted(&self) -> &[String] {
+        &self.corrupted
+    }
+
+    /// This method returns the names of files in the manifest but not in
+    /// the archive.
+    pub fn missing(&self) -> &[String] {
+        &self.missing
+    }
+
+    /// This method returns whether every file in the manifest is in the
+    /// archive with matching contents.
+    pub fn is_ok(&self) -> bool {
+        self.corrupted.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// This struct represents a reference to a slice of memory containing
+/// a requested file from the archive.
+#[allow(dead_code)]
+pub struct FileRef {
+    address: *const u8,
+    // Whether `address` points to deflated contents
+    compressed: bool,
+    // Contents of a compressed file, inflated on first use
+    inflated: Inflated,
+    length: u64,
+    stored_length: u64,
+    aligned_length: u64,
+    checksum: u64,
+    mode: Option<u32>,
+    modified: u64,
+    content_type: Option<String>,
+    digest: Option<[u8; DIGEST_SIZE]>,
+    // Result of the first `as_slice_checked()`: `UNCHECKED`, `VALID` or
+    // `INVALID`.
+    validated: AtomicU8,
+    // Holding a reference to the memory mapped file ensures it will not be
+    // unmapped until we finish using it.
+    inner: Arc<Inner>,
+}
+
+const UNCHECKED: u8 = 0;
+const VALID: u8 = 1;
+const INVALID: u8 = 2;
+
+// This holds the result of inflating a compressed file, which is computed
+// the first time it is needed and then shared by every later call.
+struct Inflated {
+    once: Once,
+    contents: UnsafeCell<Option<io::Result<Vec<u8>>>>,
+}
+
+impl Inflated {
+    fn new() -> Self {
+        Inflated {
+            once: Once::new(),
+            contents: UnsafeCell::new(None),
+        }
+    }
+
+    // This method runs `inflate` on the first call and returns its result
+    // on every call.
+    fn get_or_init<F>(&self, inflate: F) -> &io::Result<Vec<u8>>
+        where F: FnOnce() -> io::Result<Vec<u8>> {
+        // `contents` is only written inside `call_once`, which finishes
+        // before any caller gets past it.
+        self.once.call_once(|| unsafe {
+            *self.contents.get() = Some(inflate());
+        });
+
+        unsafe { (*self.contents.get()).as_ref().unwrap() }
+    }
+}
+
+// `address` points into the storage owned by `inner`, which is never
+// modified or freed while this `FileRef` holds its reference. Sharing or
+// sending it between threads is as safe as sharing a `&[u8]`.
+unsafe impl Send for FileRef {}
+unsafe impl Sync for FileRef {}
+
+impl FileRef {
+    /// This method ensures the file contents have not been corrupted. It
+    /// always returns `true` for files without a checksum (see
+    /// `has_checksum()`), unless they are compressed and fail to
+    /// decompress.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::mem;
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
+    /// assert!(cargo_toml.is_valid());
+    /// ```
+    pub fn is_valid(&self) -> bool {
+        let sl = match self.contents() {
+            Ok(sl) => sl,
+            Err(_) => return false,
+        };
+
+        if !self.has_checksum() {
+            return true;
+        }
+
+        let (checksum_computed, digest_computed) = self.inner.checksum_kind
+            .compute(self.inner.seed, sl);
+
+        self.checksum == checksum_computed && self.digest == digest_computed
+    }
+ 
+    /// This method returns whether the file has a checksum, i.e. whether
+    /// `is_valid()` actually checks its contents. Archives made with
+    /// `ChecksumKind::None` have no file checksums.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
+    /// assert!(cargo_toml.has_checksum());
+    /// ```
+    pub fn has_checksum(&self) -> bool {
+        self.inner.checksum_kind != ChecksumKind::None
+    }
+
+    /// This method retrieves the contents of a `FileRef` like `as_slice()`,
+    /// but fails if they are corrupted. The checksum is only computed the
+    /// first time; later calls reuse the result.
+    ///
+    /// A `FileRef` does not know its own name, so the returned
+    /// `CorruptedFile` error has an empty one. A compressed file that fails
+    /// to decompress returns the decoding error instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
+    /// let cargo_toml_slice = cargo_toml.as_slice_checked().unwrap();
+    /// println!("{} bytes", cargo_toml_slice.len());
+    /// ```
+    pub fn as_slice_checked(&self) -> Result<&[u8]> {
+        self.contents()?;
+
+        let state = match self.validated.load(Ordering::Acquire) {
+            UNCHECKED => {
+                let state = if self.is_valid() { VALID } else { INVALID };
+                self.validated.store(state, Ordering::Release);
+                state
+            },
+            state => state,
+        };
+
+        if state == VALID {
+            Ok(self.as_slice())
+        }
+        else {
+            Err(Error::FileArcoV1(FileArcoV1Error::CorruptedFile(String::new())))
+        }
+    }
+
+    /// This method retrieves a byte array representing the contents of a `FileRef`.
+    /// Compressed files are decompressed the first time this is called, and
+    /// are empty if that fails (use `as_slice_checked()` to get the error).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
+    /// let cargo_toml_slice = cargo_toml.as_slice();
+    /// println!("{} bytes", cargo_toml_slice.len());
+    ///
+    /// // Use `as_str` or `as_str_lossy` to view the contents as text.
+    /// println!("{}", cargo_toml.as_str_lossy());
+    /// ```
+    pub fn as_slice(&self) -> &[u8] {
+        self.contents().unwrap_or(&[])
+    }
+
+    // This method returns the contents of the file, inflating them first if
+    // the file is compressed.
+    fn contents(&self) -> Result<&[u8]> {
+        if self.compressed {
+            return match *self.inflated.get_or_init(|| inflate(self.stored(), self.length)) {
+                Ok(ref contents) => Ok(contents),
+                Err(ref e) => Err(Error::Io(io::Error::new(e.kind(), e.to_string()))),
+            };
+        }
+
+        // Empty files take up no space, so their offset may be shared with
+        // the next file (or be the end of the archive).
+        if self.length == 0 {
+            return Ok(&[]);
+        }
+
+        Ok(unsafe { slice::from_raw_parts(self.address, self.length as usize) })
+    }
+
+    // This method returns the bytes of a compressed file as stored in the
+    // archive.
+    fn stored(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.address, self.stored_length as usize) }
+    }
+ 
+    /// This method retrieves a string representing the contents of a `FileRef`.
+    /// It returns an error if the file contents do not represent a valid
+    /// UTF-8 string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(&path).unwrap(); 
+    /// 
+    /// let license = file_data.get("LICENSE-APACHE").unwrap();
+    /// let license_text = license.as_str().ok().unwrap();
+    /// println!("{}", license_text);
+    /// ```
+    pub fn as_str(&self) -> Result<&str> {
+        let sl = self.as_slice();
+
+        let s = str::from_utf8(sl)?;
+
+        Ok(s)
+    }
+
+    /// This method deserializes the contents of a `FileRef`, which must have
+    /// been serialized with bincode, into a value of type `T`. It returns an
+    /// error if the contents are not a valid encoding of `T`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(&path).unwrap(); 
+    /// 
+    /// // Strings are encoded as their length followed by their bytes.
+    /// let license = file_data.get("LICENSE-APACHE").unwrap();
+    /// assert!(license.deserialize::<String>().is_err());
+    /// ```
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T> {
+        let value = deserialize(self.as_slice())?;
+
+        Ok(value)
+    }
+
+    /// This method retrieves a string representing the contents of a
+    /// `FileRef`, replacing any invalid UTF-8 sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER`. This is useful for displaying files
+    /// that may not be text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(&path).unwrap(); 
+    /// 
+    /// let license = file_data.get("LICENSE-APACHE").unwrap();
+    /// println!("{}", license.as_str_lossy());
+    /// ```
+    pub fn as_str_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.as_slice())
+    }
+
+    /// This method works like `as_str` but guarantees the padding after
+    /// the file contents is never included: only the first `len()` bytes
+    /// are used, regardless of the aligned length stored in the archive.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(&path).unwrap(); 
+    /// 
+    /// let license = file_data.get("LICENSE-MIT").unwrap();
+    /// let license_text = license.as_str_trimmed().ok().unwrap();
+    /// assert!(!license_text.ends_with('\0'));
+    /// ```
+    pub fn as_str_trimmed(&self) -> Result<&str> {
+        self.as_text(false)
+    }
+
+    /// This method retrieves a string representing the contents of a
+    /// `FileRef`, excluding padding. If `trim_trailing_nuls` is true, any
+    /// NUL characters at the end of the file contents themselves are
+    /// removed as well. It returns an error if the file contents do not
+    /// represent a valid UTF-8 string.
+    ///
+    /// # Arguments
+    ///
+    /// * trim_trailing_nuls - whether to remove NULs ending the file contents
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(&path).unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
+    /// let cargo_toml_text = cargo_toml.as_text(true).ok().unwrap();
+    /// println!("{}", cargo_toml_text);
+    /// ```
+    pub fn as_text(&self, trim_trailing_nuls: bool) -> Result<&str> {
+        let mut sl = self.as_slice();
+
+        if trim_trailing_nuls {
+            let end = sl.iter().rposition(|&b| b != 0).map_or(0, |pos| pos + 1);
+            sl = &sl[..end];
+        }
+
+        let s = str::from_utf8(sl)?;
+
+        Ok(s)
+    }
+
+    /// This method returns a reader over the contents of a `FileRef`, which
+    /// never reads into the padding after the file contents.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(&path).unwrap(); 
+    /// 
+    /// let license = file_data.get("LICENSE-MIT").unwrap();
+    /// io::copy(&mut license.reader(), &mut io::sink()).unwrap();
+    /// ```
+    pub fn reader(&self) -> FileRefReader<'_> {
+        FileRefReader {
+            contents: self.as_slice(),
+            position: 0,
+        }
+    }
+
+    /// This method returns a copy of the contents of a `FileRef`. Unlike
+    /// `as_slice`, which is empty if a compressed file could not be
+    /// decompressed, it returns any error encountered.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(&path).unwrap(); 
+    /// 
+    /// let license = file_data.get("LICENSE-MIT").unwrap();
+    /// let contents = license.read_decompressed().ok().unwrap();
+    /// assert_eq!(&contents[..], license.as_slice());
+    /// ```
+    pub fn read_decompressed(&self) -> Result<Vec<u8>> {
+        let contents = self.contents()?;
+
+        Ok(contents.to_vec())
+    }
+
+    /// This method writes the contents of a `FileRef` to `w` and returns
+    /// the number of bytes written. Compressed files are inflated from the
+    /// stored bytes in fixed-size chunks, so the whole decompressed file is
+    /// never held in memory at once.
+    ///
+    /// # Arguments
+    ///
+    /// * w - where to write the contents
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(&path).unwrap(); 
+    /// 
+    /// let license = file_data.get("LICENSE-MIT").unwrap();
+    /// let mut contents = Vec::new();
+    /// let written = license.decompress_to(&mut contents).ok().unwrap();
+    /// assert_eq!(written, license.len());
+    /// assert_eq!(&contents[..], license.as_slice());
+    /// ```
+    pub fn decompress_to<W: Write>(&self, w: &mut W) -> Result<u64> {
+        if !self.compressed {
+            w.write_all(self.as_slice())?;
+            return Ok(self.length);
+        }
+
+        self.inflate_to(self.stored(), w)
+    }
+
+    // This method inflates `stored` into `w` in chunks of the archive's
+    // decompression buffer size and returns the number of bytes written.
+    // The chunk is reused by later calls.
+    fn inflate_to<W: Write>(&self, stored: &[u8], w: &mut W) -> Result<u64> {
+        let mut buffer = self.inner.decompress_buffers.take();
+        buffer.resize(self.inner.decompress_buffer_size, 0);
+
+        let written = inflate_chunks(stored, &mut buffer, w);
+        self.inner.decompress_buffers.give(buffer);
+
+        written
+    }
+
+    /// This method returns a tuple with a raw pointer to the beginning
+    /// of the file and the page-aligned length of the file. For compressed
+    /// files, these refer to the compressed bytes stored in the archive.
+    /// For archives opened with `FileArco::from_bytes`, the pointer may not
+    /// be page aligned.
+    ///
+    /// # Unsafety
+    ///
+    /// Callers should not use this method to modify the contents
+    /// of the file. Undefined Behavior will result.
+    ///
+    /// # Example
+    ///
+    /// This example uses the `memadvise` crate to advise the operating system
+    /// to load the entire file into physical RAM.
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    /// extern crate memadvise;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(&path).unwrap(); 
+    /// 
+    /// let license = file_data.get("LICENSE-APACHE").unwrap();
+    /// let (ptr, len) = license.as_raw();
+    /// 
+    /// memadvise::advise(ptr, len, memadvise::Advice::WillNeed).ok().unwrap();
+    /// ```
+    pub fn as_raw(&self) -> (*mut (), usize) {
+        (self.address as *mut (), self.aligned_length as usize)
+    }
+
+    /// This method retrieves the length of the file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::mem;
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
+    /// println!("File length: {}", cargo_toml.len());
+    /// ```
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// This method retrieves the permissions (e.g. Unix mode bits) the file
+    /// had when it was archived. It returns `None` if the archive predates
+    /// storing file modes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
+    /// if let Some(mode) = cargo_toml.mode() {
+    ///     println!("File mode: {:o}", mode);
+    /// }
+    /// ```
+    pub fn mode(&self) -> Option<u32> {
+        self.mode
+    }
+
+    /// This method retrieves the time the file was last modified before it
+    /// was archived. It returns `None` if the time is unknown (e.g. the
+    /// archive predates storing modification times).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
+    /// if let Some(modified) = cargo_toml.modified_time() {
+    ///     println!("Last modified: {:?}", modified);
+    /// }
+    /// ```
+    pub fn modified_time(&self) -> Option<SystemTime> {
+        if self.modified == 0 {
+            None
+        }
+        else {
+            Some(UNIX_EPOCH + Duration::from_secs(self.modified))
+        }
+    }
+
+    /// This method retrieves the content type (i.e. MIME type) of the file,
+    /// if one was stored in the archive. Archives made from a directory guess
+    /// it from the file extension; `make_from_typed_entries` stores a
+    /// user-supplied one. It always returns `None` for archives with format
+    /// revision 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("testarchives/simple_v1.fac");
+    /// let file_data = filearco::v1::FileArco::new(path).unwrap(); 
+    /// 
+    /// let cargo_toml = file_data.get("Cargo.toml").unwrap();
+    /// if let Some(content_type) = cargo_toml.content_type() {
+    ///     println!("Content type: {}", content_type);
+    /// }
+    /// ```
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+}
+
+/// Error container for handling FileArco v1 archives
+#[derive(Debug)]
+pub enum FileArcoV1Error {
+    /// Entry table's computed checksum did not match the one stored in the file.
+    CorruptedEntriesTable,
+    /// Header's computed checksum did not match the one stored in the file.
+    CorruptedHeader,
+    /// Two different files have the same length and checksum. Archives are
+    /// no longer made with this error; such files are stored separately.
+    ChecksumCollision(String),
+    /// Header, entries table, or footer could not be deserialized.
+    DeserializeFailed,
+    /// File's computed checksum did not match the one stored in the archive.
+    CorruptedFile(String),
+    /// An entry with the same name was already added to the archive.
+    DuplicateEntry(String),
+    /// Named entry is not in the archive.
+    EntryNotFound(String),
+    /// File is too small for the header of a FileArco v1 archive.
+    FileTooSmall,
+    /// File is a valid FileArco v1 archive but it has been truncated.
+    FileTruncated,
+    /// File is too large to be addressed on this platform (i.e. 4 GiB or
+    /// more on a 32-bit target).
+    FileTooLarge(String),
+    /// Archive has a version number the caller does not accept.
+    IncompatibleVersion(u64),
+    /// Requested alignment is not a power of two.
+    InvalidAlignment(u64),
+    /// Checksum manifest has a line that is not a digest followed by a name.
+    InvalidManifest(String),
+    /// Entry name is not canonical or duplicates another name once
+    /// normalized.
+    InvalidName(String),
+    /// File does not have a valid identifier.
+    NotArchive,
+    /// File has a valid identifier but an incorrect version number.
+    NotV1Archive,
+    /// Source file changed after its length and checksum were recorded.
+    SourceChanged(String),
+    /// Entry name would be written outside of the destination directory.
+    UnsafePath(String),
+    /// File is a FileArco v1 archive with a format revision this crate
+    /// does not support.
+    UnsupportedRevision,
+    /// Something weird happened.
+    Other,
+}
+
+impl fmt::Display for FileArcoV1Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FileArcoV1Error::CorruptedEntriesTable => {
+                write!(fmt, "Corrupted entries table")
+            },
+            FileArcoV1Error::CorruptedHeader => {
+                write!(fmt, "Corrupted header")
+            },
+            FileArcoV1Error::ChecksumCollision(ref name) => {
+                write!(fmt, "Checksum collision: {}", name)
+            },
+            FileArcoV1Error::CorruptedFile(ref name) => {
+                write!(fmt, "Corrupted file: {}", name)
+            },
+            FileArcoV1Error::DeserializeFailed => {
+                write!(fmt, "Malformed archive data")
+            },
+            FileArcoV1Error::DuplicateEntry(ref name) => {
+                write!(fmt, "Duplicate entry: {}", name)
+            },
+            FileArcoV1Error::EntryNotFound(ref name) => {
+                write!(fmt, "Entry not found: {}", name)
+            },
+            FileArcoV1Error::FileTooSmall => {
+                write!(fmt, "File either too small for FileArco v1 archive or truncated")
+            },
+            FileArcoV1Error::FileTooLarge(ref name) => {
+                write!(fmt, "File too large for this platform: {}", name)
+            },
+            FileArcoV1Error::IncompatibleVersion(version_number) => {
+                write!(fmt, "Incompatible FileArco v1 version number: {:#x}", version_number)
+            },
+            FileArcoV1Error::InvalidAlignment(alignment) => {
+                write!(fmt, "Alignment is not a power of two: {}", alignment)
+            },
+            FileArcoV1Error::InvalidManifest(ref line) => {
+                write!(fmt, "Invalid checksum manifest line: {:?}", line)
+            },
+            FileArcoV1Error::InvalidName(ref name) => {
+                write!(fmt, "Invalid entry name: {:?}", name)
+            },
+            FileArcoV1Error::FileTruncated => {
+                write!(fmt, "File truncated")
+            },
+            FileArcoV1Error::NotArchive => {
+                write!(fmt, "Not FileArco archive")
+            },
+            FileArcoV1Error::NotV1Archive => {
+                write!(fmt, "Not FileArco v1 archive")
+            },
+            FileArcoV1Error::SourceChanged(ref name) => {
+                write!(fmt, "Source file changed while archiving: {}", name)
+            },
+            FileArcoV1Error::UnsafePath(ref name) => {
+                write!(fmt, "Unsafe path: {}", name)
+            },
+            FileArcoV1Error::UnsupportedRevision => {
+                write!(fmt, "Unsupported FileArco v1 format revision")
+            },
+            FileArcoV1Error::Other => {
+                write!(fmt, "Something weird happened")
+            },
+        }
+    }
+}
+
+impl error::Error for FileArcoV1Error {
+    fn description(&self) -> &str {
+        static CORRUPTED_ENTRIES_TABLE: &'static str = "Corrupted entries table";
+        static CORRUPTED_HEADER: &'static str = "Corrupted header";
+        static CHECKSUM_COLLISION: &'static str = "Checksum collision";
+        static CORRUPTED_FILE: &'static str = "Corrupted file";
+        static DESERIALIZE_FAILED: &'static str = "Malformed archive data";
+        static DUPLICATE_ENTRY: &'static str = "Duplicate entry";
+        static ENTRY_NOT_FOUND: &'static str = "Entry not found";
+        static FILE_TOO_SMALL: &'static str = "File either too small for FileArco v1 archive or truncated";
+        static FILE_TRUNCATED: &'static str = "File truncated";
+        static FILE_TOO_LARGE: &'static str = "File too large for this platform";
+        static INCOMPATIBLE_VERSION: &'static str = "Incompatible FileArco v1 version number";
+        static INVALID_ALIGNMENT: &'static str = "Alignment is not a power of two";
+        static INVALID_MANIFEST: &'static str = "Invalid checksum manifest";
+        static INVALID_NAME: &'static str = "Invalid entry name";
+        static NOT_ARCHIVE: &'static str = "Not FileArco archive";
+        static NOT_V1_ARCHIVE: &'static str = "Not FileArco v1 archive";
+        static SOURCE_CHANGED: &'static str = "Source file changed while archiving";
+        static UNSAFE_PATH: &'static str = "Unsafe path";
+        static UNSUPPORTED_REVISION: &'static str = "Unsupported FileArco v1 format revision";
+        static OTHER: &'static str = "Something weird happened";
+
+        match *self {
+            FileArcoV1Error::CorruptedEntriesTable => {
+                CORRUPTED_ENTRIES_TABLE
+            },
+            FileArcoV1Error::CorruptedHeader => {
+                CORRUPTED_HEADER
+            },
+            FileArcoV1Error::ChecksumCollision(_) => {
+                CHECKSUM_COLLISION
+            },
+            FileArcoV1Error::CorruptedFile(_) => {
+                CORRUPTED_FILE
+            },
+            FileArcoV1Error::DeserializeFailed => {
+                DESERIALIZE_FAILED
+            },
+            FileArcoV1Error::DuplicateEntry(_) => {
+                DUPLICATE_ENTRY
+            },
+            FileArcoV1Error::EntryNotFound(_) => {
+                ENTRY_NOT_FOUND
+            },
+            FileArcoV1Error::FileTooSmall => {
+                FILE_TOO_SMALL
+            },
+            FileArcoV1Error::FileTruncated => {
+                FILE_TRUNCATED
+            },
+            FileArcoV1Error::FileTooLarge(_) => {
+                FILE_TOO_LARGE
+            },
+            FileArcoV1Error::IncompatibleVersion(_) => {
+                INCOMPATIBLE_VERSION
+            },
+            FileArcoV1Error::InvalidAlignment(_) => {
+                INVALID_ALIGNMENT
+            },
+            FileArcoV1Error::InvalidManifest(_) => {
+                INVALID_MANIFEST
+            },
+            FileArcoV1Error::InvalidName(_) => {
+                INVALID_NAME
+            },
+            FileArcoV1Error::NotArchive => {
+                NOT_ARCHIVE
+            },
+            FileArcoV1Error::NotV1Archive => {
+                NOT_V1_ARCHIVE
+            },
+            FileArcoV1Error::SourceChanged(_) => {
+                SOURCE_CHANGED
+            },
+            FileArcoV1Error::UnsafePath(_) => {
+                UNSAFE_PATH
+            },
+            FileArcoV1Error::UnsupportedRevision => {
+                UNSUPPORTED_REVISION
+            },
+            FileArcoV1Error::Other => {
+                OTHER
+            }
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> { None }
+}
+
+struct Inner {
+    file_offset: u64,
+    page_size: u64,
+    revision: u64,
+    version_number: u64,
+    file_length: u64,
+    entries_length: u64,
+    entries_checksum: u64,
+    // Whether the header has the compressed flag set
+    compressed: bool,
+    // Algorithm of the file checksums
+    checksum_kind: ChecksumKind,
+    // Secret value mixed into every checksum
+    seed: u64,
+    entries: Entries,
+    // Maps exact bytes of non-UTF-8 names to their keys in `entries`
+    raw_names: HashMap<Vec<u8>, String>,
+    // Maps lowercased names to their keys in `entries`, if requested
+    lowercase_names: Option<HashMap<String, String>>,
+    // Chunk size used by `FileRef::decompress_to()`
+    decompress_buffer_size: usize,
+    // Chunks released by earlier calls to `FileRef::decompress_to()`
+    decompress_buffers: BufferPool,
+    // Number of calls to `FileArco::get()`
+    #[cfg(test)]
+    lookups: AtomicUsize,
+    storage: Storage,
+}
+
+// This holds the bytes of an open archive.
+enum Storage {
+    Mmap(Mmap),
+    Bytes(Arc<Vec<u8>>),
+}
+
+impl Storage {
+    fn as_slice(&self) -> &[u8] {
+        match *self {
+            Storage::Mmap(ref map) => unsafe { map.as_slice() },
+            Storage::Bytes(ref data) => data,
+        }
+    }
+
+    fn ptr(&self) -> *const u8 {
+        self.as_slice().as_ptr()
+    }
+
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+}
+
+impl Inner {
+    // This method returns the bytes of `entry` as stored in the archive.
+    fn stored_bytes<'a>(&'a self, entry: &'a Entry) -> &'a [u8] {
+        match entry.inline {
+            Some(ref inline) => inline,
+            None => self.payload_bytes(entry),
+        }
+    }
+
+    // This method returns the bytes of `entry` in the file contents section,
+    // which are empty for inline files.
+    fn payload_bytes(&self, entry: &Entry) -> &[u8] {
+        let start = (self.file_offset + entry.offset) as usize;
+        let bytes = self.storage.as_slice();
+
+        &bytes[start..start + entry.stored_length as usize]
+    }
+
+    // Entries are only checked for compression if the archive has the
+    // compressed flag set, so other archives keep the zero-copy path.
+    fn is_compressed(&self, entry: &Entry) -> bool {
+        self.compressed && entry.compressed
+    }
+
+    fn mode_of(&self, entry: &Entry) -> Option<u32> {
+        if self.revision >= 1 {
+            Some(entry.mode)
+        }
+        else {
+            None
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Header {
+    id: [u8; 8],
+    version_number: u64,
+    file_length: u64,
+    file_offset: u64,
+    page_size: u64,
+    entries_length: u64,
+    entries_checksum: u64,
+}
+
+impl Header {
+    fn new(page_size: u64,
+           entries_length: u64,
+           file_contents_length: u64,
+           entries_checksum: u64) -> Self {
+        // Serialize test struct to determine `file_offset`.
+        let test_header = Header {
+            id: *FILEARCO_ID,
+            version_number: VERSION_NUMBER | (REVISION << REVISION_SHIFT),
+            file_length: 0,
+            file_offset: 0,
+            page_size: page_size,
+            entries_length: entries_length,
+            entries_checksum: entries_checksum,
+        };
+        let test_header_encoded = serialize(&test_header, Infinite).unwrap();
+        let header_length = test_header_encoded.len() as u64;
+
+        let file_offset = align_to(header_length + CHECKSUM_SIZE as u64 + entries_length,
+                                   page_size);
+        let file_length = file_offset + file_contents_length;
+
+        Header {
+            id: *FILEARCO_ID,
+            version_number: VERSION_NUMBER | (REVISION << REVISION_SHIFT),
+            file_length: file_length,
+            file_offset: file_offset,
+            page_size: page_size,
+            entries_length: entries_length,
+            entries_checksum: entries_checksum,
+        }
+    }
+
+    // In the trailer layout, file contents usually start at the beginning of
+    // the archive (or after the old header of an archive that was appended
+    // to), and the entries table begins right where they end
+    // (i.e. at `file_length`).
+    fn new_trailer(page_size: u64,
+                   file_offset: u64,
+                   entries_length: u64,
+                   file_contents_end: u64,
+                   entries_checksum: u64) -> Self {
+        Header {
+            id: *FILEARCO_ID,
+            version_number: VERSION_NUMBER | (REVISION << REVISION_SHIFT),
+            file_length: file_contents_end,
+            file_offset: file_offset,
+            page_size: page_size,
+            entries_length: entries_length,
+            entries_checksum: entries_checksum,
+        }
+    }
+
+    fn major_version(&self) -> u64 {
+        self.version_number & ((1 << REVISION_SHIFT) - 1)
+    }
+
+    fn revision(&self) -> u64 {
+        (self.version_number >> REVISION_SHIFT) & ((1 << (FLAGS_SHIFT - REVISION_SHIFT)) - 1)
+    }
+
+    fn flags(&self) -> u64 {
+        self.version_number >> FLAGS_SHIFT
+    }
+
+    fn with_flags(mut self, flags: u64) -> Self {
+        self.version_number |= flags << FLAGS_SHIFT;
+        self
+    }
+}
+
+// This is stored at the end of an archive using the trailer layout.
+#[repr(C)]
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Footer {
+    header_offset: u64,
+    id: [u8; 8],
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+struct Entries {
+    // Serialized in name order, so identical inputs give identical archives
+    #[serde(serialize_with = "serialize_sorted")]
+    files: HashMap<String, Entry>,
+    // Name and version of the tool that created the archive
+    producer: Option<String>,
+}
+
+impl Entries {
+    // Files are aligned to `page_size`, which is the page size recorded in
+    // the header of the archive they will be written to.
+    fn new(file_data: FileData, page_size: u64) -> Result<Self> {
+        let mut files = HashMap::new();
+        let mut raw_names = HashSet::new();
+
+        // Names that are not valid UTF-8 are keyed by their lossy form, which
+        // may already be taken, so they are added after every valid name.
+        let (valid, invalid): (Vec<_>, Vec<_>) = file_data.into_vec().into_iter()
+            .partition(|datum| str::from_utf8(datum.name_bytes()).is_ok());
+
+        for datum in valid.into_iter().chain(invalid.into_iter()) {
+            let aligned_length = get_aligned_length(datum.len(), page_size);
+
+            let raw_name = match str::from_utf8(datum.name_bytes()) {
+                Ok(_) => None,
+                Err(_) => Some(datum.name_bytes().to_vec()),
+            };
+
+            let name = match raw_name {
+                Some(ref raw_name) => {
+                    if !raw_names.insert(raw_name.clone()) {
+                        return Err(Error::FileArcoV1(FileArcoV1Error::DuplicateEntry(datum.name())));
+                    }
+
+                    unique_name(datum.name(), |name| files.contains_key(name))
+                },
+                None => datum.name(),
+            };
+
+            if files.contains_key(&name) {
+                return Err(Error::FileArcoV1(FileArcoV1Error::DuplicateEntry(name)));
+            }
+
+            let content_type = guess_content_type(&name).map(String::from);
+
+            files.insert(name,
+                         Entry {
+                             offset: 0,
+                             length: datum.len(),
+                             aligned_length: aligned_length,
+                             checksum: datum.checksum(),
+                             mode: datum.mode(),
+                             modified: datum.modified(),
+                             raw_name: raw_name,
+                             compressed: false,
+                             stored_length: datum.len(),
+                             content_type: content_type,
+                             digest: None,
+                             inline: None,
+                         }
+            );
+        }
+
+        // Offsets are assigned by the caller, which can compare contents.
+        Ok(Entries {
+            files: files,
+            producer: Some(default_producer()),
+        })
+    }
+
+    // This method builds an entries table from names and lengths alone,
+    // without reading or checksumming any file contents. Files are laid out
+    // one after another with placeholder checksums of 0, and names after
+    // the first occurrence are ignored.
+    fn from_metadata(names_and_lengths: &[(String, u64)], alignment: u64) -> Entries {
+        let mut files = HashMap::with_capacity(names_and_lengths.len());
+        let mut offset = 0;
+
+        for &(ref name, length) in names_and_lengths.iter() {
+            if files.contains_key(name) {
+                continue;
+            }
+
+            let aligned_length = align_to(length, alignment);
+
+            files.insert(name.clone(),
+                         Entry {
+                             offset: offset,
+                             length: length,
+                             aligned_length: aligned_length,
+                             checksum: 0,
+                             mode: DEFAULT_MODE,
+                             modified: 0,
+                             raw_name: None,
+                             compressed: false,
+                             stored_length: length,
+                             content_type: guess_content_type(name).map(String::from),
+                             digest: None,
+                             inline: None,
+                         }
+            );
+            offset = offset + aligned_length;
+        }
+
+        Entries {
+            files: files,
+            producer: Some(default_producer()),
+        }
+    }
+
+    // This method lays out the file contents section in name order, so
+    // identical inputs give identical archives. Files with the same length
+    // and checksum are stored only once, if `same_contents` confirms they
+    // really are identical; a file that only collides with another gets its
+    // own region. Empty files have an aligned length of 0, so they take up
+    // no space and all get the offset of the next file laid out.
+    fn assign_offsets<F>(&mut self, mut same_contents: F) -> Result<()>
+        where F: FnMut((&str, &Entry), (&str, &Entry)) -> Result<bool>
+    {
+        let mut offsets = Vec::<(String, u64)>::with_capacity(self.files.len());
+
+        {
+            let mut offset = 0;
+            let mut blobs = HashMap::<(u64, u64), Vec<(&str, &Entry, u64)>>::new();
+            let mut files = self.files.iter().collect::<Vec<_>>();
+            files.sort_by(|a, b| a.0.cmp(b.0));
+
+            for (name, val) in files.into_iter() {
+                // Inline files take no space in the file contents section.
+                if val.inline.is_some() {
+                    offsets.push((name.clone(), 0));
+                    continue;
+                }
+
+                let candidates = blobs.entry((val.length, val.checksum)).or_insert_with(Vec::new);
+                let mut shared = None;
+
+                for &(other_name, other_val, blob_offset) in candidates.iter() {
+                    if val.length == 0 || same_contents((other_name, other_val), (name, val))? {
+                        shared = Some(blob_offset);
+                        break;
+                    }
+                }
+
+                match shared {
+                    Some(blob_offset) => offsets.push((name.clone(), blob_offset)),
+                    None => {
+                        candidates.push((name, val, offset));
+                        offsets.push((name.clone(), offset));
+                        offset = offset + val.aligned_length;
+                    },
+                }
+            }
+        }
+
+        for (name, offset) in offsets.into_iter() {
+            self.files.get_mut(&name).unwrap().offset = offset;
+        }
+
+        Ok(())
+    }
+
+    // The header flags needed to read these entries.
+    fn flags(&self) -> u64 {
+        if self.files.values().any(|val| val.compressed) {
+            FLAG_COMPRESSED
+        }
+        else {
+            0
+        }
+    }
+
+    fn raw_names(&self) -> HashMap<Vec<u8>, String> {
+        self.files.iter()
+            .filter_map(|(name, entry)| {
+                entry.raw_name.as_ref().map(|raw| (raw.clone(), name.clone()))
+            })
+            .collect()
+    }
+
+    // Archives made on Windows by older versions store names with `\\`
+    // separators. Those are rekeyed with `/` so names are the same on every
+    // platform, unless that would collide with another name.
+    fn normalize_separators(&mut self) {
+        let names = self.files.keys()
+            .filter(|name| name.contains('\\'))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for name in names.into_iter() {
+            let normalized = normalize_separators(&name).into_owned();
+
+            if !self.files.contains_key(&normalized) {
+                let entry = self.files.remove(&name).unwrap();
+                self.files.insert(normalized, entry);
+            }
+        }
+    }
+
+    // Names that differ only in case map to the smallest of them, which
+    // matches the linear search in `FileArco::get_ci()`.
+    fn lowercase_names(&self) -> HashMap<String, String> {
+        let mut lowercase_names: HashMap<String, String> = HashMap::with_capacity(self.files.len());
+
+        for name in self.files.keys() {
+            let lowercase = name.to_lowercase();
+            let replace = match lowercase_names.get(&lowercase) {
+                Some(existing) => name < existing,
+                None => true,
+            };
+
+            if replace {
+                lowercase_names.insert(lowercase, name.clone());
+            }
+        }
+
+        lowercase_names
+    }
+
+    // Since files may share regions, this is the end of the last region
+    // rather than the sum of all aligned lengths.
+    fn total_aligned_length(&self) -> u64 {
+        self.files.values()
+            .map(|val| val.offset + val.aligned_length)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+struct Entry {
+    // Offset of the contents from the start of the file contents section.
+    // Empty files own no region, so this is the offset of whatever follows
+    // them and must never be read from when `length` is 0.
+    offset: u64,
+    length: u64,
+    aligned_length: u64,
+    checksum: u64,
+    mode: u32,
+    // Seconds since the Unix epoch, or 0 if unknown
+    modified: u64,
+    // Exact bytes of the name if it is not valid UTF-8
+    raw_name: Option<Vec<u8>>,
+    compressed: bool,
+    // Length of the (possibly compressed) contents in the archive
+    stored_length: u64,
+    // MIME type of the contents, if known
+    content_type: Option<String>,
+    // SHA-256 digest of the contents, if the archive has digests
+    digest: Option<[u8; DIGEST_SIZE]>,
+    // Contents of a small file stored in the entries table instead of the
+    // file contents section
+    inline: Option<Vec<u8>>,
+}
+
+// This is the entries table of archives with format revision 0.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct LegacyEntries {
+    files: HashMap<String, LegacyEntry>,
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct LegacyEntry {
+    offset: u64,
+    length: u64,
+    aligned_length: u64,
+    checksum: u64,
+}
+
+impl From<LegacyEntries> for Entries {
+    fn from(legacy: LegacyEntries) -> Self {
+        let files = legacy.files.into_iter()
+            .map(|(name, entry)| {
+                (name, Entry {
+                    offset: entry.offset,
+                    length: entry.length,
+                    aligned_length: entry.aligned_length,
+                    checksum: entry.checksum,
+                    mode: DEFAULT_MODE,
+                    modified: 0,
+                    raw_name: None,
+                    compressed: false,
+                    stored_length: entry.length,
+                    content_type: None,
+                    digest: None,
+                    inline: None,
+                })
+            })
+            .collect();
+
+        Entries {
+            files: files,
+            producer: None,
+        }
+    }
+}
+
+// This function validates the header and entries table of an archive stored
+// in `bytes` and returns them. It supports both the standard layout (header
+// first) and the trailer layout (index last).
+fn read_index(bytes: &[u8],
+              seed: u64,
+              metrics: &mut OpenMetrics) -> Result<(Header, Entries)> {
+    read_index_with(bytes.len() as u64, seed, metrics, |offset, length| {
+        let offset = offset as usize;
+        Ok(Cow::Borrowed(&bytes[offset..offset + length]))
+    })
+}
+
+// This function works like `read_index()` on an archive that is
+// `archive_length` bytes long, fetching only the bytes it needs with
+// `read_at(offset, length)`. Callers need not check bounds. The time spent
+// validating and deserializing is added to `metrics`.
+fn read_index_with<'a, F>(archive_length: u64,
+                          seed: u64,
+                          metrics: &mut OpenMetrics,
+                          mut read_at: F) -> Result<(Header, Entries)>
+    where F: FnMut(u64, usize) -> Result<Cow<'a, [u8]>>
+{
+    let (header, entries_offset) = locate_index_with(archive_length,
+                                                     seed,
+                                                     metrics,
+                                                     &mut read_at)?;
+    let entries = read_entries(&read_at(entries_offset, header.entries_length as usize)?,
+                               &header,
+                               seed,
+                               metrics)?;
+
+    Ok((header, entries))
+}
+
+// This function reads and validates the header of an archive that is
+// `archive_length` bytes long, like `read_index_with()`, and returns it
+// along with the offset of the entries table, which is known to lie within
+// the archive but has not been validated.
+fn locate_index_with<'a, F>(archive_length: u64,
+                            seed: u64,
+                            metrics: &mut OpenMetrics,
+                            read_at: &mut F) -> Result<(Header, u64)>
+    where F: FnMut(u64, usize) -> Result<Cow<'a, [u8]>>
+{
+    let header_length = (header_size() + CHECKSUM_SIZE) as u64;
+
+    // Prefer the trailer layout if a footer is present, but fall back to the
+    // standard layout in case the footer identifier is just file contents.
+    if archive_length >= FOOTER_SIZE as u64 {
+        let footer = read_at(archive_length - FOOTER_SIZE as u64, FOOTER_SIZE)?;
+
+        if let Some(header_offset) = read_footer(&footer) {
+            let header_end = header_offset.checked_add(header_length);
+
+            if header_end.is_some_and(|end| end <= archive_length) {
+                let header_bytes = read_at(header_offset, header_length as usize)?;
+
+                if let Ok(header) = read_header(&header_bytes, 0, seed, metrics) {
+                    let entries_end = header.file_length.checked_add(header.entries_length);
+
+                    if entries_end.is_none_or(|end| end > header_offset) {
+                        return Err(Error::FileArcoV1(FileArcoV1Error::FileTruncated));
+                    }
+
+                    let entries_offset = header.file_length;
+
+                    return Ok((header, entries_offset));
+                }
+            }
+        }
+    }
+
+    // Make sure file is large enough to contain a FileArco v1 header.
+    if archive_length < header_length {
+        return Err(Error::FileArcoV1(FileArcoV1Error::FileTooSmall));
+    }
+
+    let header = read_header(&read_at(0, header_length as usize)?, 0, seed, metrics)?;
+
+    if archive_length < header.file_length {
+        return Err(Error::FileArcoV1(FileArcoV1Error::FileTruncated));
+    }
+
+    // Read in entries data.
+    let entries_end = header_length.checked_add(header.entries_length);
+
+    if entries_end.is_none_or(|end| end > archive_length) {
+        return Err(Error::FileArcoV1(FileArcoV1Error::FileTruncated));
+    }
+
+    Ok((header, header_length))
+}
+
+// This function reads and validates the header (and the header checksum
+// following it) starting at `offset`.
+fn read_header(bytes: &[u8],
+               offset: u64,
+               seed: u64,
+               metrics: &mut OpenMetrics) -> Result<Header> {
+    let header_length = header_size();
+    let offset = offset as usize;
+
+    // Make sure file is large enough to contain a FileArco v1 header.
+    if bytes.len() < offset + header_length + CHECKSUM_SIZE {
+        return Err(Error::FileArcoV1(FileArcoV1Error::FileTooSmall));
+    }
+
+    // Read in header.
+    let sl = &bytes[offset..offset + header_length];
+    let start = Instant::now();
+    let header: Header = decode(sl)?;
+    metrics.deserialize_time += start.elapsed();
+
+    let start = Instant::now();
+    let checksum1 = seeded_checksum(seed, sl);
+    metrics.checksum_time += start.elapsed();
+
+    // Read in header checksum.
+    let header_checksum: u64 = decode(
+        &bytes[offset + header_length..offset + header_length + CHECKSUM_SIZE]
+    )?;
+
+    // Ensure header is valid.
+    if header.id != *FILEARCO_ID {
+        return Err(Error::FileArcoV1(FileArcoV1Error::NotArchive));
+    }
+
+    if header.major_version() != VERSION_NUMBER {
+        return Err(Error::FileArcoV1(FileArcoV1Error::NotV1Archive));
+    }
+
+    if header.revision() > REVISION {
+        return Err(Error::FileArcoV1(FileArcoV1Error::UnsupportedRevision));
+    }
+
+    if header.flags() & !KNOWN_FLAGS != 0 {
+        return Err(Error::FileArcoV1(FileArcoV1Error::UnsupportedRevision));
+    }
+
+    if checksum1 != header_checksum {
+        return Err(Error::FileArcoV1(FileArcoV1Error::CorruptedHeader));
+    }
+
+    Ok(header)
+}
+
+// This function validates and deserializes the entries table.
+fn read_entries(sl: &[u8],
+                header: &Header,
+                seed: u64,
+                metrics: &mut OpenMetrics) -> Result<Entries> {
+    metrics.entries_length = sl.len() as u64;
+
+    // Ensure entries table is valid.
+    let start = Instant::now();
+    let entries_checksum = seeded_checksum(seed, sl);
+    metrics.checksum_time += start.elapsed();
+
+    if entries_checksum != header.entries_checksum {
+        return Err(Error::FileArcoV1(FileArcoV1Error::CorruptedEntriesTable));
+    }
+
+    // The entries table of an archive with SHA-256 digests is followed by
+    // its own digest.
+    let sl = if header.flags() & FLAG_SHA256 != 0 {
+        if sl.len() < DIGEST_SIZE {
+            return Err(Error::FileArcoV1(FileArcoV1Error::CorruptedEntriesTable));
+        }
+
+        let (table, digest) = sl.split_at(sl.len() - DIGEST_SIZE);
+        let start = Instant::now();
+        let digest_computed = seeded_digest(seed, table);
+        metrics.checksum_time += start.elapsed();
+
+        if digest_computed[..] != digest[..] {
+            return Err(Error::FileArcoV1(FileArcoV1Error::CorruptedEntriesTable));
+        }
+
+        table
+    }
+    else {
+        sl
+    };
+
+    let start = Instant::now();
+    let mut entries: Entries = if header.revision() == 0 {
+        let legacy: LegacyEntries = decode(sl)?;
+        legacy.into()
+    }
+    else {
+        decode(sl)?
+    };
+    entries.normalize_separators();
+    metrics.deserialize_time += start.elapsed();
+
+    Ok(entries)
+}
+
+// This function ensures every file can be sliced out of the archive (and
+// decompressed) without its offset or length overflowing a `usize`, which is
+// `limit`.
+fn check_addressable(entries: &Entries, file_offset: u64, limit: u64) -> Result<()> {
+    for (name, entry) in entries.files.iter() {
+        let end = file_offset.checked_add(entry.offset)
+            .and_then(|start| start.checked_add(entry.stored_length));
+
+        if end.is_none_or(|end| end > limit) || entry.length > limit {
+            return Err(Error::FileArcoV1(FileArcoV1Error::FileTooLarge(name.clone())));
+        }
+    }
+
+    Ok(())
+}
+
+// This function ensures the stored contents of every file lie within the
+// archive, which is `length` bytes long, so a corrupted (or crafted) entries
+// table whose checksum is still consistent cannot make a `FileRef` point
+// past the end of the mapping. Only the stored contents are checked, since
+// they are all that is ever read; the padding after the last file may be
+// missing.
+fn check_in_bounds(entries: &Entries, file_offset: u64, length: u64) -> Result<()> {
+    for entry in entries.files.values() {
+        let end = file_offset.checked_add(entry.offset)
+            .and_then(|start| start.checked_add(entry.stored_length));
+        let inline_valid = entry.inline.as_ref()
+            .is_none_or(|inline| inline.len() as u64 == entry.length && !entry.compressed);
+
+        if end.is_none_or(|end| end > length) || !inline_valid {
+            return Err(Error::FileArcoV1(FileArcoV1Error::CorruptedEntriesTable));
+        }
+    }
+
+    Ok(())
+}
+
+// This function returns the header offset stored in the footer of an archive
+// using the trailer layout, or `None` if there is no such footer.
+fn read_footer(bytes: &[u8]) -> Option<u64> {
+    if bytes.len() < FOOTER_SIZE {
+        return None;
+    }
+
+    let footer: Footer = match decode(&bytes[bytes.len() - FOOTER_SIZE..]) {
+        Ok(footer) => footer,
+        Err(_) => return None,
+    };
+
+    if footer.id == *TRAILER_ID {
+        Some(footer.header_offset)
+    }
+    else {
+        None
+    }
+}
+
+// This function deserializes `bytes`, returning an error instead of
+// panicking if they are malformed.
+fn decode<'a, T: Deserialize<'a>>(bytes: &'a [u8]) -> Result<T> {
+    deserialize(bytes).map_err(|_| Error::FileArcoV1(FileArcoV1Error::DeserializeFailed))
+}
+
+// This function serializes `files` in name order. The encoding is the same
+// as that of the `HashMap` itself, which iterates in arbitrary order.
+fn serialize_sorted<S: Serializer>(files: &HashMap<String, Entry>,
+                                   serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+    let sorted = files.iter().collect::<BTreeMap<_, _>>();
+    sorted.serialize(serializer)
+}
+
+// This function returns the size of an encoded header.
+fn header_size() -> usize {
+    let test_header = Header::new(0, 0, 0, 0);
+    serialize(&test_header, Infinite).unwrap().len()
+}
+
+// This function quotes a CSV field if it contains a comma, quote, or line
+// break, doubling any quotes inside it.
+fn escape_csv(field: &str) -> Cow<'_, str> {
+    if field.contains(|c| c == ',' || c == '"' || c == '\r' || c == '\n') {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    }
+    else {
+        Cow::Borrowed(field)
+    }
+}
+
+// This function returns the relative path of the file stored in `entry`.
+#[cfg(unix)]
+fn entry_path<'a>(name: &'a str, entry: &'a Entry) -> &'a Path {
+    match entry.raw_name {
+        Some(ref raw_name) => Path::new(OsStr::from_bytes(raw_name)),
+        None => Path::new(name),
+    }
+}
+
+#[cfg(not(unix))]
+fn entry_path<'a>(name: &'a str, _entry: &'a Entry) -> &'a Path {
+    Path::new(name)
+}
+
+// This function uses `mincore()` to count the bytes of a mapping that are
+// resident in physical memory.
+#[cfg(unix)]
+fn resident_len(ptr: *const u8, length: usize) -> Option<usize> {
+    let page_size = get_page_size();
+    let num_pages = length.div_ceil(page_size);
+    let mut residency = vec![0u8; num_pages];
+
+    let result = unsafe {
+        libc::mincore(ptr as _, length, residency.as_mut_ptr() as _)
+    };
+
+    if result != 0 {
+        return None;
+    }
+
+    let resident_pages = residency.iter().filter(|&page| page & 1 == 1).count();
+
+    Some(resident_pages * page_size)
+}
+
+#[cfg(not(unix))]
+fn resident_len(_ptr: *const u8, _length: usize) -> Option<usize> {
+    None
+}
+
+// This function advises the operating system that a page-aligned range of
+// memory will be needed soon.
+#[cfg(feature = "prefetch")]
+fn advise_will_need(ptr: *const u8, length: usize) -> io::Result<()> {
+    advise(ptr, length, memadvise::Advice::WillNeed)
+}
+
+#[cfg(not(feature = "prefetch"))]
+fn advise_will_need(_ptr: *const u8, _length: usize) -> io::Result<()> {
+    Ok(())
+}
+
+// This function advises the operating system that a page-aligned range of
+// memory will not be needed for a while.
+#[cfg(feature = "prefetch")]
+fn advise_dont_need(ptr: *const u8, length: usize) -> io::Result<()> {
+    advise(ptr, length, memadvise::Advice::DontNeed)
+}
+
+#[cfg(not(feature = "prefetch"))]
+fn advise_dont_need(_ptr: *const u8, _length: usize) -> io::Result<()> {
+    Ok(())
+}
+
+// This function passes `advice` about a page-aligned range of memory to
+// `memadvise`, converting its errors.
+#[cfg(feature = "prefetch")]
+fn advise(ptr: *const u8, length: usize, advice: memadvise::Advice) -> io::Result<()> {
+    use memadvise::MemAdviseError;
+
+    memadvise::advise(ptr as *mut (), length, advice).map_err(|err| {
+        let message = match err {
+            MemAdviseError::NullAddress => "null address",
+            MemAdviseError::InvalidLength => "invalid length",
+            MemAdviseError::UnalignedAddress => "unaligned address",
+            MemAdviseError::InvalidRange => "invalid range",
+        };
+
+        io::Error::new(io::ErrorKind::InvalidInput, message)
+    })
+}
+
+// This function sets the permissions of an extracted file. Only the
+// permission bits are applied, so an archive cannot create setuid, setgid
+// or sticky files.
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::fs::Permissions;
+    use std::os::unix::fs::PermissionsExt;
+
+    set_permissions(path, Permissions::from_mode(mode & 0o777))
+}
+
+#[cfg(not(unix))]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    // Only the read-only flag is meaningful here.
+    let mut permissions = path.metadata()?.permissions();
+    permissions.set_readonly(mode & 0o222 == 0);
+
+    set_permissions(path, permissions)
+}
+
+// This function writes the header, header checksum, and entries table to the
+// archive followed by enough zeros to ensure files begin at a multiple of
+// `page_size`.
+fn write_prelude<H: Write>(out_file: &mut H,
+                           entries: &Entries,
+                           flags: u64,
+                           page_size: u64,
+                           seed: u64) -> Result<()> {
+    let entries_encoded = encode_entries(entries, flags, seed);
+
+    // Create header, serialize it, and write it to archive.
+    let header = Header::new(page_size,
+                             entries_encoded.len() as u64,
+                             entries.total_aligned_length(),
+                             seeded_checksum(seed, &entries_encoded))
+        .with_flags(flags);
+    let header_encoded = serialize(&header, Infinite).unwrap();
+    out_file.write_all(&header_encoded)?;
+
+    // Compute header checksum, serialize it, and write it to archive.
+    let header_checksum = seeded_checksum(seed, &header_encoded);
+    let header_checksum_encoded = serialize(
+        &header_checksum,
+        Bounded(mem::size_of::<u64>() as u64)
+    ).unwrap();
+    out_file.write_all(&header_checksum_encoded)?;
+    
+    // Write serialized entries table to archive.
+    out_file.write_all(&entries_encoded)?;
+
+    // Pad archive with zeros to ensure files begin at a multiple of `page_size`.
+    let start_length = header_encoded.len() + header_checksum_encoded.len() +
+        entries_encoded.len();
+    let padding_length = (header.file_offset as usize) - start_length;
+    let padding: Vec<u8> = vec![0u8; padding_length];
+    out_file.write_all(&padding)?;
+
+    Ok(())
+}
+
+// This function writes the entries table, header, header checksum, and
+// footer after file contents that start at `file_offset` and end at
+// `file_contents_end`.
+fn write_trailer<H: Write>(out_file: &mut H,
+                           entries: &Entries,
+                           flags: u64,
+                           page_size: u64,
+                           file_offset: u64,
+                           file_contents_end: u64,
+                           seed: u64) -> Result<()> {
+    let entries_encoded = encode_entries(entries, flags, seed);
+    out_file.write_all(&entries_encoded)?;
+
+    let header = Header::new_trailer(page_size,
+                                     file_offset,
+                                     entries_encoded.len() as u64,
+                                     file_contents_end,
+                                     seeded_checksum(seed, &entries_encoded))
+        .with_flags(flags);
+    let header_encoded = serialize(&header, Infinite).unwrap();
+    out_file.write_all(&header_encoded)?;
+
+    let header_checksum = seeded_checksum(seed, &header_encoded);
+    let header_checksum_encoded = serialize(
+        &header_checksum,
+        Bounded(mem::size_of::<u64>() as u64)
+    ).unwrap();
+    out_file.write_all(&header_checksum_encoded)?;
+
+    let footer = Footer {
+        header_offset: file_contents_end + entries_encoded.len() as u64,
+        id: *TRAILER_ID,
+    };
+    out_file.write_all(&serialize(&footer, Infinite).unwrap())?;
+
+    Ok(())
+}
+
+// This function serializes the entries table. In archives with SHA-256
+// digests, the digest of the table is appended to it (and is included in
+// `entries_length` and `entries_checksum`).
+fn encode_entries(entries: &Entries, flags: u64, seed: u64) -> Vec<u8> {
+    let mut entries_encoded: Vec<u8> = serialize(entries, Infinite).unwrap();
+
+    if flags & FLAG_SHA256 != 0 {
+        let digest = seeded_digest(seed, &entries_encoded);
+        entries_encoded.extend_from_slice(&digest);
+    }
+
+    entries_encoded
+}
+
+// This function compresses `bytes` with deflate. They are fed to the
+// encoder in the same chunks `copy_chunks()` reads, so the output matches
+// what `stream_contents()` writes.
+#[cfg(test)]
+fn deflate(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+
+    for chunk in bytes.chunks(COPY_BUFFER_SIZE) {
+        encoder.write_all(chunk)?;
+    }
+
+    encoder.finish()
+}
+
+// This function returns whether a file of `length` bytes that compresses to
+// `compressed_length` bytes should be stored compressed by
+// `CompressionMode::Auto`.
+fn worth_compressing(length: u64, compressed_length: u64) -> bool {
+    compressed_length.saturating_mul(100) < length.saturating_mul(95)
+}
+
+// This function copies the file at `path` to `out` in chunks, compressing it
+// if `compress` is set, so only one chunk is held in memory at a time. It
+// returns the checksum and digest of the contents (computed with `kind` and
+// `seed`) along with the number of bytes read and written.
+fn stream_contents<W: Write>(path: &Path,
+                             kind: ChecksumKind,
+                             seed: u64,
+                             compress: bool,
+                             out: W) -> io::Result<(u64, Option<[u8; DIGEST_SIZE]>, u64, u64)> {
+    let mut in_file = File::open(path)?;
+    let mut hasher = ContentsHasher::new(kind, seed);
+    let mut counter = CountingWriter {
+        inner: out,
+        count: 0,
+    };
+
+    let length = if compress {
+        let mut encoder = DeflateEncoder::new(&mut counter, Compression::default());
+        let length = copy_chunks(&mut in_file, &mut hasher, &mut encoder)?;
+        encoder.finish()?;
+
+        length
+    }
+    else {
+        copy_chunks(&mut in_file, &mut hasher, &mut counter)?
+    };
+
+    let (checksum, digest) = hasher.finish();
+
+    Ok((checksum, digest, length, counter.count))
+}
+
+// This function copies `in_file` to `out` in chunks of `COPY_BUFFER_SIZE`
+// bytes, feeding each one to `hasher`, and returns the number of bytes
+// copied. Both passes over a file use it, so they always agree on its
+// compressed length.
+fn copy_chunks<W: Write>(in_file: &mut File,
+                         hasher: &mut ContentsHasher,
+                         out: &mut W) -> io::Result<u64> {
+    let mut chunk = Vec::<u8>::with_capacity(COPY_BUFFER_SIZE);
+    let mut length = 0;
+
+    loop {
+        // Only the last chunk may be short.
+        chunk.clear();
+        in_file.take(COPY_BUFFER_SIZE as u64).read_to_end(&mut chunk)?;
+
+        if chunk.is_empty() {
+            return Ok(length);
+        }
+
+        hasher.update(&chunk);
+        out.write_all(&chunk)?;
+        length = length + chunk.len() as u64;
+    }
+}
+
+// This function returns whether the files at `a` and `b` have the same
+// contents, reading them a chunk at a time.
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let mut a = File::open(a)?;
+    let mut b = File::open(b)?;
+    let mut chunk_a = Vec::<u8>::with_capacity(COPY_BUFFER_SIZE);
+    let mut chunk_b = Vec::<u8>::with_capacity(COPY_BUFFER_SIZE);
+
+    loop {
+        chunk_a.clear();
+        chunk_b.clear();
+        (&mut a).take(COPY_BUFFER_SIZE as u64).read_to_end(&mut chunk_a)?;
+        (&mut b).take(COPY_BUFFER_SIZE as u64).read_to_end(&mut chunk_b)?;
+
+        if chunk_a != chunk_b {
+            return Ok(false);
+        }
+
+        if chunk_a.is_empty() {
+            return Ok(true);
+        }
+    }
+}
+
+// This function decompresses `bytes`, which must inflate to exactly `length`
+// bytes. `length` comes from the entries table, so it is not trusted to size
+// the buffer up front; decoding just stops one byte past it.
+fn inflate(bytes: &[u8], length: u64) -> io::Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    inflate_into(bytes, length, &mut contents)?;
+
+    Ok(contents)
+}
+
+// This function works like `inflate` but appends to `contents`, which must
+// be empty, so its buffer can be reused.
+fn inflate_into(bytes: &[u8], length: u64, contents: &mut Vec<u8>) -> io::Result<()> {
+    DeflateDecoder::new(bytes).take(length.saturating_add(1)).read_to_end(contents)?;
+
+    if contents.len() as u64 != length {
+        return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                  "decompressed length does not match entry"));
+    }
+
+    Ok(())
+}
+
+// This function inflates `stored` into `w` a `buffer` at a time and returns
+// the number of bytes written.
+fn inflate_chunks<W: Write>(stored: &[u8], buffer: &mut [u8], w: &mut W) -> Result<u64> {
+    let mut contents = DeflateDecoder::new(stored);
+    let mut written = 0;
+
+    loop {
+        let read = match contents.read(buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(Error::Io(e)),
+        };
+
+        w.write_all(&buffer[..read])?;
+        written = written + read as u64;
+    }
+
+    Ok(written)
+}
+
+// This function returns the producer stamped into archives by default.
+fn default_producer() -> String {
+    format!("filearco {}", env!("CARGO_PKG_VERSION"))
+}
+
+// This function returns `name`, or `name` followed by the smallest `~N`
+// suffix for which `taken` returns false.
+fn unique_name<F: Fn(&str) -> bool>(name: String, taken: F) -> String {
+    if !taken(&name) {
+        return name;
+    }
+
+    (1..).map(|n| format!("{}~{}", name, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap()
+}
+
+// This function replaces `\\` separators in `name` with `/`, the separator
+// used by archive entry names.
+fn normalize_separators(name: &str) -> Cow<'_, str> {
+    if name.contains('\\') {
+        Cow::Owned(name.replace('\\', "/"))
+    }
+    else {
+        Cow::Borrowed(name)
+    }
+}
+
+// This function formats a line of a checksum manifest like `sha256sum`
+// does. Names containing a backslash or newline are escaped, which is marked
+// by a backslash at the start of the line.
+fn manifest_line(name: &str, digest: &[u8; DIGEST_SIZE]) -> String {
+    let hex = digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+
+    if name.contains('\\') || name.contains('\n') {
+        format!("\\{}  {}\n", hex, name.replace('\\', "\\\\").replace('\n', "\\n"))
+    }
+    else {
+        format!("{}  {}\n", hex, name)
+    }
+}
+
+// This function parses a line written by `manifest_line()`, returning the
+// digest and name. The name may also be preceded by `" *"`, which
+// `sha256sum` writes for files read in binary mode. It returns `None` if
+// the line is malformed.
+fn parse_manifest_line(line: &str) -> Option<([u8; DIGEST_SIZE], String)> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+
+    let hex = line.get(..2 * DIGEST_SIZE)?;
+    let name = line.get(2 * DIGEST_SIZE..)?;
+    let name = name.strip_prefix("  ").or_else(|| name.strip_prefix(" *"))?;
+
+    let mut digest = [0u8; DIGEST_SIZE];
+    for (index, byte) in digest.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(2 * index..2 * index + 2)?, 16).ok()?;
+    }
+
+    if !escaped {
+        return Some((digest, name.to_string()));
+    }
+
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            _ => return None,
+        }
+    }
+
+    Some((digest, unescaped))
+}
+
+// This function guesses the content type of a file from the extension of its
+// name. Unknown extensions give None.
+fn guess_content_type(name: &str) -> Option<&'static str> {
+    let extension = match Path::new(name).extension().and_then(|e| e.to_str()) {
+        Some(extension) => extension.to_lowercase(),
+        None => return None,
+    };
+
+    let content_type = match extension.as_str() {
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" => "application/javascript",
+        "json" => "application/json",
+        "txt" => "text/plain",
+        "md" => "text/markdown",
+        "toml" => "application/toml",
+        "xml" => "application/xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        _ => return None,
+    };
+
+    Some(content_type)
+}
+
+// This function returns an HMAC-SHA256 keyed with `key`.
+fn new_mac(key: &[u8]) -> Hmac<Sha256> {
+    // HMAC accepts keys of any length.
+    Hmac::<Sha256>::new_from_slice(key).unwrap()
+}
+
+// This function computes the SHA-256 digest of `bytes`, preceded by `seed`
+// unless it is 0.
+fn seeded_digest(seed: u64, bytes: &[u8]) -> [u8; DIGEST_SIZE] {
+    let mut hasher = Sha256::new();
+
+    if seed != 0 {
+        hasher.update(seed.to_le_bytes());
+    }
+
+    hasher.update(bytes);
+
+    let mut digest = [0u8; DIGEST_SIZE];
+    digest.copy_from_slice(&hasher.finalize());
+    digest
+}
+
+// This function computes the checksum of `bytes` with `seed` as the initial
+// value. A seed of 0 gives the plain CRC-64 checksum.
+fn seeded_checksum(seed: u64, bytes: &[u8]) -> u64 {
+    update_checksum(seed, &ISO_TABLE, bytes)
+}
+
+// This function returns the smallest multiple of `alignment` that is greater
+// than or equal to `length`.
+fn align_to(length: u64, alignment: u64) -> u64 {
+    let alignment = cmp::max(alignment, 1);
+
+    length.div_ceil(alignment) * alignment
+}
+
+/// This function returns the smallest multiple of `page_size` greater than
+/// or equal to the given length. Callers pass the page size of the archive
+/// being read or written, which need not be that of this system.
+///
+/// # Arguments
+///
+/// * length - the input number
+///
+/// * page_size - the page size to align to (a power of 2)
+#[inline]
+pub(crate) fn get_aligned_length(length: u64, page_size: u64) -> u64 {
+    (length + (page_size-1)) & !(page_size-1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{read, remove_dir_all};
+
+    use memadvise::{advise, Advice};
+    use walkdir::WalkDir;
+    
+    use super::super::file_data::FileDatum;
+    use super::*;
+
+    fn get_file_data_stub<P: AsRef<Path>>(base_path: P) -> Result<FileData> {
+        let mut data = Vec::<FileDatum>::new();
+        data.push(FileDatum::from_parts(
+            String::from("Cargo.toml"),
+            328,
+            10574576474013701409,
+        ));
+        data.push(FileDatum::from_parts(
+            String::from("LICENSE-APACHE"),
+            10771,
+            8740797956101379381,
+        ));
+        data.push(FileDatum::from_parts(
+            String::from("LICENSE-MIT"),
+            1082,
+            13423357612537305206,
+        ));
+        
+        Ok(FileData::from_parts(
+            base_path.as_ref().to_path_buf(),
+            data,
+        ))
+    }
+
+    fn get_simple() -> Vec<String> {
+        let mut v = Vec::<String>::new();
+
+        v.push(String::from("Cargo.toml"));
+        v.push(String::from("LICENSE-APACHE"));
+        v.push(String::from("LICENSE-MIT"));
+
+        v
+    }
+
+    #[test]
+    fn test_v1_get_rounded_length() {
+        assert_eq!(get_aligned_length(0, 4096), 0);
+        assert_eq!(get_aligned_length(4096, 4096), 4096);
+        assert_eq!(get_aligned_length(4096+1, 4096), 2 * 4096);
+        assert_eq!(get_aligned_length(2*4096 - 1, 4096), 2 * 4096);
+        assert_eq!(get_aligned_length(4096+1, 16384), 16384);
+        assert_eq!(get_aligned_length(4096+1, 512), 4096 + 512);
+    }
+
+    #[test]
+    fn test_v1_filearco_foreign_page_size() {
+        let host_page_size = get_page_size() as u64;
+        create_dir_all("tmptest").ok().unwrap();
+
+        // Archives made on systems with smaller and larger pages.
+        for &page_size in [512, host_page_size * 4].iter() {
+            assert!(page_size != host_page_size);
+
+            let archive_path = format!("tmptest/test_v1_filearco_foreign_page_size_{}.fac",
+                                       page_size);
+            {
+                let file_data = ::file_data::get("testarchives/reqchandocs").ok().unwrap();
+                let archive_file = File::create(&archive_path).ok().unwrap();
+                let options = MakeOptions::new().alignment(page_size);
+                FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+            }
+
+            // Files appended later are aligned to the stored page size too.
+            let file_data = ::file_data::get("testarchives/simple").ok().unwrap();
+            FileArco::append_with(&archive_path, file_data, false).ok().unwrap();
+
+            let archive = FileArco::new(&archive_path).ok().unwrap();
+            assert_eq!(archive.page_size(), page_size);
+            assert!(archive.get("Cargo.toml").is_some());
+            assert!(archive.get("main.css").is_some());
+
+            for (name, info) in archive.entries() {
+                assert_eq!(info.offset() % page_size, 0);
+                assert_eq!(info.aligned_len(), get_aligned_length(info.len(), page_size));
+                assert!(archive.get(name).unwrap().is_valid());
+            }
+
+            assert!(archive.verify_all().into_iter().all(|(_, valid)| valid));
+        }
+    }
+
+    #[test]
+    fn test_v1_entries_new() {
+        let file_data = get_file_data_stub(&Path::new("testarchives/simple")).ok().unwrap();
+        let entries = Entries::new(file_data, get_page_size() as u64).ok().unwrap();
+
+        let simple = get_simple();
+
+        for name in simple.iter() {
+            assert!(entries.files.contains_key(name));
+        }
+    }
+
+    #[test]
+    fn test_v1_entries_from_metadata() {
+        let files = vec![(String::from("a"), 0),
+                         (String::from("b"), 1),
+                         (String::from("c"), 4096),
+                         (String::from("d"), 5000),
+                         (String::from("a"), 10)];
+
+        let entries = Entries::from_metadata(&files, 4096);
+        assert_eq!(entries.files.len(), 4);
+        assert_eq!(entries.files["a"].length, 0);
+
+        let mut sorted = entries.files.values().collect::<Vec<_>>();
+        sorted.sort_by_key(|entry| (entry.offset, entry.aligned_length));
+
+        let mut offset = 0;
+        for entry in sorted.into_iter() {
+            assert_eq!(entry.offset, offset);
+            assert_eq!(entry.aligned_length % 4096, 0);
+            assert!(entry.aligned_length >= entry.length);
+            assert_eq!(entry.checksum, 0);
+            offset = offset + entry.aligned_length;
+        }
+        assert_eq!(offset, 4 * 4096);
+        assert_eq!(entries.total_aligned_length(), offset);
+    }
+
+    #[test]
+    fn test_v1_estimated_size() {
+        let base_path = Path::new("testarchives/simple");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let files = file_data.clone().into_vec().iter()
+            .map(|datum| (datum.name(), datum.len()))
+            .collect::<Vec<_>>();
+
+        let archive_path = Path::new("tmptest/test_v1_estimated_size.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        assert_eq!(estimated_size(&files, get_page_size() as u64).ok().unwrap(),
+                   archive_path.metadata().ok().unwrap().len());
+
+        for &alignment in [0, 3000].iter() {
+            match estimated_size(&files, alignment) {
+                Err(Error::FileArcoV1(FileArcoV1Error::InvalidAlignment(a))) => {
+                    assert_eq!(a, alignment);
+                },
+                _ => panic!("invalid alignment was accepted"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_make() {
+        let base_path = Path::new("testarchives/simple");
+        let file_data = get_file_data_stub(base_path).ok().unwrap();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make.fac");
+
+        // Create directory if it does not exist
+        if let Some(parent) = archive_path.parent() {
+            create_dir_all(parent).ok().unwrap();
+        }
+
+        let archive_file = File::create(archive_path).ok().unwrap();
+        FileArco::make(file_data, archive_file).ok().unwrap();
+    }
+
+    #[test]
+    fn test_v1_filearco_make_counted() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+
+        let mut archive_bytes = Vec::<u8>::new();
+        FileArco::make(file_data.clone(), &mut archive_bytes).ok().unwrap();
+
+        let length = FileArco::make_counted(file_data, io::sink()).ok().unwrap();
+        assert_eq!(length, archive_bytes.len() as u64);
+    }
+
+    #[test]
+    fn test_v1_filearco_make_with_progress() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let mut names = file_data.clone().into_vec().iter()
+            .map(|datum| datum.name())
+            .collect::<Vec<_>>();
+        names.sort();
+
+        let mut expected = Vec::<u8>::new();
+        FileArco::make(file_data.clone(), &mut expected).ok().unwrap();
+
+        let mut started = Vec::<(String, usize, usize)>::new();
+        let mut bytes_written = 0;
+        let mut archive_bytes = Vec::<u8>::new();
+        FileArco::make_with_progress(file_data, &mut archive_bytes, |event| {
+            match event {
+                ProgressEvent::StartedFile { name, index, total } => {
+                    started.push((name.to_string(), index, total));
+                },
+                ProgressEvent::BytesWritten(length) => {
+                    bytes_written = bytes_written + length;
+                },
+            }
+        }).ok().unwrap();
+
+        // The entries table is serialized from a `HashMap`, so two runs may
+        // order it differently. Only the lengths can be compared.
+        assert_eq!(archive_bytes.len(), expected.len());
+
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+        assert_eq!(bytes_written, archive.unique_payload_bytes());
+
+        for (i, &(_, index, total)) in started.iter().enumerate() {
+            assert_eq!(index, i);
+            assert_eq!(total, names.len());
+        }
+
+        let mut reported = started.into_iter().map(|(name, _, _)| name).collect::<Vec<_>>();
+        reported.sort();
+        assert_eq!(reported, names);
+    }
+
+    #[test]
+    fn test_v1_archive_writer() {
+        let files: Vec<(&str, &[u8])> = vec![
+            ("first.txt", b"first file"),
+            ("dir/second.txt", b"second file"),
+            ("third.bin", &[0u8, 1, 2, 3, 255]),
+        ];
+
+        let archive_path = Path::new("tmptest/test_v1_archive_writer.fac");
+        let spool_path = Path::new("tmptest/test_v1_archive_writer.spool");
+
+        // Create directory if it does not exist
+        if let Some(parent) = archive_path.parent() {
+            create_dir_all(parent).ok().unwrap();
+        }
+
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = WriterOptions::new().spool_path(Some(spool_path));
+            let mut writer = ArchiveWriter::new(archive_file, options).ok().unwrap();
+
+            for &(name, contents) in files.iter() {
+                writer.add_file(name, contents).ok().unwrap();
+            }
+
+            writer.finish().ok().unwrap();
+        }
+
+        assert!(!spool_path.exists());
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        for &(name, contents) in files.iter() {
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), contents);
+        }
+    }
+
+    #[test]
+    fn test_v1_archive_writer_trailer() {
+        let files: Vec<(&str, &[u8])> = vec![
+            ("first.txt", b"first file"),
+            ("dir/second.txt", b"second file"),
+            ("third.bin", &[0u8, 1, 2, 3, 255]),
+        ];
+
+        let archive_path = Path::new("tmptest/test_v1_archive_writer_trailer.fac");
+
+        // Create directory if it does not exist
+        if let Some(parent) = archive_path.parent() {
+            create_dir_all(parent).ok().unwrap();
+        }
+
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = WriterOptions::new().layout(Layout::Trailer);
+            let mut writer = ArchiveWriter::new(archive_file, options).ok().unwrap();
+
+            for &(name, contents) in files.iter() {
+                writer.add_file(name, contents).ok().unwrap();
+            }
+
+            writer.finish().ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.inner.file_offset, 0);
+
+        for &(name, contents) in files.iter() {
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), contents);
+        }
+    }
+
+    #[test]
+    fn test_v1_archive_writer_duplicate() {
+        let mut writer = ArchiveWriter::new(Vec::<u8>::new(), WriterOptions::new())
+            .ok().unwrap();
+
+        writer.add_file("a.txt", &b"a"[..]).ok().unwrap();
+
+        match writer.add_file("a.txt", &b"b"[..]) {
+            Err(Error::FileArcoV1(FileArcoV1Error::DuplicateEntry(name))) => {
+                assert_eq!(name, "a.txt");
+            },
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_make_total_aligned_len() {
+        let base_path = Path::new("testarchives/simple");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let total_aligned_len = file_data.total_aligned_len();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_total_aligned_len.fac");
+
+        // Create directory if it does not exist
+        if let Some(parent) = archive_path.parent() {
+            create_dir_all(parent).ok().unwrap();
+        }
+
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let archive_len = archive_path.metadata().ok().unwrap().len();
+
+        assert_eq!(archive_len, archive.inner.file_offset + total_aligned_len);
+    }
+
+    #[test]
+    fn test_v1_filearco_make_dedup() {
+        let dir_path = Path::new("tmptest/test_v1_filearco_make_dedup");
+        create_dir_all(dir_path.join("copy")).ok().unwrap();
+
+        let mut license = Vec::<u8>::new();
+        File::open("testarchives/simple/LICENSE-MIT").ok().unwrap()
+            .read_to_end(&mut license).ok().unwrap();
+
+        for name in ["LICENSE-MIT", "copy/LICENSE-MIT"].iter() {
+            File::create(dir_path.join(name)).ok().unwrap()
+                .write_all(&license).ok().unwrap();
+        }
+        File::create(dir_path.join("other.txt")).ok().unwrap()
+            .write_all(b"other").ok().unwrap();
+
+        let file_data = ::file_data::get(dir_path).ok().unwrap();
+        let naive_len = file_data.total_aligned_len();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_dedup.fac");
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let archive_len = archive_path.metadata().ok().unwrap().len();
+
+        assert!(archive_len < archive.inner.file_offset + naive_len);
+
+        for name in ["LICENSE-MIT", "copy/LICENSE-MIT"].iter() {
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), &license[..]);
+        }
+        assert_eq!(archive.get("other.txt").unwrap().as_slice(), b"other");
+    }
+
+    #[test]
+    fn test_v1_filearco_make_checksum_collision() {
+        // Flipping these bits adds a multiple of the CRC-64 polynomial, so
+        // both files have the same length and checksum.
+        let first = b"0123456789abcdef".to_vec();
+        let mut second = first.clone();
+        second[0] ^= 0x01;
+        second[7] ^= 0xb0;
+        second[8] ^= 0x01;
+        assert_eq!(seeded_checksum(0, &first), seeded_checksum(0, &second));
+
+        let dir_path = Path::new("tmptest/test_v1_filearco_make_checksum_collision");
+        create_dir_all(dir_path).ok().unwrap();
+        File::create(dir_path.join("first")).ok().unwrap().write_all(&first).ok().unwrap();
+        File::create(dir_path.join("second")).ok().unwrap().write_all(&second).ok().unwrap();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_checksum_collision.fac");
+        {
+            let file_data = ::file_data::get(dir_path).ok().unwrap();
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let mut typed_bytes = Vec::<u8>::new();
+        FileArco::make_from_typed_entries(vec![(String::from("first"), first.clone(), None),
+                                               (String::from("second"), second.clone(), None)],
+                                          &mut typed_bytes).ok().unwrap();
+
+        let archives = [FileArco::new(archive_path).ok().unwrap(),
+                        FileArco::from_bytes(Arc::new(typed_bytes)).ok().unwrap()];
+
+        for archive in archives.iter() {
+            assert!(archive.entry_info("first").unwrap().offset() !=
+                    archive.entry_info("second").unwrap().offset());
+            assert_eq!(archive.get("first").unwrap().as_slice(), &first[..]);
+            assert_eq!(archive.get("second").unwrap().as_slice(), &second[..]);
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_make_empty_files() {
+        let dir_path = Path::new("tmptest/test_v1_filearco_make_empty_files");
+        create_dir_all(dir_path.join("dir")).ok().unwrap();
+
+        let files: [(&str, &[u8]); 4] = [(".gitkeep", b""),
+                                         ("a.txt", b"first file"),
+                                         ("dir/.gitkeep", b""),
+                                         ("z.txt", b"last file")];
+        for &(name, contents) in files.iter() {
+            File::create(dir_path.join(name)).ok().unwrap()
+                .write_all(contents).ok().unwrap();
+        }
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_empty_files.fac");
+        {
+            let file_data = ::file_data::get(dir_path).ok().unwrap();
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.file_count(), files.len());
+
+        for &(name, contents) in files.iter() {
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.len(), contents.len() as u64);
+            assert_eq!(fileref.as_slice(), contents);
+        }
+
+        // Only the non-empty files take up space.
+        assert_eq!(archive.unique_payload_bytes(), 2 * get_page_size() as u64);
+
+        // Empty files share the offset of the file after them.
+        let offset = |name| archive.entry_info(name).unwrap().offset();
+        assert_eq!(offset(".gitkeep"), 0);
+        assert_eq!(offset(".gitkeep"), offset("a.txt"));
+        assert_eq!(offset("dir/.gitkeep"), offset(".gitkeep"));
+        assert_eq!(offset("z.txt"), get_page_size() as u64);
+    }
+
+    #[test]
+    fn test_v1_filearco_make_compressed() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let names = file_data.clone().into_vec().iter()
+            .map(|datum| datum.name())
+            .collect::<Vec<_>>();
+
+        let plain_path = Path::new("tmptest/test_v1_filearco_make_compressed_plain.fac");
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_compressed.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let plain_file = File::create(plain_path).ok().unwrap();
+            FileArco::make(file_data.clone(), plain_file).ok().unwrap();
+
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().compression(CompressionMode::Deflate);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        let plain = FileArco::new(plain_path).ok().unwrap();
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert!(!plain.inner.compressed);
+        assert!(archive.inner.compressed);
+
+        let plain_len = plain_path.metadata().ok().unwrap().len();
+        let archive_len = archive_path.metadata().ok().unwrap().len();
+        assert!(archive_len < plain_len);
+
+        for name in names.iter() {
+            let mut expected = Vec::<u8>::new();
+            File::open(base_path.join(name)).ok().unwrap()
+                .read_to_end(&mut expected).ok().unwrap();
+
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.len(), expected.len() as u64);
+            assert_eq!(fileref.as_slice(), &expected[..]);
+            assert_eq!(fileref.read_decompressed().ok().unwrap(), expected);
+        }
+
+        for (_, bytes, compressed) in archive.raw_iter() {
+            assert_eq!(compressed, !bytes.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_v1_fileref_decompress_to() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let names = file_data.clone().into_vec().iter()
+            .map(|datum| datum.name())
+            .collect::<Vec<_>>();
+
+        let archive_path = Path::new("tmptest/test_v1_fileref_decompress_to.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().compression(CompressionMode::Deflate);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert!(archive.inner.compressed);
+
+        for name in names.iter() {
+            let expected = read(base_path.join(name)).ok().unwrap();
+
+            let mut contents = Vec::<u8>::new();
+            let written = archive.get(name).unwrap()
+                .decompress_to(&mut contents).ok().unwrap();
+            assert_eq!(written, expected.len() as u64);
+            assert_eq!(contents, expected);
+        }
+
+        // Uncompressed files are written as they are stored.
+        let plain = FileArco::new(Path::new("testarchives/simple_v1.fac")).ok().unwrap();
+        let fileref = plain.get("Cargo.toml").unwrap();
+        let mut contents = Vec::<u8>::new();
+        assert_eq!(fileref.decompress_to(&mut contents).ok().unwrap(), fileref.len());
+        assert_eq!(&contents[..], fileref.as_slice());
+    }
+
+    #[test]
+    fn test_v1_fileref_as_slice_checked() {
+        let mut archive_bytes = {
+            let mut writer = ArchiveWriter::new(Vec::<u8>::new(), WriterOptions::new())
+                .ok().unwrap();
+            writer.add_file("a.txt", &b"first file"[..]).ok().unwrap();
+            writer.add_file("b.txt", &b"second file"[..]).ok().unwrap();
+            writer.finish().ok().unwrap()
+        };
+
+        // Flip a bit of the first file, which starts after the prelude.
+        let (header, _) = read_index(&archive_bytes, 0, &mut OpenMetrics::default()).ok().unwrap();
+        archive_bytes[header.file_offset as usize] ^= 1;
+
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+
+        let corrupted = archive.get("a.txt").unwrap();
+        match corrupted.as_slice_checked() {
+            Err(Error::FileArcoV1(FileArcoV1Error::CorruptedFile(_))) => {},
+            _ => panic!("corrupted file was returned"),
+        }
+        assert_eq!(corrupted.validated.load(Ordering::Relaxed), INVALID);
+        assert!(corrupted.as_slice_checked().is_err());
+
+        let valid = archive.get("b.txt").unwrap();
+        assert_eq!(valid.validated.load(Ordering::Relaxed), UNCHECKED);
+        assert_eq!(valid.as_slice_checked().ok().unwrap(), b"second file");
+        assert_eq!(valid.validated.load(Ordering::Relaxed), VALID);
+        assert_eq!(valid.as_slice_checked().ok().unwrap(), b"second file");
+    }
+
+    #[test]
+    fn test_v1_filearco_open_if_compatible() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+
+        let archive = FileArco::open_if_compatible(archive_path, &[1]).ok().unwrap();
+        assert_eq!(archive.version_number(), 1);
+        assert!(archive.get("Cargo.toml").is_some());
+
+        match FileArco::open_if_compatible(archive_path, &[2]) {
+            Err(Error::FileArcoV1(FileArcoV1Error::IncompatibleVersion(1))) => {},
+            _ => panic!("incompatible archive was opened"),
+        }
+
+        // Archives that fail to open are reported as such.
+        match FileArco::open_if_compatible(Path::new("Cargo.toml"), &[1]) {
+            Err(Error::FileArcoV1(FileArcoV1Error::IncompatibleVersion(_))) | Ok(_) => {
+                panic!("non-archive was opened")
+            },
+            Err(_) => {},
+        }
+
+        // Archives made by this crate have a newer revision and flags, but
+        // the same major version.
+        let new_path = Path::new("tmptest/test_v1_filearco_open_if_compatible.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let file_data = get_file_data_stub(Path::new("testarchives/simple")).ok().unwrap();
+            let new_file = File::create(new_path).ok().unwrap();
+            FileArco::make(file_data, new_file).ok().unwrap();
+        }
+
+        let archive = FileArco::open_if_compatible(new_path, &[1]).ok().unwrap();
+        assert!(archive.version_number() != 1);
+        assert!(archive.get("Cargo.toml").is_some());
+
+        match FileArco::open_if_compatible(new_path, &[2]) {
+            Err(Error::FileArcoV1(FileArcoV1Error::IncompatibleVersion(version_number))) => {
+                assert_eq!(version_number, archive.version_number());
+            },
+            _ => panic!("incompatible archive was opened"),
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_new_with_decompress_buffer() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let names = file_data.clone().into_vec().iter()
+            .map(|datum| datum.name())
+            .collect::<Vec<_>>();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_new_with_decompress_buffer.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().compression(CompressionMode::Deflate);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        let default = FileArco::new(archive_path).ok().unwrap();
+        let tiny = FileArco::new_with_decompress_buffer(archive_path, 7).ok().unwrap();
+        let large = FileArco::new_with_decompress_buffer(archive_path, 1 << 20).ok().unwrap();
+        let zero = FileArco::new_with_decompress_buffer(archive_path, 0).ok().unwrap();
+        assert_eq!(default.inner.decompress_buffer_size, COPY_BUFFER_SIZE);
+        assert_eq!(tiny.inner.decompress_buffer_size, 7);
+        assert_eq!(zero.inner.decompress_buffer_size, 1);
+
+        for name in names.iter() {
+            let expected = read(base_path.join(name)).ok().unwrap();
+
+            for archive in [&tiny, &large, &zero].iter() {
+                let fileref = archive.get(name).unwrap();
+                assert_eq!(fileref.read_decompressed().ok().unwrap(), expected);
+
+                let mut contents = Vec::<u8>::new();
+                fileref.decompress_to(&mut contents).ok().unwrap();
+                assert_eq!(contents, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_make_compressed_streamed() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let names = file_data.clone().into_vec().iter()
+            .map(|datum| datum.name())
+            .collect::<Vec<_>>();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_compressed_streamed.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().compression(CompressionMode::Deflate);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        // Some files span several chunks.
+        assert!(names.iter().any(|name| {
+            archive.get(name).unwrap().len() > COPY_BUFFER_SIZE as u64
+        }));
+
+        for name in names.iter() {
+            let expected = read(base_path.join(name)).ok().unwrap();
+            let fileref = archive.get(name).unwrap();
+
+            assert_eq!(fileref.checksum, ::crc::crc64::checksum_iso(&expected));
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.read_decompressed().ok().unwrap(), expected);
+            if !expected.is_empty() {
+                assert_eq!(fileref.stored_length, deflate(&expected).ok().unwrap().len() as u64);
+            }
+        }
+
+        // Compressed contents are not kept from the first pass, so changing
+        // the file after it was laid out is caught when it is read again.
+        let dir_path = Path::new("tmptest/test_v1_filearco_make_compressed_streamed");
+        create_dir_all(dir_path).ok().unwrap();
+        let original = b"compress me ".repeat(100);
+        File::create(dir_path.join("a.txt")).ok().unwrap().write_all(&original).ok().unwrap();
+
+        let file_data = ::file_data::get(dir_path).ok().unwrap();
+        let options = MakeOptions::new().compression(CompressionMode::Deflate);
+        let mut archive_bytes = Vec::<u8>::new();
+        let result = FileArco::make_reporting(file_data, &mut archive_bytes, &options, |event| {
+            if let ProgressEvent::StartedFile { .. } = event {
+                File::create(dir_path.join("a.txt")).ok().unwrap()
+                    .write_all(b"changed").ok().unwrap();
+            }
+        });
+
+        match result {
+            Err(Error::FileArcoV1(FileArcoV1Error::SourceChanged(ref name))) => {
+                assert_eq!(name, "a.txt");
+            },
+            _ => panic!("compressed contents were retained from the first pass"),
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_make_compressed_auto() {
+        let dir_path = Path::new("tmptest/test_v1_filearco_make_compressed_auto");
+        create_dir_all(dir_path).ok().unwrap();
+
+        let text = b"All work and no play makes Jack a dull boy.\n".repeat(256);
+
+        // xorshift64 output does not compress.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let blob = (0..16384).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        }).collect::<Vec<_>>();
+
+        File::create(dir_path.join("text.txt")).ok().unwrap()
+            .write_all(&text).ok().unwrap();
+        File::create(dir_path.join("blob.bin")).ok().unwrap()
+            .write_all(&blob).ok().unwrap();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_compressed_auto.fac");
+        {
+            let file_data = ::file_data::get(dir_path).ok().unwrap();
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().compression(CompressionMode::Auto);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert!(archive.inner.compressed);
+
+        let stored = archive.raw_iter()
+            .map(|(name, bytes, compressed)| (name, (bytes.len(), compressed)))
+            .collect::<HashMap<_, _>>();
+        assert!(stored["text.txt"].1);
+        assert!(stored["text.txt"].0 < text.len());
+        assert!(!stored["blob.bin"].1);
+        assert_eq!(stored["blob.bin"].0, blob.len());
+
+        for &(name, contents) in [("text.txt", &text), ("blob.bin", &blob)].iter() {
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), &contents[..]);
+            assert_eq!(fileref.read_decompressed().ok().unwrap(), *contents);
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_make_source_changed() {
+        let dir_path = Path::new("tmptest/test_v1_filearco_make_source_changed");
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_source_changed.fac");
+        create_dir_all(dir_path).ok().unwrap();
+
+        // Same length, different contents; different length; no longer
+        // empty.
+        let changes: [(&[u8], &[u8]); 3] = [(b"original", b"modified"),
+                                            (b"original", b"original contents"),
+                                            (b"", b"grown")];
+
+        for &(before, after) in changes.iter() {
+            File::create(dir_path.join("changed.txt")).ok().unwrap()
+                .write_all(before).ok().unwrap();
+            File::create(dir_path.join("stable.txt")).ok().unwrap()
+                .write_all(b"stable").ok().unwrap();
+
+            let file_data = ::file_data::get(dir_path).ok().unwrap();
+
+            File::create(dir_path.join("changed.txt")).ok().unwrap()
+                .write_all(after).ok().unwrap();
+
+            for &compression in [CompressionMode::None, CompressionMode::Deflate].iter() {
+                let archive_file = File::create(archive_path).ok().unwrap();
+                let options = MakeOptions::new().compression(compression);
+
+                match FileArco::make_with(file_data.clone(), archive_file, &options) {
+                    Err(Error::FileArcoV1(FileArcoV1Error::SourceChanged(ref name))) => {
+                        assert_eq!(name, "changed.txt");
+                    },
+                    _ => panic!("changed source file was archived"),
+                }
+            }
+        }
+
+        // Unchanged files are archived as usual.
+        let file_data = ::file_data::get(dir_path).ok().unwrap();
+        let archive_file = File::create(archive_path).ok().unwrap();
+        FileArco::make(file_data, archive_file).ok().unwrap();
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.get("changed.txt").unwrap().as_slice(), b"grown");
+    }
+
+    #[test]
+    fn test_v1_filearco_make_inline() {
+        let dir_path = Path::new("tmptest/test_v1_filearco_make_inline");
+        create_dir_all(dir_path.join("tiny")).ok().unwrap();
+
+        let mut files = (0..20)
+            .map(|i| (format!("tiny/{}.txt", i), format!("tiny file {}\n", i).into_bytes()))
+            .collect::<Vec<_>>();
+        files.push((String::from("same.txt"), b"tiny file 0\n".to_vec()));
+        files.push((String::from("large.txt"), vec![b'x'; 1000]));
+
+        for &(ref name, ref contents) in files.iter() {
+            File::create(dir_path.join(name)).ok().unwrap()
+                .write_all(contents).ok().unwrap();
+        }
+        File::create(dir_path.join("empty.txt")).ok().unwrap();
+
+        let make = |options: &MakeOptions| {
+            let file_data = ::file_data::get(dir_path).ok().unwrap();
+            let mut archive_bytes = Vec::<u8>::new();
+            FileArco::make_with(file_data, &mut archive_bytes, options).ok().unwrap();
+            FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap()
+        };
+
+        let options = MakeOptions::new().alignment(4096);
+        let plain = make(&options);
+        let inlined = make(&options.clone().inline_threshold(100));
+
+        for &(ref name, ref contents) in files.iter() {
+            let fileref = inlined.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), &contents[..]);
+        }
+        assert_eq!(inlined.get("empty.txt").unwrap().len(), 0);
+
+        let entries = &inlined.inner.entries.files;
+        assert!(entries["tiny/0.txt"].inline.is_some());
+        assert!(entries["same.txt"].inline.is_some());
+        assert!(entries["large.txt"].inline.is_none());
+        assert!(inlined.pages_for_names(&["tiny/0.txt"]).is_empty());
+
+        // Only the large file is left in the file contents section.
+        let contents_length = |archive: &FileArco| {
+            archive.inner.storage.len() as u64 - archive.inner.file_offset
+        };
+        assert_eq!(contents_length(&inlined), 4096);
+        assert_eq!(contents_length(&plain), 21 * 4096);
+        assert!(inlined.inner.storage.len() < plain.inner.storage.len());
+
+        // Compressed and checksummed archives can inline files too.
+        let options = MakeOptions::new()
+            .inline_threshold(100)
+            .compression(CompressionMode::Deflate)
+            .checksum(ChecksumKind::Sha256);
+        let compressed = make(&options);
+
+        for &(ref name, ref contents) in files.iter() {
+            let fileref = compressed.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), &contents[..]);
+        }
+        assert!(!compressed.inner.entries.files["tiny/1.txt"].compressed);
+        assert!(compressed.inner.entries.files["large.txt"].compressed);
+    }
+
+    #[test]
+    fn test_v1_filearco_make_large_file() {
+        let dir_path = Path::new("tmptest/test_v1_filearco_make_large_file");
+        create_dir_all(dir_path).ok().unwrap();
+
+        // 6 MiB spans many copy chunks, and the last one is short.
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let large = (0..6 * 1024 * 1024 + 123).map(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            if i % 3 == 0 { state as u8 } else { b'a' }
+        }).collect::<Vec<_>>();
+
+        File::create(dir_path.join("large.bin")).ok().unwrap()
+            .write_all(&large).ok().unwrap();
+        File::create(dir_path.join("copy.bin")).ok().unwrap()
+            .write_all(&large).ok().unwrap();
+
+        for &compression in [CompressionMode::None, CompressionMode::Deflate].iter() {
+            let archive_path = Path::new("tmptest/test_v1_filearco_make_large_file.fac");
+            {
+                let file_data = ::file_data::get(dir_path).ok().unwrap();
+                let archive_file = File::create(archive_path).ok().unwrap();
+                let options = MakeOptions::new()
+                    .compression(compression)
+                    .checksum(ChecksumKind::Sha256);
+                FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+            }
+
+            let archive = FileArco::new(archive_path).ok().unwrap();
+
+            for name in ["large.bin", "copy.bin"].iter() {
+                let fileref = archive.get(name).unwrap();
+                assert!(fileref.is_valid());
+                assert_eq!(fileref.len(), large.len() as u64);
+                assert!(fileref.as_slice() == &large[..]);
+            }
+
+            // Identical files share a region.
+            assert_eq!(archive.entry_info("large.bin").unwrap().offset(),
+                       archive.entry_info("copy.bin").unwrap().offset());
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_make_reproducible() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+
+        let mut first = Vec::<u8>::new();
+        FileArco::make(file_data.clone(), &mut first).ok().unwrap();
+
+        // Each `HashMap` iterates in a different order.
+        for _ in 0..4 {
+            let mut second = Vec::<u8>::new();
+            FileArco::make(file_data.clone(), &mut second).ok().unwrap();
+            assert!(first == second);
+        }
+
+        // Files are laid out in name order.
+        let archive = FileArco::from_bytes(Arc::new(first)).ok().unwrap();
+        let mut entries = archive.entries().collect::<Vec<_>>();
+        entries.sort_by_key(|&(_, info)| info.offset());
+        let names = entries.iter().map(|&(name, _)| name).collect::<Vec<_>>();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+    }
+
+    #[test]
+    fn test_v1_filearco_make_with_alignment() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let names = file_data.clone().into_vec().iter()
+            .map(|datum| datum.name())
+            .collect::<Vec<_>>();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_with_alignment.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make_with_alignment(file_data.clone(), archive_file, 65536).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.page_size(), 65536);
+        assert_eq!(archive.inner.file_offset % 65536, 0);
+
+        for name in names.iter() {
+            let entry = &archive.inner.entries.files[name];
+            assert_eq!(entry.offset % 65536, 0);
+            assert_eq!(entry.aligned_length % 65536, 0);
+
+            let mut expected = Vec::<u8>::new();
+            File::open(base_path.join(name)).ok().unwrap()
+                .read_to_end(&mut expected).ok().unwrap();
+
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), &expected[..]);
+        }
+
+        match FileArco::make_with_alignment(file_data, Vec::<u8>::new(), 3000) {
+            Err(Error::FileArcoV1(FileArcoV1Error::InvalidAlignment(3000))) => {},
+            _ => panic!("alignment that is not a power of two should fail"),
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_pack_response() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let names = ["LICENSE-MIT", "Cargo.toml", "LICENSE-APACHE"];
+        let (body, index) = archive.pack_response(&names).ok().unwrap();
+
+        assert_eq!(index.len(), names.len());
+        let mut next_offset = 0;
+
+        for (&(ref name, offset, length), expected_name) in index.iter().zip(names.iter()) {
+            assert_eq!(name, expected_name);
+            assert_eq!(offset, next_offset);
+
+            let range = &body[offset as usize..(offset + length) as usize];
+            assert_eq!(range, archive.get(name).unwrap().as_slice());
+
+            next_offset = offset + length;
+        }
+        assert_eq!(next_offset, body.len() as u64);
+
+        match archive.pack_response(&["Cargo.toml", "missing.txt"]) {
+            Err(Error::Io(ref err)) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+            _ => panic!("missing file was packed"),
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_validate_names() {
+        let make_archive = |names: &[&str]| {
+            let mut writer = ArchiveWriter::new(Vec::<u8>::new(), WriterOptions::new())
+                .ok().unwrap();
+            for name in names.iter() {
+                writer.add_file(name, &b"contents"[..]).ok().unwrap();
+            }
+            FileArco::from_bytes(Arc::new(writer.finish().ok().unwrap())).ok().unwrap()
+        };
+
+        let archive = make_archive(&["a.txt", "dir/b.txt", "dir/sub/c.txt"]);
+        assert!(archive.validate_names().is_ok());
+
+        // These differ as strings but name the same file.
+        let archive = make_archive(&["dir/b.txt", "dir//b.txt"]);
+        match archive.validate_names() {
+            Err(Error::FileArcoV1(FileArcoV1Error::InvalidName(ref name))) => {
+                assert_eq!(name, "dir/b.txt");
+            },
+            _ => panic!("duplicate name was accepted"),
+        }
+
+        for bad_name in ["", "/etc/passwd", "../escape.txt", "dir/../b.txt",
+                         "./a.txt", "nul\0.txt"].iter() {
+            let archive = make_archive(&["ok.txt", bad_name]);
+            match archive.validate_names() {
+                Err(Error::FileArcoV1(FileArcoV1Error::InvalidName(ref name))) => {
+                    assert_eq!(name, bad_name);
+                },
+                _ => panic!("{:?} was accepted", bad_name),
+            }
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_unique_payload_bytes() {
+        let license = b"same contents".to_vec();
+        let blobs = vec![(String::from("a.txt"), license.clone()),
+                         (String::from("b.txt"), license.clone()),
+                         (String::from("c.txt"), license),
+                         (String::from("other.txt"), b"other".to_vec())];
+
+        let mut archive_bytes = Vec::<u8>::new();
+        FileArco::make_from_entries(blobs, &mut archive_bytes).ok().unwrap();
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+
+        let naive_len = archive.entries()
+            .map(|(_, info)| info.aligned_len())
+            .sum::<u64>();
+        let page_size = get_page_size() as u64;
+
+        assert_eq!(naive_len, 4 * page_size);
+        assert_eq!(archive.unique_payload_bytes(), 2 * page_size);
+
+        let plain = FileArco::new("testarchives/simple_v1.fac").ok().unwrap();
+        assert_eq!(plain.unique_payload_bytes(),
+                   plain.entries().map(|(_, info)| info.aligned_len()).sum::<u64>());
+    }
+
+    #[test]
+    fn test_v1_filearco_make_from_entries() {
+        let blobs = vec![(String::from("first.txt"), b"first blob".to_vec()),
+                         (String::from("dir/second.bin"), vec![0u8, 1, 2, 3, 255]),
+                         (String::from("empty.txt"), Vec::new())];
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_from_entries.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make_from_entries(blobs.clone(), archive_file).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.file_count(), blobs.len());
+
+        for &(ref name, ref contents) in blobs.iter() {
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), &contents[..]);
+        }
+
+        let duplicates = vec![(String::from("a"), Vec::new()),
+                              (String::from("a"), Vec::new())];
+        match FileArco::make_from_entries(duplicates, io::sink()) {
+            Err(Error::FileArcoV1(FileArcoV1Error::DuplicateEntry(ref name))) => {
+                assert_eq!(name, "a");
+            },
+            _ => panic!("duplicate entry was accepted"),
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_producer() {
+        let base_path = Path::new("testarchives/simple");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_producer.fac");
+        let anonymous_path = Path::new("tmptest/test_v1_filearco_producer_anonymous.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data.clone(), archive_file).ok().unwrap();
+
+            let anonymous_file = File::create(anonymous_path).ok().unwrap();
+            let options = MakeOptions::new().producer(None::<String>);
+            FileArco::make_with(file_data, anonymous_file, &options).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let producer = archive.producer().unwrap();
+        assert!(!producer.is_empty());
+        assert!(producer.starts_with("filearco "));
+
+        assert!(FileArco::new(anonymous_path).ok().unwrap().producer().is_none());
+
+        // Archives predating producers have none.
+        let legacy = FileArco::new("testarchives/simple_v1.fac").ok().unwrap();
+        assert!(legacy.producer().is_none());
+    }
+
+    #[test]
+    fn test_v1_filearco_seed() {
+        let base_path = Path::new("testarchives/simple");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_seed.fac");
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().seed(0x0123456789abcdef);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        let archive = FileArco::new_with_seed(archive_path, 0x0123456789abcdef)
+            .ok().unwrap();
+
+        for name in get_simple().iter() {
+            assert!(archive.get(name).unwrap().is_valid());
+        }
+
+        // The seed is not stored in the archive, so the wrong seed fails
+        // validation before any file is read.
+        match FileArco::new_with_seed(archive_path, 0xfedcba9876543210) {
+            Err(Error::FileArcoV1(FileArcoV1Error::CorruptedHeader)) => {},
+            _ => panic!("archive opened with wrong seed"),
+        }
+        assert!(FileArco::new(archive_path).is_err());
+    }
+
+    #[test]
+    fn test_v1_filearco_new_malformed_entries() {
+        // The entries table has a valid checksum but is too short to hold
+        // even the number of entries.
+        let entries_encoded = b"abcd";
+        let header = Header::new(get_page_size() as u64,
+                                 entries_encoded.len() as u64,
+                                 0,
+                                 seeded_checksum(0, entries_encoded));
+        let header_encoded = serialize(&header, Infinite).unwrap();
+        let header_checksum = seeded_checksum(0, &header_encoded);
+
+        let mut archive_bytes = header_encoded.clone();
+        archive_bytes.extend(serialize(&header_checksum, Infinite).unwrap());
+        archive_bytes.extend(&entries_encoded[..]);
+        archive_bytes.resize(header.file_offset as usize, 0);
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_new_malformed_entries.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        File::create(archive_path).ok().unwrap().write_all(&archive_bytes).ok().unwrap();
+
+        match FileArco::new(archive_path) {
+            Err(Error::FileArcoV1(FileArcoV1Error::DeserializeFailed)) => {},
+            _ => panic!("malformed entries table was accepted"),
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_open_timed() {
+        let archive_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(archive_path).ok().unwrap();
+        let file_count = file_data.len();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_open_timed.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let (archive, metrics) = FileArco::open_timed(archive_path).ok().unwrap();
+        assert_eq!(archive.file_count(), file_count);
+
+        let mut archive_bytes = Vec::<u8>::new();
+        File::open(archive_path).ok().unwrap()
+            .read_to_end(&mut archive_bytes).ok().unwrap();
+        let (header, _) = read_index(&archive_bytes, 0, &mut OpenMetrics::default()).ok().unwrap();
+
+        assert_eq!(metrics.entries_len(), header.entries_length);
+        assert!(metrics.map_time() > Duration::from_secs(0));
+        assert!(metrics.checksum_time() > Duration::from_secs(0));
+        assert!(metrics.deserialize_time() > Duration::from_secs(0));
+    }
+
+    #[test]
+    fn test_v1_filearco_copy_to() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let copy_path = Path::new("tmptest/test_v1_filearco_copy_to.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        archive.copy_to_synced(copy_path).ok().unwrap();
+
+        let mut original = Vec::<u8>::new();
+        File::open(archive_path).ok().unwrap().read_to_end(&mut original).ok().unwrap();
+        let mut copy = Vec::<u8>::new();
+        File::open(copy_path).ok().unwrap().read_to_end(&mut copy).ok().unwrap();
+        assert_eq!(copy, original);
+
+        let copied = FileArco::new(copy_path).ok().unwrap();
+        assert!(copied.verify_all().iter().all(|&(_, valid)| valid));
+        assert_eq!(copied.file_count(), archive.file_count());
+    }
+
+    #[test]
+    fn test_v1_filearco_from_bytes() {
+        let mut data = Vec::<u8>::new();
+        File::open("testarchives/simple_v1.fac").ok().unwrap()
+            .read_to_end(&mut data).ok().unwrap();
+        let data = Arc::new(data);
+
+        let mapped = FileArco::new("testarchives/simple_v1.fac").ok().unwrap();
+        let archive = FileArco::from_bytes(data.clone()).ok().unwrap();
+
+        for name in get_simple().iter() {
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), mapped.get(name).unwrap().as_slice());
+
+            // The contents are not copied out of the buffer.
+            let start = data.as_ptr() as usize;
+            let address = fileref.as_slice().as_ptr() as usize;
+            assert!(address >= start && address < start + data.len());
+        }
+
+        assert_eq!(archive.is_fully_resident(), Some(true));
+        archive.prefetch_many(&get_simple()).ok().unwrap();
+
+        let truncated = Arc::new(data[..100].to_vec());
+        assert!(FileArco::from_bytes(truncated).is_err());
+    }
+
+    #[test]
+    fn test_v1_filearco_new() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let simple = get_simple();
+
+        match FileArco::new(archive_path) {
+            Ok(archive) => {
+                for name in simple.iter() {
+                    assert!(archive.inner.entries.files.contains_key(name));
+                }
+            },
+            Err(err) => {
+                println!("test_v1_filearco_new {}", err.to_string());
+                assert!(false); },
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_header_fields() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive_bytes = {
+            let mut bytes = Vec::<u8>::new();
+            File::open(archive_path).ok().unwrap().read_to_end(&mut bytes).ok().unwrap();
+            bytes
+        };
+        let (header, _) = read_index(&archive_bytes, 0, &mut OpenMetrics::default()).ok().unwrap();
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.version_number(), header.version_number);
+        assert_eq!(archive.version_number() & 0xffff_ffff, 1);
+        assert_eq!(archive.revision(), header.revision());
+        assert_eq!(archive.file_length(), archive_bytes.len() as u64);
+        assert_eq!(archive.file_offset(), header.file_offset);
+        assert_eq!(archive.entries_length(), header.entries_length);
+        assert_eq!(archive.entries_checksum(), header.entries_checksum);
+    }
+
+    #[test]
+    fn test_v1_filearco_revisions() {
+        let mut archive_bytes = Vec::<u8>::new();
+        FileArco::make_from_entries(vec![(String::from("a.txt"), b"a\n".to_vec())],
+                                    &mut archive_bytes).ok().unwrap();
+
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes.clone())).ok().unwrap();
+        assert_eq!(archive.revision(), REVISION);
+
+        // Only the revision changes, so the header checksum has to be fixed
+        // up to reach the revision check.
+        let mut newer = archive_bytes;
+        newer[0x0c] = (REVISION + 1) as u8;
+        let header_length = header_size();
+        let checksum = seeded_checksum(0, &newer[..header_length]);
+        newer[header_length..header_length + CHECKSUM_SIZE]
+            .copy_from_slice(&checksum.to_le_bytes());
+
+        match FileArco::from_bytes(Arc::new(newer)) {
+            Err(Error::FileArcoV1(FileArcoV1Error::UnsupportedRevision)) => {},
+            _ => panic!("archive with a newer revision was opened"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_v1_filearco_from_raw_fd() {
+        use std::os::unix::io::IntoRawFd;
+
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let fd = File::open(archive_path).ok().unwrap().into_raw_fd();
+
+        let archive = unsafe { FileArco::from_raw_fd(fd) }.ok().unwrap();
+        let expected = FileArco::new(archive_path).ok().unwrap();
+
+        for name in get_simple().iter() {
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), expected.get(name).unwrap().as_slice());
+        }
+    }
+
+    #[test]
+    fn test_v1_check_addressable() {
+        let limit = u32::MAX as u64;
+        let files = vec![(String::from("small.bin"), 1024),
+                         (String::from("big.bin"), 5 << 30)];
+
+        let entries = Entries::from_metadata(&files[..1], 4096);
+        assert!(check_addressable(&entries, 4096, limit).is_ok());
+
+        // The offset alone can push a small file past the limit.
+        match check_addressable(&entries, limit, limit) {
+            Err(Error::FileArcoV1(FileArcoV1Error::FileTooLarge(ref name))) => {
+                assert_eq!(name, "small.bin");
+            },
+            _ => panic!("file past the limit was accepted"),
+        }
+
+        let entries = Entries::from_metadata(&files, 4096);
+        match check_addressable(&entries, 4096, limit) {
+            Err(Error::FileArcoV1(FileArcoV1Error::FileTooLarge(ref name))) => {
+                assert_eq!(name, "big.bin");
+            },
+            _ => panic!("file larger than the limit was accepted"),
+        }
+        assert!(check_addressable(&entries, 4096, u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_v1_filearco_entry_out_of_bounds() {
+        // The header claims one page of contents regardless of where the
+        // entry points, so only its checksums are consistent.
+        let files = vec![(String::from("a.bin"), 1024)];
+        let build = |offset: u64| {
+            let mut entries = Entries::from_metadata(&files, 4096);
+            entries.files.get_mut("a.bin").unwrap().offset = offset;
+
+            let entries_encoded = encode_entries(&entries, 0, 0);
+            let header = Header::new(4096,
+                                     entries_encoded.len() as u64,
+                                     4096,
+                                     seeded_checksum(0, &entries_encoded));
+            let header_encoded = serialize(&header, Infinite).unwrap();
+
+            let mut archive_bytes = header_encoded.clone();
+            archive_bytes.extend_from_slice(&seeded_checksum(0, &header_encoded).to_le_bytes());
+            archive_bytes.extend_from_slice(&entries_encoded);
+            archive_bytes.resize(header.file_offset as usize, 0);
+            archive_bytes.extend_from_slice(&[7u8; 4096]);
+            archive_bytes
+        };
+
+        let archive = FileArco::from_bytes(Arc::new(build(0))).ok().unwrap();
+        assert_eq!(archive.get("a.bin").unwrap().as_slice(), &[7u8; 1024][..]);
+
+        // Ending exactly at the end of the archive is fine.
+        assert!(FileArco::from_bytes(Arc::new(build(4096 - 1024))).is_ok());
+
+        for &offset in [4096 - 1023, 4096, 1 << 40].iter() {
+            match FileArco::from_bytes(Arc::new(build(offset))) {
+                Err(Error::FileArcoV1(FileArcoV1Error::CorruptedEntriesTable)) => {},
+                _ => panic!("entry at offset {} was accepted", offset),
+            }
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_compressed_bad_length() {
+        // The entry claims a huge decompressed length, which must not be
+        // allocated up front, and the checksums are all consistent.
+        let stored = deflate(b"hello").ok().unwrap();
+        let files = vec![(String::from("a.bin"), stored.len() as u64)];
+        let build = |length: u64, stored: &[u8]| {
+            let mut entries = Entries::from_metadata(&files, 4096);
+            {
+                let entry = entries.files.get_mut("a.bin").unwrap();
+                entry.length = length;
+                entry.compressed = true;
+            }
+
+            let entries_encoded = encode_entries(&entries, 0, 0);
+            let header = Header::new(4096,
+                                     entries_encoded.len() as u64,
+                                     4096,
+                                     seeded_checksum(0, &entries_encoded))
+                .with_flags(FLAG_COMPRESSED | FLAG_NO_CHECKSUMS);
+            let header_encoded = serialize(&header, Infinite).unwrap();
+
+            let mut archive_bytes = header_encoded.clone();
+            archive_bytes.extend_from_slice(&seeded_checksum(0, &header_encoded).to_le_bytes());
+            archive_bytes.extend_from_slice(&entries_encoded);
+            archive_bytes.resize(header.file_offset as usize, 0);
+            archive_bytes.extend_from_slice(stored);
+            archive_bytes.resize(header.file_offset as usize + 4096, 0);
+            archive_bytes
+        };
+
+        let archive = FileArco::from_bytes(Arc::new(build(5, &stored))).ok().unwrap();
+        assert_eq!(archive.get("a.bin").unwrap().as_slice(), b"hello");
+
+        let archive = FileArco::from_bytes(Arc::new(build(1 << 60, &stored))).ok().unwrap();
+        let fileref = archive.get("a.bin").unwrap();
+        assert!(!fileref.is_valid());
+        assert!(fileref.as_slice().is_empty());
+        assert!(fileref.as_slice_checked().is_err());
+        assert!(fileref.read_decompressed().is_err());
+
+        let garbage = vec![0xffu8; stored.len()];
+        let archive = FileArco::from_bytes(Arc::new(build(5, &garbage))).ok().unwrap();
+        let fileref = archive.get("a.bin").unwrap();
+        assert!(!fileref.is_valid());
+        assert!(fileref.as_slice_checked().is_err());
+    }
+
+    #[test]
+    fn test_v1_filearco_page_size() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        assert_eq!(archive.page_size(), 4096);
+    }
+
+    #[test]
+    fn test_v1_filearco_alignment_report() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let report = archive.alignment_report();
+        assert_eq!(report.archive_page_size(), 4096);
+        assert_eq!(report.host_page_size(), get_page_size() as u64);
+
+        let report = archive.alignment_report_for(4096);
+        assert!(report.matches_host());
+        assert!(report.is_aligned());
+
+        // Smaller host pages still divide the archive's pages.
+        let report = archive.alignment_report_for(1024);
+        assert!(!report.matches_host());
+        assert!(report.is_aligned());
+
+        // The archive was made on a host with smaller pages.
+        let report = archive.alignment_report_for(16384);
+        assert_eq!(report.host_page_size(), 16384);
+        assert!(!report.matches_host());
+        assert!(!report.is_aligned());
+
+        // Buffers are not known to be page aligned.
+        let mut archive_bytes = Vec::new();
+        File::open(archive_path).ok().unwrap().read_to_end(&mut archive_bytes).ok().unwrap();
+        let buffered = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+        let report = buffered.alignment_report_for(4096);
+        assert!(report.matches_host());
+        assert!(!report.is_aligned());
+    }
+
+    #[test]
+    fn test_v1_filearco_get() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let base_path = Path::new("testarchives/simple");
+        let simple = get_file_data_stub(base_path).ok().unwrap();
+        let svec = simple.into_vec();
+
+        for entry in svec.iter() {
+            if let Some(fileref) = archive.get(entry.name()) {
+                assert_eq!(fileref.len(), entry.len());
+                assert!(fileref.is_valid());
+            }
+            else {
+                assert!(false);
+            }
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_entry_info() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let archive_bytes = read(archive_path).ok().unwrap();
+
+        for name in get_simple().iter() {
+            let info = archive.entry_info(name).unwrap();
+            let fileref = archive.get(name).unwrap();
+
+            assert_eq!(info.len(), fileref.len());
+            assert_eq!(info.aligned_len(), fileref.aligned_length);
+            assert_eq!(info.checksum(), fileref.checksum);
+            assert_eq!(info.aligned_len() % archive.page_size(), 0);
+
+            let start = (archive.file_offset() + info.offset()) as usize;
+            let end = start + info.len() as usize;
+            assert_eq!(&archive_bytes[start..end], fileref.as_slice());
+        }
+
+        assert!(archive.entry_info("missing.txt").is_none());
+    }
+
+    #[test]
+    fn test_v1_filearco_get_many() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let names = ["LICENSE-MIT", "missing.txt", "Cargo.toml", "src/missing.rs"];
+        let results = archive.get_many(&names);
+
+        assert_eq!(results.len(), names.len());
+        for (&(name, ref fileref), &expected) in results.iter().zip(names.iter()) {
+            assert_eq!(name, expected);
+
+            match archive.get(name) {
+                Some(expected) => {
+                    let fileref = fileref.as_ref().unwrap();
+                    assert_eq!(fileref.as_slice(), expected.as_slice());
+                },
+                None => assert!(fileref.is_none()),
+            }
+        }
+        assert!(results[0].1.is_some());
+        assert!(results[1].1.is_none());
+        assert!(results[2].1.is_some());
+        assert!(results[3].1.is_none());
+
+        assert!(archive.get_many(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_v1_filearco_open_paged() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        create_dir_all("tmptest").ok().unwrap();
+
+        let options = [MakeOptions::new(),
+                       MakeOptions::new().compression(CompressionMode::Deflate),
+                       MakeOptions::new().checksum(ChecksumKind::Sha256).inline_threshold(512)];
+
+        for (i, options) in options.iter().enumerate() {
+            let archive_path = format!("tmptest/test_v1_filearco_open_paged_{}.fac", i);
+            {
+                let file_data = ::file_data::get(base_path).ok().unwrap();
+                let archive_file = File::create(&archive_path).ok().unwrap();
+                FileArco::make_with(file_data, archive_file, options).ok().unwrap();
+            }
+
+            let mapped = FileArco::new(&archive_path).ok().unwrap();
+            let paged = FileArco::open_paged(&archive_path).ok().unwrap();
+
+            assert_eq!(paged.file_count(), mapped.file_count());
+            assert_eq!(paged.version_number(), mapped.version_number());
+            let mut names = mapped.entries().map(|(name, _)| name).collect::<Vec<_>>();
+            names.sort();
+            assert_eq!(paged.names(), names);
+
+            for name in paged.names() {
+                let fileref = mapped.get(name).unwrap();
+                let filebuf = paged.get(name).ok().unwrap().unwrap();
+
+                assert!(filebuf.is_valid());
+                assert_eq!(filebuf.as_slice(), fileref.as_slice());
+                assert_eq!(filebuf.len(), fileref.len());
+                assert_eq!(filebuf.mode(), fileref.mode());
+                assert_eq!(filebuf.modified_time(), fileref.modified_time());
+            }
+
+            assert!(paged.get("missing.txt").ok().unwrap().is_none());
+        }
+
+        // Corrupted contents are read as they are.
+        let archive_path = "tmptest/test_v1_filearco_open_paged_0.fac";
+        let offset = {
+            let mapped = FileArco::new(archive_path).ok().unwrap();
+            let info = mapped.entry_info("main.css").unwrap();
+            mapped.file_offset() + info.offset()
+        };
+        {
+            let mut file = OpenOptions::new().write(true).open(archive_path).ok().unwrap();
+            file.seek(SeekFrom::Start(offset)).ok().unwrap();
+            file.write_all(b"#").ok().unwrap();
+        }
+
+        let paged = FileArco::open_paged(archive_path).ok().unwrap();
+        assert!(!paged.get("main.css").ok().unwrap().unwrap().is_valid());
+        assert!(paged.get("rustdoc.css").ok().unwrap().unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_v1_filearco_open_paged_with_pool() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let archive_path = Path::new("tmptest/test_v1_filearco_open_paged_with_pool.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let file_data = ::file_data::get(base_path).ok().unwrap();
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().compression(CompressionMode::Auto);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        let threads = 4;
+        let rounds = 20;
+        let unpooled = Arc::new(FileArco::open_paged(archive_path).ok().unwrap());
+        let pooled = Arc::new(FileArco::open_paged_with_pool(archive_path, 2 * threads).ok().unwrap());
+        let names = pooled.names().into_iter().map(String::from).collect::<Vec<_>>();
+
+        for archive in [unpooled.clone(), pooled.clone()].iter() {
+            let handles = (0..threads)
+                .map(|_| {
+                    let archive = archive.clone();
+                    let names = names.clone();
+
+                    ::std::thread::spawn(move || {
+                        for _ in 0..rounds {
+                            for name in names.iter() {
+                                let expected = read(base_path.join(name)).ok().unwrap();
+                                let filebuf = archive.get(name).ok().unwrap().unwrap();
+
+                                assert!(filebuf.is_valid());
+                                assert_eq!(filebuf.as_slice(), &expected[..]);
+                            }
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for handle in handles.into_iter() {
+                handle.join().ok().unwrap();
+            }
+        }
+
+        // Each thread holds at most two buffers at once (the stored bytes
+        // and the decompressed contents), so the pool never runs dry.
+        let reads = threads * rounds * names.len();
+        assert!(unpooled.pool.allocations.load(Ordering::Relaxed) >= reads);
+        assert!(pooled.pool.allocations.load(Ordering::Relaxed) <= 2 * threads);
+
+        // Buffers taken out with `into_vec()` are not returned.
+        let buffers = pooled.pool.lock().len();
+        let contents = pooled.get(&names[0]).ok().unwrap().unwrap().into_vec();
+        assert!(!contents.is_empty());
+        assert!(pooled.pool.lock().len() < buffers);
+    }
+
+    #[test]
+    fn test_v1_filearco_try_close() {
+        let path = Path::new("testarchives/simple_v1.fac");
+
+        let archive = FileArco::new(path).ok().unwrap();
+        archive.try_close().ok().unwrap();
+
+        let archive = FileArco::new(path).ok().unwrap();
+        let fileref = archive.get("Cargo.toml").unwrap();
+        let handle = archive.handle();
+        let rerooted = archive.rerooted("assets");
+
+        // A failed close gives the archive back.
+        let archive = match archive.try_close() {
+            Err((archive, count)) => {
+                assert_eq!(count, 3);
+                archive
+            },
+            _ => panic!("archive with outstanding references was closed"),
+        };
+
+        // The mapping outlives the failed close.
+        assert!(fileref.is_valid());
+        assert!(archive.get("Cargo.toml").unwrap().is_valid());
+        assert!(rerooted.get("assets/LICENSE-MIT").unwrap().is_valid());
+
+        drop(handle);
+        drop(rerooted);
+
+        let archive = match archive.try_close() {
+            Err((archive, count)) => {
+                assert_eq!(count, 1);
+                archive
+            },
+            _ => panic!("archive with an outstanding FileRef was closed"),
+        };
+
+        drop(fileref);
+        archive.try_close().ok().unwrap();
+
+        let archive = FileArco::new(path).ok().unwrap();
+        drop(archive.get("Cargo.toml").unwrap());
+        archive.try_close().ok().unwrap();
+    }
+
+    #[test]
+    fn test_v1_filearco_open_best() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+
+        let mapped = FileArco::open_best(archive_path).ok().unwrap();
+        assert_eq!(mapped.backend(), Backend::Mmap);
+
+        let buffered = FileArco::open_best_with(archive_path, |_| {
+            Err(io::Error::from(io::ErrorKind::Other))
+        }).ok().unwrap();
+        assert_eq!(buffered.backend(), Backend::Buffered);
+
+        assert_eq!(buffered.file_count(), mapped.file_count());
+        for (name, _) in mapped.entries() {
+            let fileref = buffered.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), mapped.get(name).unwrap().as_slice());
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_get_separators() {
+        // This simulates an archive made on Windows by an older version.
+        let entries = vec![(String::from("dir\\windows.txt"), b"windows".to_vec()),
+                           (String::from("dir/unix.txt"), b"unix".to_vec())];
+        let mut archive_bytes = Vec::new();
+        FileArco::make_from_entries(entries, &mut archive_bytes).ok().unwrap();
+
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+
+        for name in ["dir/windows.txt", "dir\\windows.txt"].iter() {
+            assert!(archive.contains(name));
+            assert_eq!(archive.get(name).unwrap().as_slice(), b"windows");
+        }
+
+        for name in ["dir/unix.txt", "dir\\unix.txt"].iter() {
+            assert!(archive.contains(name));
+            assert_eq!(archive.get(name).unwrap().as_slice(), b"unix");
+        }
+
+        let mut names = archive.entries().map(|(name, _)| name).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["dir/unix.txt", "dir/windows.txt"]);
+    }
+
+    #[test]
+    fn test_v1_filearco_tree() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let archive_path = Path::new("tmptest/test_v1_filearco_tree.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let file_data = ::file_data::get(base_path).ok().unwrap();
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let tree = archive.tree();
+
+        let reqchan = tree.directory("reqchan").unwrap();
+        let files = reqchan.files().map(|(name, _)| name).collect::<Vec<_>>();
+        for name in ["Responder.t.html", "RequestContract.t.html",
+                     "struct.RequestContract.html", "struct.Requester.html",
+                     "struct.Responder.html"].iter() {
+            assert!(files.contains(name));
+            let length = base_path.join("reqchan").join(name).metadata().ok().unwrap().len();
+            assert_eq!(reqchan.file_len(name), Some(length));
+        }
+        assert!(reqchan.file_len("missing.html").is_none());
+
+        // Nested directories are nested nodes.
+        let ops = tree.directory("implementors").unwrap()
+            .directory("core").unwrap()
+            .directory("ops").unwrap();
+        assert!(ops.file_len("trait.Drop.js").is_some());
+        assert!(tree.file_len("main.css").is_some());
+        assert!(tree.file_len("reqchan").is_none());
+
+        // Every file appears exactly once.
+        fn file_count(node: &DirNode) -> usize {
+            node.files().count() +
+                node.directories().map(|(_, child)| file_count(child)).sum::<usize>()
+        }
+        assert_eq!(file_count(&tree), archive.file_count());
+    }
+
+    #[test]
+    fn test_v1_filearco_iter() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let mut names = Vec::new();
+
+        for (name, fileref) in archive.iter() {
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), archive.get(name).unwrap().as_slice());
+            names.push(name);
+        }
+
+        names.sort();
+        assert_eq!(names, vec!["Cargo.toml", "LICENSE-APACHE", "LICENSE-MIT"]);
+
+        // Only the calls to `get()` above looked files up by name.
+        let lookups = archive.inner.lookups.load(Ordering::Relaxed);
+        assert_eq!(lookups, 3);
+
+        assert_eq!(archive.iter().count(), 3);
+        archive.verify_all();
+        archive.extract_to("tmptest/test_v1_filearco_iter").ok().unwrap();
+        assert_eq!(archive.inner.lookups.load(Ordering::Relaxed), lookups);
+    }
+
+    #[test]
+    fn test_v1_filearco_get_ci() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+
+        for archive in [FileArco::new(archive_path).ok().unwrap(),
+                        FileArco::new_case_insensitive(archive_path).ok().unwrap()].iter() {
+            let fileref = archive.get_ci("cARGO.toml").unwrap();
+            assert_eq!(fileref.len(), archive.get("Cargo.toml").unwrap().len());
+            assert!(archive.get_ci("Cargo.tom").is_none());
+        }
+
+        let entries = vec![(String::from("readme"), b"lower".to_vec()),
+                           (String::from("README"), b"upper".to_vec()),
+                           (String::from("ReadMe"), b"mixed".to_vec())];
+        let archive_path = Path::new("tmptest/test_v1_filearco_get_ci.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make_from_entries(entries, archive_file).ok().unwrap();
+        }
+
+        // Both lookups resolve the collision the same way.
+        let scanned = FileArco::new(archive_path).ok().unwrap();
+        let indexed = FileArco::new_case_insensitive(archive_path).ok().unwrap();
+        let expected = scanned.get_ci("rEaDmE").unwrap().as_slice().to_vec();
+
+        for name in ["readme", "README", "ReadMe", "rEaDmE"].iter() {
+            assert_eq!(scanned.get_ci(name).unwrap().as_slice(), &expected[..]);
+            assert_eq!(indexed.get_ci(name).unwrap().as_slice(), &expected[..]);
+        }
+
+        // Exact lookups are unaffected.
+        assert_eq!(indexed.get("readme").unwrap().as_slice(), b"lower");
+    }
+
+    #[test]
+    fn test_v1_filearco_contains() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        for name in get_simple().iter() {
+            assert!(archive.contains(name));
+        }
+        assert!(archive.contains("Cargo.toml"));
+        assert!(!archive.contains("Cargo.lock"));
+        assert!(!archive.contains(""));
+
+        assert_eq!(archive.file_count(), get_simple().len());
+    }
+
+    #[test]
+    fn test_v1_fileref_as_str_lossy() {
+        let archive_bytes = {
+            let mut writer = ArchiveWriter::new(Vec::<u8>::new(), WriterOptions::new())
+                .ok().unwrap();
+            writer.add_file("binary.bin", &b"abc\xff\xfedef"[..]).ok().unwrap();
+            writer.add_file("text.txt", &b"plain text"[..]).ok().unwrap();
+            writer.finish().ok().unwrap()
+        };
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+
+        let binary = archive.get("binary.bin").unwrap();
+        assert!(binary.as_str().is_err());
+        assert_eq!(binary.as_str_lossy(), "abc\u{fffd}\u{fffd}def");
+
+        let text = archive.get("text.txt").unwrap();
+        match text.as_str_lossy() {
+            Cow::Borrowed(s) => assert_eq!(s, "plain text"),
+            Cow::Owned(_) => panic!("valid UTF-8 should not be copied"),
+        }
+    }
+
+    #[test]
+    fn test_v1_fileref_deserialize() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Settings {
+            name: String,
+            volume: u8,
+            scores: Vec<u64>,
+        }
+
+        let settings = Settings {
+            name: String::from("player"),
+            volume: 7,
+            scores: vec![10, 200, 3000],
+        };
+
+        let entries = vec![
+            (String::from("settings.bin"), serialize(&settings, Infinite).ok().unwrap(), None),
+            (String::from("short.bin"), vec![1, 2, 3], None),
+        ];
+        let mut archive_bytes = Vec::<u8>::new();
+        FileArco::make_from_typed_entries(entries, &mut archive_bytes).ok().unwrap();
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+
+        let fileref = archive.get("settings.bin").unwrap();
+        assert_eq!(fileref.deserialize::<Settings>().ok().unwrap(), settings);
+
+        match archive.get("short.bin").unwrap().deserialize::<Settings>() {
+            Err(Error::Bincode(_)) => {},
+            _ => panic!("truncated struct was deserialized"),
+        }
+    }
+
+    #[test]
+    fn test_v1_fileref_as_str_trimmed() {
+        let files: Vec<(&str, &[u8])> = vec![
+            ("text.txt", b"some text"),
+            ("nuls.txt", b"ends in nuls\0\0"),
+            ("empty.txt", b""),
+        ];
+
+        let archive_path = Path::new("tmptest/test_v1_fileref_as_str_trimmed.fac");
+
+        // Create directory if it does not exist
+        if let Some(parent) = archive_path.parent() {
+            create_dir_all(parent).ok().unwrap();
+        }
+
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let mut writer = ArchiveWriter::new(archive_file, WriterOptions::new())
+                .ok().unwrap();
+
+            for &(name, contents) in files.iter() {
+                writer.add_file(name, contents).ok().unwrap();
+            }
+
+            writer.finish().ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        // The padding following the file contents is never included.
+        let text = archive.get("text.txt").unwrap();
+        assert!(text.aligned_length > text.length);
+        assert_eq!(text.as_str_trimmed().ok().unwrap(), "some text");
+        assert_eq!(text.as_text(true).ok().unwrap(), "some text");
+
+        // NULs that are part of the file contents are kept unless asked.
+        let nuls = archive.get("nuls.txt").unwrap();
+        assert_eq!(nuls.as_str_trimmed().ok().unwrap(), "ends in nuls\0\0");
+        assert_eq!(nuls.as_text(true).ok().unwrap(), "ends in nuls");
+        assert!(!nuls.as_text(true).ok().unwrap().contains('\0'));
+
+        let empty = archive.get("empty.txt").unwrap();
+        assert_eq!(empty.as_str_trimmed().ok().unwrap(), "");
+        assert_eq!(empty.as_text(true).ok().unwrap(), "");
+    }
+
+    #[test]
+    fn test_v1_filearco_extract_to() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let dest = Path::new("tmptest/test_v1_filearco_extract_to");
+        archive.extract_to(dest).ok().unwrap();
+
+        for name in get_simple().iter() {
+            let mut expected = Vec::<u8>::new();
+            File::open(Path::new("testarchives/simple").join(name)).ok().unwrap()
+                .read_to_end(&mut expected).ok().unwrap();
+
+            let mut extracted = Vec::<u8>::new();
+            File::open(dest.join(name)).ok().unwrap()
+                .read_to_end(&mut extracted).ok().unwrap();
+
+            assert_eq!(extracted, expected);
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_extract_matching() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_extract_matching.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let dest = Path::new("tmptest/test_v1_filearco_extract_matching");
+        if dest.exists() {
+            remove_dir_all(dest).ok().unwrap();
+        }
+
+        let count = archive.extract_matching("*.css", dest).ok().unwrap();
+        assert_eq!(count, 3);
+
+        let mut extracted = WalkDir::new(dest).into_iter()
+            .map(|entry| entry.ok().unwrap())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.path().strip_prefix(dest).ok().unwrap()
+                 .to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        extracted.sort();
+
+        assert_eq!(extracted, vec!["main.css", "normalize.css", "rustdoc.css"]);
+
+        match archive.extract_matching("[", dest) {
+            Err(Error::Pattern(_)) => {},
+            _ => panic!("invalid pattern should fail"),
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_extract_file() {
+        let archive = FileArco::new("testarchives/simple_v1.fac").ok().unwrap();
+
+        let dest_dir = Path::new("tmptest/test_v1_filearco_extract_file");
+        if dest_dir.exists() {
+            remove_dir_all(dest_dir).ok().unwrap();
+        }
+
+        let dest = dest_dir.join("nested/LICENSE-MIT");
+        archive.extract_file("LICENSE-MIT", &dest).ok().unwrap();
+
+        let mut original = Vec::new();
+        File::open("testarchives/simple/LICENSE-MIT").ok().unwrap()
+            .read_to_end(&mut original).ok().unwrap();
+        let mut extracted = Vec::new();
+        File::open(&dest).ok().unwrap().read_to_end(&mut extracted).ok().unwrap();
+        assert_eq!(extracted, original);
+
+        match archive.extract_file("missing.txt", dest_dir.join("missing.txt")) {
+            Err(Error::Io(ref err)) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+            _ => panic!("missing file should fail"),
+        }
+        assert!(!dest_dir.join("missing.txt").exists());
+
+        // Corrupted files are not written.
+        let mut data = Vec::new();
+        File::open("testarchives/simple_v1.fac").ok().unwrap()
+            .read_to_end(&mut data).ok().unwrap();
+        let offset = (archive.file_offset() +
+                      archive.entry_info("LICENSE-MIT").unwrap().offset()) as usize;
+        data[offset] = !data[offset];
+
+        let corrupted = FileArco::from_bytes(Arc::new(data)).ok().unwrap();
+        let corrupted_dest = dest_dir.join("corrupted/LICENSE-MIT");
+
+        match corrupted.extract_file("LICENSE-MIT", &corrupted_dest) {
+            Err(Error::FileArcoV1(FileArcoV1Error::CorruptedFile(ref name))) => {
+                assert_eq!(name, "LICENSE-MIT");
+            },
+            _ => panic!("corrupted file should fail"),
+        }
+        assert!(!corrupted_dest.exists());
+    }
+
+    #[test]
+    fn test_v1_filearco_extract_to_corrupted() {
+        let archive_path = Path::new("tmptest/test_v1_filearco_extract_to_corrupted.fac");
+
+        // Create directory if it does not exist
+        if let Some(parent) = archive_path.parent() {
+            create_dir_all(parent).ok().unwrap();
+        }
+
+        let mut archive_bytes = {
+            let mut writer = ArchiveWriter::new(Vec::<u8>::new(), WriterOptions::new())
+                .ok().unwrap();
+            writer.add_file("dir/file.txt", &b"file contents"[..]).ok().unwrap();
+            writer.finish().ok().unwrap()
+        };
+
+        // Flip a bit of the file contents, which start after the prelude.
+        let (header, _) = read_index(&archive_bytes, 0, &mut OpenMetrics::default()).ok().unwrap();
+        archive_bytes[header.file_offset as usize] ^= 1;
+        File::create(archive_path).ok().unwrap().write_all(&archive_bytes).ok().unwrap();
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let dest = Path::new("tmptest/test_v1_filearco_extract_to_corrupted");
+
+        match archive.extract_to(dest) {
+            Err(Error::FileArcoV1(FileArcoV1Error::CorruptedFile(ref name))) => {
+                assert_eq!(name, "dir/file.txt");
+            },
+            _ => panic!("corrupted file was extracted"),
+        }
+        assert!(!dest.join("dir/file.txt").exists());
+    }
+
+    #[test]
+    fn test_v1_filearco_extract_to_with() {
+        let archive_path = Path::new("tmptest/test_v1_filearco_extract_to_with.fac");
+        create_dir_all("tmptest").ok().unwrap();
+
+        let mut archive_bytes = {
+            let mut writer = ArchiveWriter::new(Vec::<u8>::new(), WriterOptions::new())
+                .ok().unwrap();
+            writer.add_file("a.txt", &b"first file"[..]).ok().unwrap();
+            writer.add_file("b.txt", &b"second file"[..]).ok().unwrap();
+            writer.add_file("c.txt", &b"third file"[..]).ok().unwrap();
+            writer.finish().ok().unwrap()
+        };
+
+        // Corrupt the region of "b.txt" only.
+        let (header, entries) = read_index(&archive_bytes, 0, &mut OpenMetrics::default()).ok().unwrap();
+        let offset = header.file_offset + entries.files["b.txt"].offset;
+        archive_bytes[offset as usize] ^= 1;
+        File::create(archive_path).ok().unwrap().write_all(&archive_bytes).ok().unwrap();
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let extract = |policy: &str, options: ExtractOptions| {
+            let dest = Path::new("tmptest/test_v1_filearco_extract_to_with").join(policy);
+            if dest.exists() {
+                remove_dir_all(&dest).ok().unwrap();
+            }
+
+            let result = archive.extract_to_with(&dest, &options);
+            let written = ["a.txt", "b.txt", "c.txt"].iter()
+                .filter(|name| dest.join(name).exists())
+                .cloned()
+                .collect::<Vec<_>>();
+
+            (result, written)
+        };
+
+        let (result, written) = extract("skip", ExtractOptions::new().on_corrupt(OnCorrupt::Skip));
+        assert_eq!(result.ok().unwrap(), 2);
+        assert_eq!(written, vec!["a.txt", "c.txt"]);
+
+        let (result, written) = extract("abort", ExtractOptions::new().on_corrupt(OnCorrupt::Abort));
+        match result {
+            Err(Error::FileArcoV1(FileArcoV1Error::CorruptedFile(ref name))) => {
+                assert_eq!(name, "b.txt");
+            },
+            _ => panic!("corrupted file was extracted"),
+        }
+        assert_eq!(written, vec!["a.txt"]);
+
+        let (result, written) = extract("write_anyway",
+                                        ExtractOptions::new().on_corrupt(OnCorrupt::WriteAnyway));
+        assert_eq!(result.ok().unwrap(), 3);
+        assert_eq!(written, vec!["a.txt", "b.txt", "c.txt"]);
+
+        let (result, written) = extract("no_verify", ExtractOptions::new().verify(false));
+        assert_eq!(result.ok().unwrap(), 3);
+        assert_eq!(written, vec!["a.txt", "b.txt", "c.txt"]);
+    }
+
+    #[test]
+    fn test_v1_file_byte_diff() {
+        let make_archive = |path: &Path, contents: &[u8]| {
+            let archive_file = File::create(path).ok().unwrap();
+            let mut writer = ArchiveWriter::new(archive_file, WriterOptions::new())
+                .ok().unwrap();
+            writer.add_file("changed.txt", contents).ok().unwrap();
+            writer.add_file("same.txt", &b"unchanged"[..]).ok().unwrap();
+            writer.finish().ok().unwrap();
+
+            FileArco::new(path).ok().unwrap()
+        };
+
+        create_dir_all("tmptest").ok().unwrap();
+        let a = make_archive(Path::new("tmptest/test_v1_file_byte_diff_a.fac"),
+                             b"hello world");
+        let b = make_archive(Path::new("tmptest/test_v1_file_byte_diff_b.fac"),
+                             b"hello_world");
+
+        assert_eq!(file_byte_diff(&a, &b, "changed.txt").unwrap(),
+                   vec![(5, b' ', b'_')]);
+        assert_eq!(file_byte_diff(&b, &a, "changed.txt").unwrap(),
+                   vec![(5, b'_', b' ')]);
+        assert!(file_byte_diff(&a, &b, "same.txt").unwrap().is_empty());
+        assert!(file_byte_diff(&a, &b, "missing.txt").is_none());
+
+        // Without checksums, files of the same length are still compared.
+        let make_unchecked = |name: &str, contents: &[u8]| {
+            let dir_path = Path::new("tmptest").join(name);
+            let archive_path = dir_path.with_extension("fac");
+            create_dir_all(&dir_path).ok().unwrap();
+            File::create(dir_path.join("changed.txt")).ok().unwrap()
+                .write_all(contents).ok().unwrap();
+
+            let file_data = ::file_data::get(&dir_path).ok().unwrap();
+            let archive_file = File::create(&archive_path).ok().unwrap();
+            let options = MakeOptions::new().checksum(ChecksumKind::None);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+
+            FileArco::new(archive_path).ok().unwrap()
+        };
+
+        let a = make_unchecked("test_v1_file_byte_diff_unchecked_a", b"hello world");
+        let b = make_unchecked("test_v1_file_byte_diff_unchecked_b", b"hello_world");
+
+        assert_eq!(file_byte_diff(&a, &b, "changed.txt").unwrap(),
+                   vec![(5, b' ', b'_')]);
+        assert!(file_byte_diff(&a, &a, "changed.txt").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_v1_filearco_verify_all() {
+        let archive_path = Path::new("tmptest/test_v1_filearco_verify_all.fac");
+
+        // Create directory if it does not exist
+        if let Some(parent) = archive_path.parent() {
+            create_dir_all(parent).ok().unwrap();
+        }
+
+        let mut archive_bytes = {
+            let mut writer = ArchiveWriter::new(Vec::<u8>::new(), WriterOptions::new())
+                .ok().unwrap();
+            writer.add_file("a.txt", &b"first file"[..]).ok().unwrap();
+            writer.add_file("b.txt", &b"second file"[..]).ok().unwrap();
+            writer.add_file("c.txt", &b"third file"[..]).ok().unwrap();
+            writer.finish().ok().unwrap()
+        };
+
+        // Corrupt the region of "b.txt" only.
+        let (header, entries) = read_index(&archive_bytes, 0, &mut OpenMetrics::default()).ok().unwrap();
+        let offset = header.file_offset + entries.files["b.txt"].offset;
+        archive_bytes[offset as usize] ^= 1;
+        File::create(archive_path).ok().unwrap().write_all(&archive_bytes).ok().unwrap();
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        assert_eq!(archive.verify_all(),
+                   vec![(String::from("a.txt"), true),
+                        (String::from("b.txt"), false),
+                        (String::from("c.txt"), true)]);
+    }
+
+    #[test]
+    fn test_v1_filearco_checksum_manifest() {
+        let mut archive_bytes = {
+            let mut writer = ArchiveWriter::new(Vec::<u8>::new(), WriterOptions::new())
+                .ok().unwrap();
+            writer.add_file("a.txt", &b"first file"[..]).ok().unwrap();
+            writer.add_file("dir/b.txt", &b"second file"[..]).ok().unwrap();
+            writer.add_file("empty.txt", &b""[..]).ok().unwrap();
+            writer.finish().ok().unwrap()
+        };
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes.clone())).ok().unwrap();
+
+        let mut manifest = Vec::<u8>::new();
+        archive.write_checksum_manifest(&mut manifest).ok().unwrap();
+
+        // Lines look like `sha256sum` output, sorted by name.
+        let manifest_text = String::from_utf8(manifest.clone()).ok().unwrap();
+        let lines = manifest_text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0],
+                   format!("{}  a.txt", Sha256::digest(b"first file").iter()
+                           .map(|byte| format!("{:02x}", byte))
+                           .collect::<String>()));
+        assert!(lines[1].ends_with("  dir/b.txt"));
+        assert!(lines[2].starts_with("e3b0c44298fc1c14"));
+
+        let report = archive.verify_against_manifest(&manifest[..]).ok().unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.valid(), &["a.txt", "dir/b.txt", "empty.txt"]);
+
+        // Corrupt "dir/b.txt" and list a file the archive does not have.
+        let (header, entries) = read_index(&archive_bytes, 0, &mut OpenMetrics::default()).ok().unwrap();
+        let offset = header.file_offset + entries.files["dir/b.txt"].offset;
+        archive_bytes[offset as usize] ^= 1;
+        let corrupted = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+
+        manifest.extend_from_slice(b"\n");
+        manifest.extend_from_slice(lines[0].replace("a.txt", "gone.txt").as_bytes());
+
+        let report = corrupted.verify_against_manifest(&manifest[..]).ok().unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.valid(), &["a.txt", "empty.txt"]);
+        assert_eq!(report.corrupted(), &["dir/b.txt"]);
+        assert_eq!(report.missing(), &["gone.txt"]);
+
+        match archive.verify_against_manifest(&b"not a manifest\n"[..]) {
+            Err(Error::FileArcoV1(FileArcoV1Error::InvalidManifest(ref line))) => {
+                assert_eq!(line, "not a manifest");
+            },
+            _ => panic!("malformed manifest was accepted"),
+        }
+
+        // Names with a backslash or newline are escaped.
+        let digest = [0xabu8; DIGEST_SIZE];
+        let line = manifest_line("odd\\name\n.txt", &digest);
+        assert!(line.starts_with("\\abab"));
+        assert!(line.ends_with("  odd\\\\name\\n.txt\n"));
+        assert_eq!(parse_manifest_line(line.trim_end_matches('\n')),
+                   Some((digest, String::from("odd\\name\n.txt"))));
+    }
+
+    #[test]
+    fn test_v1_filearco_make_without_checksums() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let names = file_data.clone().into_vec().iter()
+            .map(|datum| datum.name())
+            .collect::<Vec<_>>();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_without_checksums.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().checksum(ChecksumKind::None);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.inner.checksum_kind, ChecksumKind::None);
+
+        for name in names.iter() {
+            let fileref = archive.get(name).unwrap();
+            assert!(!fileref.has_checksum());
+            assert_eq!(fileref.checksum, 0);
+            assert!(fileref.is_valid());
+            assert!(verify_file_at(archive_path, name).ok().unwrap());
+        }
+
+        assert!(archive.verify_all().is_empty());
+    }
+
+    #[test]
+    fn test_v1_filearco_make_crc32() {
+        let base_path = Path::new("testarchives/simple");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let names = file_data.clone().into_vec().iter()
+            .map(|datum| datum.name())
+            .collect::<Vec<_>>();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_crc32.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().checksum(ChecksumKind::Crc32);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.inner.checksum_kind, ChecksumKind::Crc32);
+
+        for name in names.iter() {
+            let fileref = archive.get(name).unwrap();
+            let contents = read(base_path.join(name)).ok().unwrap();
+
+            assert!(fileref.has_checksum());
+            assert_eq!(fileref.checksum, ::crc::crc32::checksum_ieee(&contents) as u64);
+            assert!(fileref.is_valid());
+            assert!(verify_file_at(archive_path, name).ok().unwrap());
+        }
+
+        // Corruption is still detected.
+        let mut archive_bytes = read(archive_path).ok().unwrap();
+        let offset = archive.file_offset() + archive.entries()
+            .find(|&(name, _)| name == "Cargo.toml").unwrap().1.offset();
+        archive_bytes[offset as usize] ^= 1;
+
+        let corrupted = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+        assert!(!corrupted.get("Cargo.toml").unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_v1_filearco_make_sha256() {
+        let base_path = Path::new("testarchives/simple");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let names = file_data.clone().into_vec().iter()
+            .map(|datum| datum.name())
+            .collect::<Vec<_>>();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_sha256.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().checksum(ChecksumKind::Sha256);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.inner.checksum_kind, ChecksumKind::Sha256);
+
+        for name in names.iter() {
+            let fileref = archive.get(name).unwrap();
+            let contents = read(base_path.join(name)).ok().unwrap();
+
+            assert!(fileref.has_checksum());
+            assert_eq!(&fileref.digest.unwrap()[..], &Sha256::digest(&contents)[..]);
+            assert!(fileref.is_valid());
+            assert!(verify_file_at(archive_path, name).ok().unwrap());
+        }
+
+        // Corruption is still detected.
+        let mut archive_bytes = read(archive_path).ok().unwrap();
+        let offset = archive.file_offset() + archive.entries()
+            .find(|&(name, _)| name == "Cargo.toml").unwrap().1.offset();
+        archive_bytes[offset as usize] ^= 1;
+
+        let corrupted = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+        assert!(!corrupted.get("Cargo.toml").unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_v1_filearco_make_signed() {
+        let base_path = Path::new("testarchives/simple");
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_signed.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let file_data = ::file_data::get(base_path).ok().unwrap();
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make_signed(file_data, archive_file, b"secret").ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert!(archive.verify_signature(b"secret"));
+        assert!(!archive.verify_signature(b"Secret"));
+        assert!(!archive.verify_signature(b""));
+        assert_eq!(check_trailing_bytes(archive_path).ok().unwrap(), 0);
+
+        // The signature block follows the archive.
+        let archive_bytes = read(archive_path).ok().unwrap();
+        let signed_length = archive.file_length() as usize;
+        assert_eq!(archive_bytes.len(), signed_length + SIGNATURE_SIZE);
+
+        let unsigned_bytes = archive_bytes[..signed_length].to_vec();
+        let unsigned = FileArco::from_bytes(Arc::new(unsigned_bytes)).ok().unwrap();
+        assert!(!unsigned.verify_signature(b"secret"));
+
+        // Modifying any file contents invalidates the signature.
+        let mut modified_bytes = archive_bytes.clone();
+        let offset = archive.file_offset() + archive.entries()
+            .find(|&(name, _)| name == "Cargo.toml").unwrap().1.offset();
+        modified_bytes[offset as usize] ^= 1;
+
+        let modified = FileArco::from_bytes(Arc::new(modified_bytes)).ok().unwrap();
+        assert!(!modified.verify_signature(b"secret"));
+    }
+
+    #[test]
+    fn test_v1_filearco_make_with_options() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let names = file_data.clone().into_vec().iter()
+            .map(|datum| datum.name())
+            .collect::<Vec<_>>();
+
+        // Compressed files with SHA-256 digests, aligned to 16 KiB.
+        let options = MakeOptions::new()
+            .alignment(16384)
+            .compression(CompressionMode::Deflate)
+            .checksum(ChecksumKind::Sha256);
+        let mut archive_bytes = Vec::<u8>::new();
+        FileArco::make_with(file_data.clone(), &mut archive_bytes, &options).ok().unwrap();
+
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+        assert_eq!(archive.page_size(), 16384);
+        assert!(archive.inner.compressed);
+        assert_eq!(archive.inner.checksum_kind, ChecksumKind::Sha256);
+
+        for name in names.iter() {
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), &read(base_path.join(name)).ok().unwrap()[..]);
+        }
+
+        // Seeded CRC-32 checksums, no producer, and a signature.
+        let options = MakeOptions::new()
+            .seed(0x5eed)
+            .checksum(ChecksumKind::Crc32)
+            .producer(None::<String>)
+            .signing_key(Some(&b"secret"[..]));
+        let archive_path = Path::new("tmptest/test_v1_filearco_make_with_options.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        assert!(FileArco::new(archive_path).is_err());
+        let archive = FileArco::new_with_seed(archive_path, 0x5eed).ok().unwrap();
+        assert_eq!(archive.inner.checksum_kind, ChecksumKind::Crc32);
+        assert_eq!(archive.producer(), None);
+        assert!(archive.verify_signature(b"secret"));
+        assert!(names.iter().all(|name| archive.get(name).unwrap().is_valid()));
+    }
+
+    #[test]
+    fn test_v1_verify_file_at() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+
+        for name in get_simple().iter() {
+            assert!(verify_file_at(archive_path, name).ok().unwrap());
+        }
+        assert!(verify_file_at(archive_path, "missing.txt").is_err());
+    }
+
+    #[test]
+    fn test_v1_verify_file_at_corrupted() {
+        let archive_path = Path::new("tmptest/test_v1_verify_file_at_corrupted.fac");
+
+        // Create directory if it does not exist
+        if let Some(parent) = archive_path.parent() {
+            create_dir_all(parent).ok().unwrap();
+        }
+
+        let options = WriterOptions::new().layout(Layout::Trailer);
+        let mut archive_bytes = {
+            let mut writer = ArchiveWriter::new(Vec::<u8>::new(), options).ok().unwrap();
+            writer.add_file("a.txt", &b"first file"[..]).ok().unwrap();
+            writer.add_file("b.txt", &b"second file"[..]).ok().unwrap();
+            writer.finish().ok().unwrap()
+        };
+
+        let (_, entries) = read_index(&archive_bytes, 0, &mut OpenMetrics::default()).ok().unwrap();
+        archive_bytes[entries.files["b.txt"].offset as usize] ^= 1;
+        File::create(archive_path).ok().unwrap().write_all(&archive_bytes).ok().unwrap();
+
+        assert!(verify_file_at(archive_path, "a.txt").ok().unwrap());
+        assert!(!verify_file_at(archive_path, "b.txt").ok().unwrap());
+    }
+
+    #[test]
+    fn test_v1_filearco_content_fingerprint() {
+        let base_path = Path::new("testarchives/simple");
+        let mut fingerprints = Vec::new();
+        let mut entries_checksums = Vec::new();
+
+        for &alignment in [4096u64, 65536].iter() {
+            let file_data = ::file_data::get(base_path).ok().unwrap();
+            let mut archive_bytes = Vec::new();
+            FileArco::make_with_alignment(file_data, &mut archive_bytes, alignment).ok().unwrap();
+
+            let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+            fingerprints.push(archive.content_fingerprint());
+            entries_checksums.push(archive.entries_checksum());
+        }
+
+        assert_eq!(fingerprints[0], fingerprints[1]);
+        assert!(entries_checksums[0] != entries_checksums[1]);
+
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.content_fingerprint(), fingerprints[0]);
+
+        let mut removed_bytes = Vec::new();
+        archive.remove_to(&["LICENSE-MIT"], &mut removed_bytes).ok().unwrap();
+        let removed = FileArco::from_bytes(Arc::new(removed_bytes)).ok().unwrap();
+        assert!(removed.content_fingerprint() != fingerprints[0]);
+    }
+
+    #[test]
+    fn test_v1_filearco_remove_to() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let removed_path = Path::new("tmptest/test_v1_filearco_remove_to.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let removed_file = File::create(removed_path).ok().unwrap();
+            archive.remove_to(&["LICENSE-APACHE"], removed_file).ok().unwrap();
+        }
+
+        let removed = FileArco::new(removed_path).ok().unwrap();
+        assert_eq!(removed.file_count(), 2);
+        assert!(removed.get("LICENSE-APACHE").is_none());
+
+        for name in ["Cargo.toml", "LICENSE-MIT"].iter() {
+            let fileref = removed.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), archive.get(name).unwrap().as_slice());
+        }
+
+        // No space is left where the removed file was.
+        let apache_length = archive.entries()
+            .find(|&(name, _)| name == "LICENSE-APACHE").unwrap().1.aligned_len();
+        assert_eq!(removed.file_length() - removed.file_offset(),
+                   archive.file_length() - archive.file_offset() - apache_length);
+
+        match archive.remove_to(&["missing"], io::sink()) {
+            Err(Error::FileArcoV1(FileArcoV1Error::EntryNotFound(ref name))) => {
+                assert_eq!(name, "missing");
+            },
+            _ => panic!("missing name was accepted"),
+        }
+
+        // A name given twice is removed once.
+        let mut removed_bytes = Vec::<u8>::new();
+        archive.remove_to(&["LICENSE-APACHE", "LICENSE-APACHE"], &mut removed_bytes).ok().unwrap();
+        let removed = FileArco::from_bytes(Arc::new(removed_bytes)).ok().unwrap();
+        assert_eq!(removed.file_count(), 2);
+
+        // Names are looked up with either separator, like `get`.
+        let nested_path = Path::new("tmptest/test_v1_filearco_remove_to_nested.fac");
+        {
+            let file_data = ::file_data::get("testarchives/reqchandocs").ok().unwrap();
+            let archive_file = File::create(nested_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let nested = FileArco::new(nested_path).ok().unwrap();
+        let mut removed_bytes = Vec::<u8>::new();
+        nested.remove_to(&["src\\reqchan\\lib.rs.html"], &mut removed_bytes).ok().unwrap();
+
+        let removed = FileArco::from_bytes(Arc::new(removed_bytes)).ok().unwrap();
+        assert_eq!(removed.file_count(), nested.file_count() - 1);
+        assert!(removed.get("src/reqchan/lib.rs.html").is_none());
+    }
+
+    #[test]
+    fn test_v1_filearco_filter_to() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let archive_path = Path::new("tmptest/test_v1_filearco_filter_to.fac");
+        let filtered_path = Path::new("tmptest/test_v1_filearco_filter_to_html.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let file_data = ::file_data::get(base_path).ok().unwrap();
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        {
+            let filtered_file = File::create(filtered_path).ok().unwrap();
+            archive.filter_to(|name, info| {
+                assert_eq!(info.len(), archive.get(name).unwrap().len());
+                name.ends_with(".html")
+            }, filtered_file).ok().unwrap();
+        }
+
+        let filtered = FileArco::new(filtered_path).ok().unwrap();
+        let html = archive.entries()
+            .map(|(name, _)| name)
+            .filter(|name| name.ends_with(".html"))
+            .collect::<BTreeSet<_>>();
+        assert!(!html.is_empty());
+        assert!(html.len() < archive.file_count());
+        assert_eq!(filtered.entries().map(|(name, _)| name).collect::<BTreeSet<_>>(), html);
+
+        for name in html.iter() {
+            let fileref = filtered.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.as_slice(), archive.get(name).unwrap().as_slice());
+        }
+
+        // Offsets are recompacted.
+        let html_length = archive.entries()
+            .filter(|&(name, _)| name.ends_with(".html"))
+            .map(|(_, info)| info.aligned_len())
+            .sum::<u64>();
+        assert!(filtered.file_length() - filtered.file_offset() <= html_length);
+        assert!(filtered.entries().all(|(_, info)| {
+            info.offset() + info.aligned_len() <= filtered.file_length() - filtered.file_offset()
+        }));
+    }
+
+    #[test]
+    fn test_v1_filearco_append() {
+        let archive_path = Path::new("tmptest/test_v1_filearco_append.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        FileArco::new("testarchives/simple_v1.fac").ok().unwrap()
+            .copy_to(archive_path).ok().unwrap();
+
+        let dir_path = Path::new("tmptest/test_v1_filearco_append");
+        create_dir_all(dir_path).ok().unwrap();
+        File::create(dir_path.join("new.txt")).ok().unwrap()
+            .write_all(b"new file").ok().unwrap();
+        File::create(dir_path.join("Cargo.toml")).ok().unwrap()
+            .write_all(b"[package]").ok().unwrap();
+
+        let original_length = archive_path.metadata().ok().unwrap().len();
+
+        // Nothing is written if a name is already in the archive.
+        let file_data = ::file_data::get(dir_path).ok().unwrap();
+        match FileArco::append(archive_path, file_data) {
+            Err(Error::FileArcoV1(FileArcoV1Error::DuplicateEntry(ref name))) => {
+                assert_eq!(name, "Cargo.toml");
+            },
+            _ => panic!("duplicate entry was accepted"),
+        }
+        assert_eq!(archive_path.metadata().ok().unwrap().len(), original_length);
+
+        let file_data = ::file_data::get(dir_path).ok().unwrap();
+        FileArco::append_with(archive_path, file_data, true).ok().unwrap();
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.file_count(), 4);
+        assert_eq!(archive.get("new.txt").unwrap().as_slice(), b"new file");
+        assert_eq!(archive.get("Cargo.toml").unwrap().as_slice(), b"[package]");
+        assert!(archive.verify_all().into_iter().all(|(_, valid)| valid));
+
+        // The existing contents were left in place.
+        let original = FileArco::new("testarchives/simple_v1.fac").ok().unwrap();
+        let license = original.get("LICENSE-MIT").unwrap();
+        assert_eq!(archive.get("LICENSE-MIT").unwrap().as_slice(), license.as_slice());
+
+        let mut archive_bytes = Vec::new();
+        File::open(archive_path).ok().unwrap().read_to_end(&mut archive_bytes).ok().unwrap();
+        let offset = (original.file_offset() + original.entries()
+                      .find(|&(name, _)| name == "LICENSE-MIT").unwrap().1.offset()) as usize;
+        assert_eq!(&archive_bytes[offset..offset + license.len() as usize], license.as_slice());
+
+        // Appending again replaces the previous trailer.
+        let other_path = Path::new("tmptest/test_v1_filearco_append_other");
+        create_dir_all(other_path).ok().unwrap();
+        File::create(other_path.join("other.txt")).ok().unwrap()
+            .write_all(b"other file").ok().unwrap();
+
+        let file_data = ::file_data::get(other_path).ok().unwrap();
+        FileArco::append(archive_path, file_data).ok().unwrap();
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.file_count(), 5);
+        assert_eq!(archive.get("new.txt").unwrap().as_slice(), b"new file");
+        assert_eq!(archive.get("other.txt").unwrap().as_slice(), b"other file");
+        assert!(archive.verify_all().into_iter().all(|(_, valid)| valid));
+        assert_eq!(check_trailing_bytes(archive_path).ok().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_v1_filearco_append_source_changed() {
+        let archive_path = Path::new("tmptest/test_v1_filearco_append_source_changed.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        FileArco::new("testarchives/simple_v1.fac").ok().unwrap()
+            .copy_to(archive_path).ok().unwrap();
+
+        let dir_path = Path::new("tmptest/test_v1_filearco_append_source_changed");
+        create_dir_all(dir_path).ok().unwrap();
+        File::create(dir_path.join("grows.txt")).ok().unwrap()
+            .write_all(b"short").ok().unwrap();
+
+        let file_data = ::file_data::get(dir_path).ok().unwrap();
+        File::create(dir_path.join("grows.txt")).ok().unwrap()
+            .write_all(b"much longer than before").ok().unwrap();
+
+        match FileArco::append(archive_path, file_data) {
+            Err(Error::FileArcoV1(FileArcoV1Error::SourceChanged(ref name))) => {
+                assert_eq!(name, "grows.txt");
+            },
+            _ => panic!("changed source was appended"),
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_append_with_seed() {
+        let seed = 0x0123456789abcdef;
+        let archive_path = Path::new("tmptest/test_v1_filearco_append_with_seed.fac");
+        {
+            let file_data = ::file_data::get("testarchives/simple").ok().unwrap();
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().seed(seed);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
+        }
+
+        let dir_path = Path::new("tmptest/test_v1_filearco_append_with_seed");
+        create_dir_all(dir_path).ok().unwrap();
+        File::create(dir_path.join("new.txt")).ok().unwrap()
+            .write_all(b"new file").ok().unwrap();
+        let file_data = ::file_data::get(dir_path).ok().unwrap();
+
+        // Without the seed, the archive is rejected before it is changed.
+        match FileArco::append(archive_path, file_data.clone()) {
+            Err(Error::FileArcoV1(FileArcoV1Error::CorruptedHeader)) => {},
+            _ => panic!("seeded archive appended to without its seed"),
+        }
+
+        FileArco::append_with_seed(archive_path, file_data, false, seed).ok().unwrap();
+
+        let archive = FileArco::new_with_seed(archive_path, seed).ok().unwrap();
+        assert_eq!(archive.file_count(), get_simple().len() + 1);
+        assert_eq!(archive.get("new.txt").unwrap().as_slice(), b"new file");
+        assert!(archive.verify_all().into_iter().all(|(_, valid)| valid));
+        assert!(FileArco::new(archive_path).is_err());
+    }
+
+    #[test]
+    fn test_v1_stream_entries() {
+        // Entries are streamed from revision 0 archives too.
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let mut streamed = stream_entries(archive_path).ok().unwrap()
+            .map(|entry| entry.ok().unwrap())
+            .collect::<Vec<_>>();
+        streamed.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut expected = archive.entries()
+            .map(|(name, info)| (name.to_string(), info))
+            .collect::<Vec<_>>();
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(streamed, expected);
+
+        // Build a large archive without any contents to write.
+        let files = (0..50000u64)
+            .map(|index| (format!("dir{}/file{}.bin", index % 100, index), index % 4096))
+            .collect::<Vec<_>>();
+        let entries = Entries::from_metadata(&files, 4096);
+
+        let archive_path = Path::new("tmptest/test_v1_stream_entries.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let mut archive_file = File::create(archive_path).ok().unwrap();
+            write_prelude(&mut archive_file, &entries, entries.flags(), 4096, 0).ok().unwrap();
+            let file_length = archive_file.seek(SeekFrom::End(0)).ok().unwrap() +
+                entries.total_aligned_length();
+            archive_file.set_len(file_length).ok().unwrap();
+        }
+
+        let mut count = 0;
+        for entry in stream_entries(archive_path).ok().unwrap() {
+            let (name, info) = entry.ok().unwrap();
+            let entry = &entries.files[&name];
+            assert_eq!(info.offset(), entry.offset);
+            assert_eq!(info.len(), entry.length);
+            count = count + 1;
+        }
+        assert_eq!(count, files.len());
+
+        // A corrupted table is reported after the entries.
+        let mut archive_bytes = read(archive_path).ok().unwrap();
+        let header_length = header_size() + CHECKSUM_SIZE;
+        let name_start = header_length + 8 + 8;
+        archive_bytes[name_start] ^= 0x01;
+        let corrupted_path = Path::new("tmptest/test_v1_stream_entries_corrupted.fac");
+        File::create(corrupted_path).ok().unwrap().write_all(&archive_bytes).ok().unwrap();
+
+        let results = stream_entries(corrupted_path).ok().unwrap().collect::<Vec<_>>();
+        assert_eq!(results.len(), files.len() + 1);
+        match results.last() {
+            Some(&Err(Error::FileArcoV1(FileArcoV1Error::CorruptedEntriesTable))) => {},
+            _ => panic!("corrupted entries table was not reported"),
+        }
+    }
+
+    #[test]
+    fn test_v1_diff() {
+        let archive = FileArco::new(Path::new("testarchives/simple_v1.fac")).ok().unwrap();
+        assert!(diff(&archive, &archive).is_empty());
+
+        let base_path = Path::new("testarchives/simple");
+        let mut files = ["Cargo.toml", "LICENSE-MIT"].iter()
+            .map(|name| (name.to_string(), read(base_path.join(name)).ok().unwrap()))
+            .collect::<Vec<_>>();
+        files[0].1.extend_from_slice(b"\n[dependencies]\n");
+        files.push((String::from("README.md"), b"# simple".to_vec()));
+
+        let mut modified_bytes = Vec::<u8>::new();
+        FileArco::make_from_entries(files, &mut modified_bytes).ok().unwrap();
+        let modified = FileArco::from_bytes(Arc::new(modified_bytes)).ok().unwrap();
+
+        let changes = diff(&archive, &modified);
+        assert!(!changes.is_empty());
+        assert_eq!(changes.added(), &[String::from("README.md")]);
+        assert_eq!(changes.removed(), &[String::from("LICENSE-APACHE")]);
+        assert_eq!(changes.changed(), &[String::from("Cargo.toml")]);
+
+        let changes = diff(&modified, &archive);
+        assert_eq!(changes.added(), &[String::from("LICENSE-APACHE")]);
+        assert_eq!(changes.removed(), &[String::from("README.md")]);
+        assert_eq!(changes.changed(), &[String::from("Cargo.toml")]);
+
+        // Archives with different checksums are compared by contents.
+        let crc32_bytes = {
+            let file_data = ::file_data::get(base_path).ok().unwrap();
+            let mut crc32_bytes = Vec::<u8>::new();
+            let options = MakeOptions::new().checksum(ChecksumKind::Crc32);
+            FileArco::make_with(file_data, &mut crc32_bytes, &options).ok().unwrap();
+            crc32_bytes
+        };
+        let crc32 = FileArco::from_bytes(Arc::new(crc32_bytes)).ok().unwrap();
+        assert!(diff(&archive, &crc32).is_empty());
+        assert_eq!(diff(&crc32, &modified).changed(), &[String::from("Cargo.toml")]);
+    }
+
+    #[test]
+    fn test_v1_read_raw_header() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let header = read_raw_header(archive_path).ok().unwrap();
+        assert!(header.is_checksum_valid());
+        assert_eq!(header.header_offset(), 0);
+        assert_eq!(&header.id(), FILEARCO_ID);
+        assert_eq!(header.version_number(), archive.version_number());
+        assert_eq!(header.file_length(), archive.file_length());
+        assert_eq!(header.file_offset(), archive.file_offset());
+        assert_eq!(header.page_size(), archive.page_size());
+        assert_eq!(header.entries_length(), archive.entries_length());
+        assert_eq!(header.entries_checksum(), archive.entries_checksum());
+
+        // A corrupted header is still returned.
+        let mut archive_bytes = read(archive_path).ok().unwrap();
+        archive_bytes[8] ^= 0x80;
+        let corrupted_path = Path::new("tmptest/test_v1_read_raw_header.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        File::create(corrupted_path).ok().unwrap().write_all(&archive_bytes).ok().unwrap();
+
+        assert!(FileArco::new(corrupted_path).is_err());
+        let corrupted = read_raw_header(corrupted_path).ok().unwrap();
+        assert!(!corrupted.is_checksum_valid());
+        assert_eq!(corrupted.version_number(), header.version_number() ^ 0x80);
+        assert_eq!(corrupted.header_checksum(), header.header_checksum());
+        assert_eq!(corrupted.file_length(), header.file_length());
+
+        // Trailer layout archives are read from the header the footer
+        // points to.
+        let mut writer = ArchiveWriter::new(Vec::<u8>::new(),
+                                            WriterOptions::new().layout(Layout::Trailer))
+            .ok().unwrap();
+        writer.add_file("a.txt", &b"first file"[..]).ok().unwrap();
+        let trailer_bytes = writer.finish().ok().unwrap();
+        let trailer_path = Path::new("tmptest/test_v1_read_raw_header_trailer.fac");
+        File::create(trailer_path).ok().unwrap().write_all(&trailer_bytes).ok().unwrap();
+
+        let trailer = read_raw_header(trailer_path).ok().unwrap();
+        assert!(trailer.is_checksum_valid());
+        assert!(trailer.header_offset() > 0);
+        assert_eq!(trailer.file_offset(), 0);
+    }
+
+    #[test]
+    fn test_v1_check_trailing_bytes() {
+        let archive_path = Path::new("tmptest/test_v1_check_trailing_bytes.fac");
+        create_dir_all("tmptest").ok().unwrap();
+
+        let entries = vec![(String::from("a.txt"), b"first file".to_vec())];
+        let mut archive_bytes = Vec::new();
+        FileArco::make_from_entries(entries, &mut archive_bytes).ok().unwrap();
+        File::create(archive_path).ok().unwrap().write_all(&archive_bytes).ok().unwrap();
+
+        assert_eq!(check_trailing_bytes(archive_path).ok().unwrap(), 0);
+
+        archive_bytes.extend_from_slice(b"appended");
+        File::create(archive_path).ok().unwrap().write_all(&archive_bytes).ok().unwrap();
+
+        assert_eq!(check_trailing_bytes(archive_path).ok().unwrap(), 8);
+        assert_eq!(check_trailing_bytes("testarchives/simple_v1.fac").ok().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_v1_filearco_with_overlay() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let license_mit = archive.get("LICENSE-MIT").unwrap().as_slice().to_vec();
+
+        let mut overlay = HashMap::new();
+        overlay.insert(String::from("Cargo.toml"), b"overlay".to_vec());
+        overlay.insert(String::from("LICENSE-APACHE"), OVERLAY_MASK.to_vec());
+        overlay.insert(String::from("new.txt"), b"new file".to_vec());
+
+        let overlaid = archive.with_overlay(overlay);
+
+        assert_eq!(overlaid.get("Cargo.toml").unwrap().as_slice(), b"overlay");
+        assert_eq!(overlaid.get("new.txt").unwrap().as_slice(), b"new file");
+        assert!(overlaid.get("LICENSE-APACHE").is_none());
+
+        match overlaid.get("LICENSE-MIT").unwrap() {
+            OverlayRef::Archive(fileref) => {
+                assert_eq!(fileref.as_slice(), &license_mit[..]);
+            },
+            OverlayRef::Overlay(_) => panic!("file should come from the archive"),
+        }
+
+        assert_eq!(overlaid.names(), vec!["Cargo.toml", "LICENSE-MIT", "new.txt"]);
+        assert!(overlaid.archive().get("LICENSE-APACHE").is_some());
+    }
+
+    #[test]
+    fn test_v1_filearco_rerooted() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let cargo_toml = archive.get("Cargo.toml").unwrap();
+
+        let rerooted = archive.rerooted("assets/");
+        assert_eq!(rerooted.prefix(), "assets/");
+
+        let fileref = rerooted.get("assets/Cargo.toml").unwrap();
+        assert_eq!(fileref.as_slice(), cargo_toml.as_slice());
+        assert!(rerooted.get("assets\\Cargo.toml").is_some());
+        assert!(rerooted.get("Cargo.toml").is_none());
+        assert!(rerooted.get("assetsCargo.toml").is_none());
+
+        let names = rerooted.names();
+        assert_eq!(names.len(), archive.file_count());
+        assert!(names.iter().all(|name| name.starts_with("assets/")));
+        assert!(names.contains(&String::from("assets/Cargo.toml")));
+
+        // An empty prefix leaves the names unchanged.
+        let unchanged = archive.rerooted("");
+        assert!(unchanged.get("Cargo.toml").is_some());
+    }
+
+    #[test]
+    fn test_v1_fileref_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<FileArco>();
+        assert_send_sync::<FileRef>();
+
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let expected = archive.get("LICENSE-MIT").unwrap().as_slice().to_vec();
+
+        let fileref = archive.get("LICENSE-MIT").unwrap();
+        drop(archive);
+
+        let handle = ::std::thread::spawn(move || {
+            assert!(fileref.is_valid());
+            fileref.as_slice().to_vec()
+        });
+
+        assert_eq!(handle.join().ok().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_v1_filearco_handle() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ArchiveHandle>();
+
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let handle = archive.handle();
+        drop(archive);
+
+        let threads = get_simple().into_iter()
+            .map(|name| {
+                let handle = handle.clone();
+
+                ::std::thread::spawn(move || {
+                    let fileref = handle.get(&name).unwrap();
+                    assert!(fileref.is_valid());
+                    (name, fileref.as_slice().to_vec())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads.into_iter() {
+            let (name, contents) = thread.join().ok().unwrap();
+
+            let mut expected = Vec::<u8>::new();
+            File::open(Path::new("testarchives/simple").join(&name)).ok().unwrap()
+                .read_to_end(&mut expected).ok().unwrap();
+
+            assert_eq!(contents, expected);
+        }
+
+        assert!(handle.contains("Cargo.toml"));
+        assert!(handle.get("missing.txt").is_none());
+    }
+
+    #[test]
+    fn test_v1_fileref_reader() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        for name in get_simple().iter() {
+            let fileref = archive.get(name).unwrap();
+
+            let mut copy = Vec::<u8>::new();
+            let copied = io::copy(&mut fileref.reader(), &mut copy).ok().unwrap();
+
+            assert_eq!(copied, fileref.len());
+            assert_eq!(&copy[..], fileref.as_slice());
+        }
+
+        // Reads past the end of the file return 0 instead of padding.
+        let fileref = archive.get("Cargo.toml").unwrap();
+        let mut reader = fileref.reader();
+        let mut buffer = vec![0u8; fileref.len() as usize + 10];
+        assert_eq!(reader.read(&mut buffer).ok().unwrap(), fileref.len() as usize);
+        assert_eq!(reader.read(&mut buffer).ok().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_v1_fileref_as_slice() {
+        let dir_path = Path::new("testarchives/simple");
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let simple = get_file_data_stub(dir_path).ok().unwrap();
+        let base_path = simple.path();
+        let svec = simple.into_vec();
+
+        for entry in svec.into_iter() {
+            let full_name = format!(
+                "{}/{}",
+                &base_path.to_string_lossy(),
+                &entry.name()
+            );
+            let full_path = Path::new(&full_name);
+
+            // Read in input file contents.
+            let mut in_file = File::open(full_path).ok().unwrap();
+            let mut contents = Vec::<u8>::with_capacity(entry.len() as usize); 
+            in_file.read_to_end(&mut contents).ok().unwrap();
+            
+            let archived_file = archive.get(&entry.name()).unwrap();
+            let length2 = archived_file.len();
+
+            assert_eq!(entry.len(), archived_file.as_slice().len() as u64);
+            assert_eq!(length2, archived_file.as_slice().len() as u64);
+            assert_eq!(contents, archived_file.as_slice());
+        }
+    }
+    
+    #[test]
+    fn test_v1_filearco_entries() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let base_path = Path::new("testarchives/simple");
+        let simple = get_file_data_stub(base_path).ok().unwrap().into_vec();
+
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), simple.len());
+
+        for datum in simple.iter() {
+            let &(_, info) = entries.iter()
+                .find(|&&(name, _)| name == datum.name())
+                .unwrap();
+
+            assert_eq!(info.len(), datum.len());
+            assert_eq!(info.checksum(), datum.checksum());
+            assert_eq!(info.aligned_len(), get_aligned_length(datum.len(), archive.page_size()));
+            assert_eq!(info.offset() % archive.page_size(), 0);
+        }
+    }
+
+    #[test]
+    fn test_v1_filearco_raw_iter() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        let mut count = 0;
+
+        for (name, bytes, compressed) in archive.raw_iter() {
+            assert!(!compressed);
+            assert_eq!(bytes, archive.get(name).unwrap().as_slice());
+            count = count + 1;
+        }
+
+        assert_eq!(count, get_simple().len());
+    }
+
+    #[test]
+    fn test_v1_filearco_is_fully_resident() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        // Touch every page of every file.
+        let mut sum = 0u64;
+        for (_, bytes, _) in archive.raw_iter() {
+            for chunk in bytes.chunks(512) {
+                sum = sum.wrapping_add(chunk[0] as u64);
             }
         }
+        assert!(sum > 0);
+
+        if cfg!(unix) {
+            let resident = archive.resident_len().unwrap();
+            assert!(resident > 0);
+            assert!(resident <= archive.inner.storage.len() + get_page_size());
+
+            // Pages may be evicted at any time, but pages touched just now
+            // should still be resident.
+            assert_eq!(archive.is_fully_resident(), Some(true));
+        }
+        else {
+            assert_eq!(archive.is_fully_resident(), None);
+        }
     }
 
-    fn cause(&self) -> Option<&error::Error> { None }
-}
+    #[test]
+    fn test_v1_filearco_prefetch_many() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
 
-struct Inner {
-    file_offset: u64,
-    page_size: u64,
-    entries: Entries,
-    map: Mmap,
-}
+        // All files are adjacent, so they should be coalesced into one range.
+        let mut ranges = Vec::new();
+        archive.prefetch_many_with(&get_simple(), |offset, length| {
+            ranges.push((offset, length));
+            Ok(())
+        }).ok().unwrap();
 
-#[repr(C)]
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
-struct Header {
-    id: [u8; 8],
-    version_number: u64,
-    file_length: u64,
-    file_offset: u64,
-    page_size: u64,
-    entries_length: u64,
-    entries_checksum: u64,
-}
+        let total = archive.inner.entries.total_aligned_length() as usize;
+        assert_eq!(ranges, vec![(archive.inner.file_offset as usize, total)]);
 
-impl Header {
-    fn new(page_size: u64,
-           entries_length: u64,
-           file_contents_length: u64,
-           entries_checksum: u64) -> Self {
-        // Serialize test struct to determine `file_offset`.
-        let test_header = Header {
-            id: *FILEARCO_ID,
-            version_number: VERSION_NUMBER,
-            file_length: 0,
-            file_offset: 0,
-            page_size: page_size,
-            entries_length: entries_length,
-            entries_checksum: entries_checksum,
-        };
-        let test_header_encoded = serialize(&test_header, Infinite).unwrap();
-        let header_length = test_header_encoded.len() as u64;
+        // Absent names are ignored.
+        let mut calls = 0;
+        archive.prefetch_many_with(&["missing"], |_, _| {
+            calls = calls + 1;
+            Ok(())
+        }).ok().unwrap();
+        assert_eq!(calls, 0);
 
-        let file_offset = get_aligned_length(header_length + entries_length);
-        let file_length = file_offset + file_contents_length;
+        archive.prefetch_many(&get_simple()).ok().unwrap();
+    }
 
-        Header {
-            id: *FILEARCO_ID,
-            version_number: VERSION_NUMBER,
-            file_length: file_length,
-            file_offset: file_offset,
-            page_size: page_size,
-            entries_length: entries_length,
-            entries_checksum: entries_checksum,
+    #[test]
+    fn test_v1_filearco_pages_for_names() {
+        // Small files share pages when aligned to less than a page.
+        let base_path = Path::new("testarchives/simple");
+        let archive_path = Path::new("tmptest/test_v1_filearco_pages_for_names.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let file_data = ::file_data::get(base_path).ok().unwrap();
+            let archive_file = File::create(archive_path).ok().unwrap();
+            let options = MakeOptions::new().alignment(512);
+            FileArco::make_with(file_data, archive_file, &options).ok().unwrap();
         }
-    }
-}
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
-struct Entries {
-    files: HashMap<String, Entry>,
-}
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let names = get_simple();
 
-impl Entries {
-    fn new(file_data: FileData) -> Self {
-        let mut files = HashMap::new();
-        
-        for datum in file_data.into_vec() {
-            let aligned_length = get_aligned_length(datum.len());
+        let pages = archive.pages_for_names(&names);
+        let separate = names.iter()
+            .map(|name| archive.pages_for_names(&[name]))
+            .collect::<Vec<_>>();
 
-            files.insert(datum.name(),
-                         Entry {
-                             offset: 0,
-                             length: datum.len(),
-                             aligned_length: aligned_length,
-                             checksum: datum.checksum(),
-                         }
-            );
+        let mut union = BTreeSet::new();
+        for file_pages in separate.iter() {
+            assert!(!file_pages.is_empty());
+            union.extend(file_pages.iter().cloned());
         }
+        assert_eq!(pages, union);
+        assert!(pages.len() < separate.iter().map(|file_pages| file_pages.len()).sum());
 
-        let mut offset = 0;
-        let keys = files.keys().cloned().collect::<Vec<_>>();
+        // The pages match the ranges `prefetch_many` advises.
+        let page_size = get_page_size();
+        let mut advised = BTreeSet::new();
+        archive.prefetch_many_with(&names, |offset, length| {
+            advised.extend(offset / page_size..(offset + length + page_size - 1) / page_size);
+            Ok(())
+        }).ok().unwrap();
+        assert_eq!(pages, advised);
 
-        for key in keys {
-            let val = files.get_mut(&key).unwrap();
-            val.offset = offset;
-            offset = offset + val.aligned_length;
-        }
+        assert!(archive.pages_for_names(&["missing"]).is_empty());
 
-        Entries {
-            files: files 
+        // Names are looked up with either separator, like `get`.
+        let nested_path = Path::new("tmptest/test_v1_filearco_pages_for_names_nested.fac");
+        {
+            let file_data = ::file_data::get("testarchives/reqchandocs").ok().unwrap();
+            let archive_file = File::create(nested_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
         }
+
+        let nested = FileArco::new(nested_path).ok().unwrap();
+        let pages = nested.pages_for_names(&["src/reqchan/lib.rs.html"]);
+        assert!(!pages.is_empty());
+        assert_eq!(nested.pages_for_names(&["src\\reqchan\\lib.rs.html"]), pages);
     }
 
-    fn total_aligned_length(&self) -> u64 {
-        let mut total_length = 0_u64;
-        
-        let keys = self.files.keys().cloned().collect::<Vec<_>>();
+    #[test]
+    fn test_v1_filearco_prefetch_all() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let mapped = FileArco::new(archive_path).ok().unwrap();
+        let buffered = FileArco::from_bytes(Arc::new(read(archive_path).ok().unwrap()))
+            .ok().unwrap();
 
-        for key in keys {
-            let val = self.files.get(&key).unwrap();
-            total_length = total_length + val.aligned_length;
-        }
+        for archive in [mapped, buffered].iter() {
+            archive.prefetch_all().ok().unwrap();
+            archive.release().ok().unwrap();
 
-        total_length
+            // Released archives are still readable.
+            for name in get_simple().iter() {
+                assert!(archive.get(name).unwrap().is_valid());
+            }
+        }
     }
-}
-
-#[repr(C)]
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
-struct Entry {
-    offset: u64,
-    length: u64,
-    aligned_length: u64,
-    checksum: u64,
-}
 
-/// This function returns the smallest multiple of 2^12 (i.e. 4096)
-/// greater than or equal to the given length.
-///
-/// # Arguments
-///
-/// * length - the input number
-#[inline]
-fn get_aligned_length(length: u64) -> u64 {
-    let page_size = get_page_size() as u64;
+    // This parses one CSV record per line, handling quoted fields.
+    fn parse_csv(text: &str) -> Vec<Vec<String>> {
+        text.split("\r\n")
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let mut fields = Vec::new();
+                let mut field = String::new();
+                let mut quoted = false;
+                let mut chars = line.chars().peekable();
 
-    // Assume memory page size is a power of 2.
-    (length + (page_size-1)) & !(page_size-1)
-}
+                while let Some(c) = chars.next() {
+                    match (c, quoted) {
+                        ('"', true) if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        },
+                        ('"', _) => quoted = !quoted,
+                        (',', false) => fields.push(mem::replace(&mut field, String::new())),
+                        (c, _) => field.push(c),
+                    }
+                }
 
-#[cfg(test)]
-mod tests {
-    use std::fs::create_dir_all;
+                fields.push(field);
+                fields
+            })
+            .collect()
+    }
 
-    use memadvise::{advise, Advice};
-    
-    use super::super::file_data::FileDatum;
-    use super::*;
+    #[test]
+    fn test_v1_filearco_write_csv() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
 
-    fn get_file_data_stub<P: AsRef<Path>>(base_path: P) -> Result<FileData> {
-        let mut data = Vec::<FileDatum>::new();
-        data.push(FileDatum::new(
-            String::from("Cargo.toml"),
-            328,
-            10574576474013701409,
-        ));
-        data.push(FileDatum::new(
-            String::from("LICENSE-APACHE"),
-            10771,
-            8740797956101379381,
-        ));
-        data.push(FileDatum::new(
-            String::from("LICENSE-MIT"),
-            1082,
-            13423357612537305206,
-        ));
-        
-        Ok(FileData::new(
-            base_path.as_ref().to_path_buf(),
-            data,
-        ))
-    }
+        let mut csv = Vec::<u8>::new();
+        archive.write_csv(&mut csv).ok().unwrap();
 
-    fn get_simple() -> Vec<String> {
-        let mut v = Vec::<String>::new();
+        let rows = parse_csv(str::from_utf8(&csv).ok().unwrap());
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], vec!["name", "length", "aligned_length", "offset", "checksum"]);
 
-        v.push(String::from("Cargo.toml"));
-        v.push(String::from("LICENSE-APACHE"));
-        v.push(String::from("LICENSE-MIT"));
+        for row in rows[1..].iter() {
+            let entry = archive.inner.entries.files.get(&row[0]).unwrap();
+            assert_eq!(row[1].parse::<u64>().ok().unwrap(), entry.length);
+            assert_eq!(row[2].parse::<u64>().ok().unwrap(), entry.aligned_length);
+            assert_eq!(row[3].parse::<u64>().ok().unwrap(), entry.offset);
+            assert_eq!(row[4].parse::<u64>().ok().unwrap(), entry.checksum);
+        }
+    }
 
-        v
+    #[test]
+    fn test_v1_escape_csv() {
+        assert_eq!(escape_csv("plain.txt"), "plain.txt");
+        assert_eq!(escape_csv("a,b.txt"), "\"a,b.txt\"");
+        assert_eq!(escape_csv("say \"hi\".txt"), "\"say \"\"hi\"\".txt\"");
+        assert_eq!(parse_csv(&escape_csv("say \"hi\", ok"))[0][0], "say \"hi\", ok");
     }
 
     #[test]
-    fn test_v1_get_rounded_length() {
-        assert_eq!(get_aligned_length(0), 0);
-        assert_eq!(get_aligned_length(4096), 4096);
-        assert_eq!(get_aligned_length(4096+1), 2 * 4096);
-        assert_eq!(get_aligned_length(2*4096 - 1), 2 * 4096);
+    fn test_v1_fileref_mode_legacy() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        assert_eq!(archive.get("Cargo.toml").unwrap().mode(), None);
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_v1_entries_new() {
-        let file_data = get_file_data_stub(&Path::new("testarchives/simple")).ok().unwrap();
-        let entries = Entries::new(file_data);
+    fn test_v1_fileref_mode() {
+        use std::fs::{set_permissions, Permissions};
+        use std::os::unix::fs::PermissionsExt;
 
-        let simple = get_simple();
+        let dir_path = Path::new("tmptest/test_v1_fileref_mode");
+        create_dir_all(dir_path).ok().unwrap();
 
-        for name in simple.iter() {
-            assert!(entries.files.contains_key(name));
+        let script_path = dir_path.join("script.sh");
+        let text_path = dir_path.join("text.txt");
+        File::create(&script_path).ok().unwrap()
+            .write_all(b"#!/bin/sh\necho hello\n").ok().unwrap();
+        File::create(&text_path).ok().unwrap()
+            .write_all(b"hello\n").ok().unwrap();
+        set_permissions(&script_path, Permissions::from_mode(0o755)).ok().unwrap();
+        set_permissions(&text_path, Permissions::from_mode(0o600)).ok().unwrap();
+
+        let file_data = ::file_data::get(dir_path).ok().unwrap();
+        let archive_path = Path::new("tmptest/test_v1_fileref_mode.fac");
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
         }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+
+        assert_eq!(archive.get("script.sh").unwrap().mode().unwrap(), 0o755);
+        assert_eq!(archive.get("text.txt").unwrap().mode().unwrap(), 0o600);
+
+        // Special bits are stored but not applied when extracting.
+        set_permissions(&script_path, Permissions::from_mode(0o4755)).ok().unwrap();
+        let mut archive_bytes = Vec::<u8>::new();
+        FileArco::make(::file_data::get(dir_path).ok().unwrap(), &mut archive_bytes).ok().unwrap();
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+        assert_eq!(archive.get("script.sh").unwrap().mode().unwrap(), 0o4755);
+
+        let extracted_path = Path::new("tmptest/test_v1_fileref_mode_script.sh");
+        archive.extract_file("script.sh", extracted_path).ok().unwrap();
+        let extracted_mode = extracted_path.metadata().ok().unwrap().permissions().mode();
+        assert_eq!(extracted_mode & 0o7777, 0o755);
     }
 
     #[test]
-    fn test_v1_filearco_make() {
-        let base_path = Path::new("testarchives/simple");
-        let file_data = get_file_data_stub(base_path).ok().unwrap();
+    fn test_v1_fileref_modified_time() {
+        let archive_path = Path::new("testarchives/simple_v1.fac");
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert!(archive.get("Cargo.toml").unwrap().modified_time().is_none());
 
-        let archive_path = Path::new("tmptest/test_v1_filearco_make.fac");
+        let base_path = Path::new("testarchives/simple");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let archive_path = Path::new("tmptest/test_v1_fileref_modified_time.fac");
 
         // Create directory if it does not exist
         if let Some(parent) = archive_path.parent() {
             create_dir_all(parent).ok().unwrap();
         }
 
-        let archive_file = File::create(archive_path).ok().unwrap();
-        FileArco::make(file_data, archive_file).ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let expected = base_path.join("Cargo.toml").metadata().ok().unwrap()
+            .modified().ok().unwrap()
+            .duration_since(UNIX_EPOCH).ok().unwrap()
+            .as_secs();
+        let modified = archive.get("Cargo.toml").unwrap().modified_time().unwrap();
+
+        assert_eq!(modified.duration_since(UNIX_EPOCH).ok().unwrap().as_secs(), expected);
     }
 
     #[test]
-    fn test_v1_filearco_new() {
+    fn test_v1_fileref_content_type() {
         let archive_path = Path::new("testarchives/simple_v1.fac");
-        let simple = get_simple();
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.get("Cargo.toml").unwrap().content_type(), None);
 
-        match FileArco::new(archive_path) {
-            Ok(archive) => {
-                for name in simple.iter() {
-                    assert!(archive.inner.entries.files.contains_key(name));
-                }
-            },
-            Err(err) => {
-                println!("test_v1_filearco_new {}", err.to_string());
-                assert!(false); },
-        }
+        let entries = vec![(String::from("index"),
+                            b"<p>Hello</p>".to_vec(),
+                            Some(String::from("text/html"))),
+                           (String::from("page.html"), b"<p>Hello</p>".to_vec(), None)];
+        let mut archive_bytes = Vec::new();
+        FileArco::make_from_typed_entries(entries, &mut archive_bytes).ok().unwrap();
+
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+        assert_eq!(archive.get("index").unwrap().content_type(), Some("text/html"));
+        assert_eq!(archive.get("page.html").unwrap().content_type(), None);
+
+        let entries = vec![(String::from("page.HTML"), b"<p>Hello</p>".to_vec()),
+                           (String::from("style.css"), b"p {}".to_vec()),
+                           (String::from("README"), b"Hello".to_vec())];
+        let mut archive_bytes = Vec::new();
+        FileArco::make_from_entries(entries, &mut archive_bytes).ok().unwrap();
+
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+        assert_eq!(archive.get("page.HTML").unwrap().content_type(), Some("text/html"));
+        assert_eq!(archive.get("style.css").unwrap().content_type(), Some("text/css"));
+        assert_eq!(archive.get("README").unwrap().content_type(), None);
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_v1_filearco_page_size() {
-        let archive_path = Path::new("testarchives/simple_v1.fac");
+    fn test_v1_filearco_get_bytes_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir_path = Path::new("tmptest/test_v1_filearco_get_bytes_non_utf8");
+        create_dir_all(dir_path).ok().unwrap();
+
+        let raw_name: &[u8] = b"caf\xe9.txt";
+        File::create(dir_path.join(OsStr::from_bytes(raw_name))).ok().unwrap()
+            .write_all(b"coffee").ok().unwrap();
+        File::create(dir_path.join("plain.txt")).ok().unwrap()
+            .write_all(b"plain").ok().unwrap();
+
+        let file_data = ::file_data::get(dir_path).ok().unwrap();
+        assert!(file_data.clone().into_vec().iter()
+                .any(|datum| datum.name_bytes() == raw_name));
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_get_bytes_non_utf8.fac");
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
         let archive = FileArco::new(archive_path).ok().unwrap();
 
-        assert_eq!(archive.page_size(), 4096);
+        let fileref = archive.get_bytes(raw_name).unwrap();
+        assert!(fileref.is_valid());
+        assert_eq!(fileref.as_slice(), b"coffee");
+        assert_eq!(archive.get_bytes(b"plain.txt").unwrap().as_slice(), b"plain");
+
+        // The lossy name is not an exact match.
+        assert!(archive.get_bytes("caf\u{FFFD}.txt".as_bytes()).is_none());
+        assert!(archive.get_bytes(b"caf\xe8.txt").is_none());
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_v1_filearco_get() {
-        let archive_path = Path::new("testarchives/simple_v1.fac");
-        let archive = FileArco::new(archive_path).ok().unwrap();
+    fn test_v1_filearco_non_utf8_collisions() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::{OsStrExt, OsStringExt};
 
-        let base_path = Path::new("testarchives/simple");
-        let simple = get_file_data_stub(base_path).ok().unwrap();
-        let svec = simple.into_vec();
+        // All three names have the same lossy form.
+        let raw_names: [&[u8]; 3] = [b"caf\xe9.txt", b"caf\xe8.txt", "caf\u{FFFD}.txt".as_bytes()];
 
-        for entry in svec.iter() {
-            if let Some(fileref) = archive.get(entry.name()) {
-                assert_eq!(fileref.len(), entry.len());
-                assert!(fileref.is_valid());
-            }
-            else {
-                assert!(false);
-            }
+        let dir_path = Path::new("tmptest/test_v1_filearco_non_utf8_collisions");
+        create_dir_all(dir_path).ok().unwrap();
+        for (i, raw_name) in raw_names.iter().enumerate() {
+            File::create(dir_path.join(OsStr::from_bytes(raw_name))).ok().unwrap()
+                .write_all(format!("file {}", i).as_bytes()).ok().unwrap();
+        }
+
+        let mut archive_bytes = Vec::<u8>::new();
+        let file_data = ::file_data::get(dir_path).ok().unwrap();
+        FileArco::make(file_data, &mut archive_bytes).ok().unwrap();
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
+
+        // The valid name keeps its own key.
+        assert_eq!(archive.get("caf\u{FFFD}.txt").unwrap().as_slice(), b"file 2");
+
+        for (i, raw_name) in raw_names.iter().enumerate() {
+            let fileref = archive.get_bytes(raw_name).unwrap();
+            assert_eq!(fileref.as_slice(), format!("file {}", i).as_bytes());
+        }
+
+        let mut expected = raw_names.iter()
+            .map(|raw_name| OsString::from_vec(raw_name.to_vec()))
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(archive.names_os(), expected);
+
+        // Appending matches existing names by their exact bytes.
+        let append_dir = Path::new("tmptest/test_v1_filearco_non_utf8_collisions_append");
+        create_dir_all(append_dir).ok().unwrap();
+        File::create(append_dir.join(OsStr::from_bytes(b"caf\xe7.txt"))).ok().unwrap()
+            .write_all(b"file 3").ok().unwrap();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_non_utf8_collisions.fac");
+        {
+            let file_data = ::file_data::get(dir_path).ok().unwrap();
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+        FileArco::append(archive_path, ::file_data::get(append_dir).ok().unwrap()).ok().unwrap();
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.file_count(), 4);
+        assert_eq!(archive.get_bytes(b"caf\xe7.txt").unwrap().as_slice(), b"file 3");
+        assert_eq!(archive.get_bytes(b"caf\xe9.txt").unwrap().as_slice(), b"file 0");
+
+        match FileArco::append(archive_path, ::file_data::get(append_dir).ok().unwrap()) {
+            Err(Error::FileArcoV1(FileArcoV1Error::DuplicateEntry(_))) => {},
+            _ => panic!("appended a duplicate name"),
         }
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_v1_fileref_as_slice() {
-        let dir_path = Path::new("testarchives/simple");
-        let archive_path = Path::new("testarchives/simple_v1.fac");
-        let archive = FileArco::new(archive_path).ok().unwrap();
+    fn test_v1_filearco_names_by_offset() {
+        let archive_bytes = {
+            let mut writer = ArchiveWriter::new(Vec::<u8>::new(), WriterOptions::new())
+                .ok().unwrap();
+            writer.add_file("zeta.txt", &b"first file"[..]).ok().unwrap();
+            writer.add_file("alpha.txt", &b"second file"[..]).ok().unwrap();
+            writer.add_file("mid.txt", &b"third file"[..]).ok().unwrap();
+            writer.finish().ok().unwrap()
+        };
+        let archive = FileArco::from_bytes(Arc::new(archive_bytes)).ok().unwrap();
 
-        let simple = get_file_data_stub(dir_path).ok().unwrap();
-        let base_path = simple.path();
-        let svec = simple.into_vec();
+        assert_eq!(archive.names_by_offset(), vec!["zeta.txt", "alpha.txt", "mid.txt"]);
 
-        for entry in svec.into_iter() {
-            let full_name = format!(
-                "{}/{}",
-                &base_path.to_string_lossy(),
-                &entry.name()
-            );
-            let full_path = Path::new(&full_name);
+        let archive = FileArco::new(Path::new("testarchives/simple_v1.fac")).ok().unwrap();
+        let names = archive.names_by_offset();
+        assert_eq!(names.len(), archive.file_count());
 
-            // Read in input file contents.
-            let mut in_file = File::open(full_path).ok().unwrap();
-            let mut contents = Vec::<u8>::with_capacity(entry.len() as usize); 
-            in_file.read_to_end(&mut contents).ok().unwrap();
-            
-            let archived_file = archive.get(&entry.name()).unwrap();
-            let length2 = archived_file.len();
+        let offsets = names.iter()
+            .map(|name| archive.entry_info(name).unwrap().offset())
+            .collect::<Vec<_>>();
+        assert!(offsets.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
 
-            assert_eq!(entry.len(), archived_file.as_slice().len() as u64);
-            assert_eq!(length2, archived_file.as_slice().len() as u64);
-            assert_eq!(contents, archived_file.as_slice());
+    #[test]
+    fn test_v1_filearco_names_os() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let dir_path = Path::new("tmptest/test_v1_filearco_names_os");
+        create_dir_all(dir_path).ok().unwrap();
+
+        let raw_name: &[u8] = b"na\xefve.txt";
+        File::create(dir_path.join(OsStr::from_bytes(raw_name))).ok().unwrap()
+            .write_all(b"raw").ok().unwrap();
+        File::create(dir_path.join("plain.txt")).ok().unwrap()
+            .write_all(b"plain").ok().unwrap();
+
+        let archive_path = Path::new("tmptest/test_v1_filearco_names_os.fac");
+        {
+            let file_data = ::file_data::get(dir_path).ok().unwrap();
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let names = archive.names_os();
+
+        assert_eq!(names, vec![OsString::from_vec(raw_name.to_vec()),
+                               OsString::from("plain.txt")]);
+
+        // The names address the original files exactly.
+        for name in names.iter() {
+            let mut contents = Vec::<u8>::new();
+            File::open(dir_path.join(name)).ok().unwrap()
+                .read_to_end(&mut contents).ok().unwrap();
+
+            let fileref = archive.get_bytes(name.as_bytes()).unwrap();
+            assert_eq!(fileref.as_slice(), &contents[..]);
         }
     }
-    
+
     #[test]
     fn test_v1_fileref_as_raw() {
         let dir_path = Path::new("testarchives/simple");
@@ -0,0 +1,720 @@
+//! This module creates and manages a FileArco v2 archive file.
+//!
+//! Version 2 has the same overall structure as version 1 (a header, an
+//! entries table, and page aligned file contents), but every integer is
+//! written as an explicit little endian field instead of using bincode's
+//! layout. Archives made on any machine read identically everywhere.
+//!
+//! # Example
+//!
+//! This example creates a FileArco v2 archive file containing 3 text files.
+//! It then opens the newly created file and outputs the text of all 3
+//! stored files.
+//!
+//! ```rust
+//! extern crate filearco;
+//!
+//! use std::fs::File;
+//! use std::path::{Path, PathBuf};
+//!
+//! let base_path = Path::new("testarchives/simple");
+//! let file_data = filearco::get_file_data(base_path).ok().unwrap();
+//!
+//! let archive_path = Path::new("tmptest/doctest_simple_v2.fac");
+//! let archive_file = File::create(archive_path).ok().unwrap();
+//! filearco::v2::FileArco::make(file_data, archive_file).ok().unwrap();
+//!
+//! let archive = filearco::v2::FileArco::new(archive_path).ok().unwrap();
+//! let cargo_toml = archive.get("Cargo.toml").unwrap();
+//! println!("{}", cargo_toml.as_str().ok().unwrap());
+//! let license_mit = archive.get("LICENSE-MIT").unwrap();
+//! println!("{}", license_mit.as_str().ok().unwrap());
+//! let license_apache = archive.get("LICENSE-APACHE").unwrap();
+//! println!("{}", license_apache.as_str().ok().unwrap());
+//! ```
+
+use std::collections::HashMap;
+use std::convert::AsRef;
+use std::error;
+#[cfg(unix)]
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::str;
+use std::sync::Arc;
+
+use crc::crc64::{checksum_iso as checksum, update as update_checksum, ISO_TABLE};
+use memmap::{Mmap, Protection};
+use page_size::get as get_page_size;
+
+use super::{Error, FILEARCO_ID, Result};
+use file_data::{FileData, FileDatum};
+
+const VERSION_NUMBER: u64 = 2;
+
+// Files are copied into the archive this many bytes at a time.
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+// The header is 7 u64 fields after the 8 byte identifier, and the last one
+// is the checksum of everything before it.
+const HEADER_SIZE: usize = 64;
+const HEADER_CHECKSUM_OFFSET: usize = HEADER_SIZE - 8;
+
+/// This struct represents a FileArco v2 archive file.
+pub struct FileArco {
+    inner: Arc<Inner>,
+}
+
+impl FileArco {
+    /// This method opens and validates a FileArco v2 archive file.
+    ///
+    /// # Arguments
+    ///
+    /// * path - file path of the archive
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::fs::File;
+    /// use std::path::Path;
+    ///
+    /// let base_path = Path::new("testarchives/simple");
+    /// let file_data = filearco::get_file_data(base_path).ok().unwrap();
+    ///
+    /// let archive_path = Path::new("tmptest/doc_v2_new.fac");
+    /// let archive_file = File::create(archive_path).ok().unwrap();
+    /// filearco::v2::FileArco::make(file_data, archive_file).ok().unwrap();
+    ///
+    /// let archive = filearco::v2::FileArco::new(archive_path).ok().unwrap();
+    /// ```
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let map = Mmap::open_path(path, Protection::Read)?;
+        let bytes = unsafe { map.as_slice() };
+
+        let header = Header::decode(bytes)?;
+        let entries = read_entries(bytes, &header)?;
+
+        Ok(FileArco {
+            inner: Arc::new(Inner {
+                file_offset: header.file_offset,
+                page_size: header.page_size,
+                entries: entries,
+                map: map,
+            })
+        })
+    }
+
+    /// This method retrieves a file from the archive, if it exists.
+    ///
+    /// # Arguments
+    ///
+    /// * file_path - name of file to retrieve
+    pub fn get<P: AsRef<str>>(&self, file_path: P) -> Option<FileRef> {
+        self.inner.entries.get(file_path.as_ref()).map(|entry| {
+            FileRef {
+                offset: self.inner.file_offset + entry.offset,
+                length: entry.length,
+                checksum: entry.checksum,
+                inner: self.inner.clone(),
+            }
+        })
+    }
+
+    /// This method returns the page size of the system that created the
+    /// archive.
+    pub fn page_size(&self) -> u64 {
+        self.inner.page_size
+    }
+
+    /// This method returns the number of files in the archive.
+    pub fn file_count(&self) -> usize {
+        self.inner.entries.len()
+    }
+
+    /// This method creates a FileArco v2 archive file containing the files
+    /// described by `file_data`.
+    ///
+    /// # Arguments
+    ///
+    /// * file_data - file paths and other metadata of the input files
+    ///
+    /// * out_file - destination of archive file
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate filearco;
+    ///
+    /// use std::io;
+    /// use std::path::Path;
+    ///
+    /// let base_path = Path::new("testarchives/simple");
+    /// let file_data = filearco::get_file_data(base_path).ok().unwrap();
+    ///
+    /// filearco::v2::FileArco::make(file_data, io::stdout()).ok().unwrap();
+    /// ```
+    pub fn make<H: Write>(file_data: FileData, mut out_file: H) -> Result<()> {
+        let base_path = file_data.path();
+        let page_size = get_page_size() as u64;
+
+        // Files are stored in name order so the archive does not depend on
+        // the order they were found in.
+        let mut data = file_data.into_vec();
+        data.sort_by_key(|datum| datum.name());
+
+        let mut entries = Vec::<(String, Entry)>::with_capacity(data.len());
+        let mut offset = 0;
+
+        for datum in data.iter() {
+            let aligned_length = align_to(datum.len(), page_size);
+
+            entries.push((datum.name(), Entry {
+                offset: offset,
+                length: datum.len(),
+                aligned_length: aligned_length,
+                checksum: datum.checksum(),
+            }));
+            offset = offset + aligned_length;
+        }
+
+        let entries_encoded = encode_entries(&entries);
+        let file_offset = align_to((HEADER_SIZE + entries_encoded.len()) as u64, page_size);
+
+        let header = Header {
+            file_length: file_offset + offset,
+            file_offset: file_offset,
+            page_size: page_size,
+            entries_length: entries_encoded.len() as u64,
+            entries_checksum: checksum(&entries_encoded),
+        };
+
+        out_file.write_all(&header.encode())?;
+        out_file.write_all(&entries_encoded)?;
+        let padding_length = file_offset as usize - HEADER_SIZE - entries_encoded.len();
+        out_file.write_all(&vec![0u8; padding_length])?;
+
+        for (datum, &(ref name, ref entry)) in data.iter().zip(entries.iter()) {
+            // A file that changed since `file_data` was gathered would shift
+            // every file after it, so its length and checksum are checked
+            // as it is copied.
+            let (length, file_checksum) = copy_contents(&base_path.join(datum_path(datum)),
+                                                        entry.length,
+                                                        &mut out_file)?;
+            if length != entry.length || file_checksum != entry.checksum {
+                return Err(Error::FileArcoV2(FileArcoV2Error::SourceChanged(name.clone())));
+            }
+
+            let padding_length = (entry.aligned_length - entry.length) as usize;
+            out_file.write_all(&vec![0u8; padding_length])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// This struct represents a reference to a file in the archive.
+pub struct FileRef {
+    offset: u64,
+    length: u64,
+    checksum: u64,
+    // Holding a reference to the memory mapped file ensures it will not be
+    // unmapped until we finish using it.
+    inner: Arc<Inner>,
+}
+
+impl FileRef {
+    /// This method ensures the file contents have not been corrupted.
+    pub fn is_valid(&self) -> bool {
+        checksum(self.as_slice()) == self.checksum
+    }
+
+    /// This method retrieves a byte array representing the contents of a
+    /// `FileRef`.
+    pub fn as_slice(&self) -> &[u8] {
+        let bytes = unsafe { self.inner.map.as_slice() };
+        let start = self.offset as usize;
+
+        &bytes[start..start + self.length as usize]
+    }
+
+    /// This method retrieves a string representing the contents of a
+    /// `FileRef`.
+    pub fn as_str(&self) -> Result<&str> {
+        str::from_utf8(self.as_slice()).map_err(Error::from)
+    }
+
+    /// This method returns the length of the file.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+}
+
+/// This is the error type for FileArco v2 archives.
+#[derive(Debug)]
+pub enum FileArcoV2Error {
+    /// Entry table's computed checksum did not match the one stored in the file.
+    CorruptedEntriesTable,
+    /// Header's computed checksum did not match the one stored in the file.
+    CorruptedHeader,
+    /// File is too small for the header of a FileArco v2 archive.
+    FileTooSmall,
+    /// File is a valid FileArco v2 archive but it has been truncated.
+    FileTruncated,
+    /// File does not have a valid identifier.
+    NotArchive,
+    /// File has a valid identifier but an incorrect version number.
+    NotV2Archive,
+    /// Source file changed after its length and checksum were recorded.
+    SourceChanged(String),
+}
+
+impl fmt::Display for FileArcoV2Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FileArcoV2Error::SourceChanged(ref name) => {
+                write!(fmt, "Source file changed while archiving: {}", name)
+            },
+            _ => write!(fmt, "{}", error::Error::description(self)),
+        }
+    }
+}
+
+impl error::Error for FileArcoV2Error {
+    fn description(&self) -> &str {
+        static CORRUPTED_ENTRIES_TABLE: &'static str = "Corrupted entries table";
+        static CORRUPTED_HEADER: &'static str = "Corrupted header";
+        static FILE_TOO_SMALL: &'static str = "File too small for FileArco v2 archive";
+        static FILE_TRUNCATED: &'static str = "File truncated";
+        static NOT_ARCHIVE: &'static str = "Not FileArco archive";
+        static NOT_V2_ARCHIVE: &'static str = "Not FileArco v2 archive";
+        static SOURCE_CHANGED: &'static str = "Source file changed while archiving";
+
+        match *self {
+            FileArcoV2Error::CorruptedEntriesTable => {
+                CORRUPTED_ENTRIES_TABLE
+            },
+            FileArcoV2Error::CorruptedHeader => {
+                CORRUPTED_HEADER
+            },
+            FileArcoV2Error::FileTooSmall => {
+                FILE_TOO_SMALL
+            },
+            FileArcoV2Error::FileTruncated => {
+                FILE_TRUNCATED
+            },
+            FileArcoV2Error::NotArchive => {
+                NOT_ARCHIVE
+            },
+            FileArcoV2Error::NotV2Archive => {
+                NOT_V2_ARCHIVE
+            },
+            FileArcoV2Error::SourceChanged(_) => {
+                SOURCE_CHANGED
+            },
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> { None }
+}
+
+struct Inner {
+    file_offset: u64,
+    page_size: u64,
+    entries: HashMap<String, Entry>,
+    map: Mmap,
+}
+
+// All fields are stored as little endian u64s in this order, after the
+// identifier and version number and before the header checksum.
+#[derive(Debug, PartialEq)]
+struct Header {
+    file_length: u64,
+    file_offset: u64,
+    page_size: u64,
+    entries_length: u64,
+    entries_checksum: u64,
+}
+
+impl Header {
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::<u8>::with_capacity(HEADER_SIZE);
+        encoded.extend_from_slice(FILEARCO_ID);
+
+        for field in [VERSION_NUMBER, self.file_length, self.file_offset,
+                      self.page_size, self.entries_length,
+                      self.entries_checksum].iter() {
+            encoded.extend_from_slice(&field.to_le_bytes());
+        }
+
+        let header_checksum = checksum(&encoded);
+        encoded.extend_from_slice(&header_checksum.to_le_bytes());
+
+        encoded
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(Error::FileArcoV2(FileArcoV2Error::FileTooSmall));
+        }
+
+        if bytes[..8] != FILEARCO_ID[..] {
+            return Err(Error::FileArcoV2(FileArcoV2Error::NotArchive));
+        }
+
+        if read_u64(bytes, 8) != Some(VERSION_NUMBER) {
+            return Err(Error::FileArcoV2(FileArcoV2Error::NotV2Archive));
+        }
+
+        if read_u64(bytes, HEADER_CHECKSUM_OFFSET) != Some(checksum(&bytes[..HEADER_CHECKSUM_OFFSET])) {
+            return Err(Error::FileArcoV2(FileArcoV2Error::CorruptedHeader));
+        }
+
+        let header = Header {
+            file_length: read_u64(bytes, 16).unwrap(),
+            file_offset: read_u64(bytes, 24).unwrap(),
+            page_size: read_u64(bytes, 32).unwrap(),
+            entries_length: read_u64(bytes, 40).unwrap(),
+            entries_checksum: read_u64(bytes, 48).unwrap(),
+        };
+
+        if header.file_offset > header.file_length {
+            return Err(Error::FileArcoV2(FileArcoV2Error::CorruptedHeader));
+        }
+
+        if (bytes.len() as u64) < header.file_length {
+            return Err(Error::FileArcoV2(FileArcoV2Error::FileTruncated));
+        }
+
+        Ok(header)
+    }
+}
+
+// Each entry is stored as the length of its name, the UTF-8 bytes of its
+// name, and then these fields, all as little endian u64s.
+#[derive(Debug, PartialEq)]
+struct Entry {
+    offset: u64,
+    length: u64,
+    aligned_length: u64,
+    checksum: u64,
+}
+
+// The entries table is the number of entries followed by each entry.
+fn encode_entries(entries: &[(String, Entry)]) -> Vec<u8> {
+    let mut encoded = Vec::<u8>::new();
+    encoded.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+
+    for &(ref name, ref entry) in entries.iter() {
+        encoded.extend_from_slice(&(name.len() as u64).to_le_bytes());
+        encoded.extend_from_slice(name.as_bytes());
+
+        for field in [entry.offset, entry.length, entry.aligned_length,
+                      entry.checksum].iter() {
+            encoded.extend_from_slice(&field.to_le_bytes());
+        }
+    }
+
+    encoded
+}
+
+// This function validates the entries table and ensures every file lies
+// within the file contents section.
+fn read_entries(bytes: &[u8], header: &Header) -> Result<HashMap<String, Entry>> {
+    let start = HEADER_SIZE as u64;
+    let end = start.checked_add(header.entries_length)
+        .filter(|&end| end <= header.file_offset && end <= bytes.len() as u64)
+        .ok_or(Error::FileArcoV2(FileArcoV2Error::FileTruncated))?;
+    let sl = &bytes[start as usize..end as usize];
+
+    if checksum(sl) != header.entries_checksum {
+        return Err(Error::FileArcoV2(FileArcoV2Error::CorruptedEntriesTable));
+    }
+
+    decode_entries(sl, header.file_length - header.file_offset)
+        .ok_or(Error::FileArcoV2(FileArcoV2Error::CorruptedEntriesTable))
+}
+
+// This function returns `None` if the entries table is malformed or refers
+// to contents past `contents_length`.
+fn decode_entries(sl: &[u8], contents_length: u64) -> Option<HashMap<String, Entry>> {
+    let count = read_u64(sl, 0)?;
+    let mut position = 8;
+    let mut entries = HashMap::new();
+
+    for _ in 0..count {
+        let name_length = read_u64(sl, position)? as usize;
+        position = position + 8;
+
+        let name_end = position.checked_add(name_length).filter(|&end| end <= sl.len())?;
+        let name = str::from_utf8(&sl[position..name_end]).ok()?.to_string();
+        position = name_end;
+
+        let entry = Entry {
+            offset: read_u64(sl, position)?,
+            length: read_u64(sl, position + 8)?,
+            aligned_length: read_u64(sl, position + 16)?,
+            checksum: read_u64(sl, position + 24)?,
+        };
+        position = position + 32;
+
+        if entry.offset.checked_add(entry.length).is_none_or(|end| end > contents_length) {
+            return None;
+        }
+
+        entries.insert(name, entry);
+    }
+
+    Some(entries)
+}
+
+// This function copies the file at `path` to `out` a chunk at a time and
+// returns the number of bytes copied and their checksum. It stops one byte
+// past `length`, which is enough to tell the file grew.
+fn copy_contents<W: Write>(path: &Path, length: u64, out: &mut W) -> Result<(u64, u64)> {
+    let mut in_file = File::open(path)?.take(length + 1);
+    let mut chunk = Vec::<u8>::with_capacity(COPY_BUFFER_SIZE);
+    let mut copied = 0;
+    let mut file_checksum = 0;
+
+    loop {
+        chunk.clear();
+        (&mut in_file).take(COPY_BUFFER_SIZE as u64).read_to_end(&mut chunk)?;
+
+        if chunk.is_empty() {
+            return Ok((copied, file_checksum));
+        }
+
+        file_checksum = update_checksum(file_checksum, &ISO_TABLE, &chunk);
+        out.write_all(&chunk)?;
+        copied = copied + chunk.len() as u64;
+    }
+}
+
+// This function returns the relative path of the file described by `datum`,
+// including any bytes that are not valid UTF-8.
+#[cfg(unix)]
+fn datum_path(datum: &FileDatum) -> PathBuf {
+    PathBuf::from(OsStr::from_bytes(datum.name_bytes()))
+}
+
+// Names are always valid UTF-8 on other platforms.
+#[cfg(not(unix))]
+fn datum_path(datum: &FileDatum) -> PathBuf {
+    PathBuf::from(datum.name())
+}
+
+// This function reads the little endian u64 at `position`, if `bytes` is
+// long enough.
+fn read_u64(bytes: &[u8], position: usize) -> Option<u64> {
+    let end = position.checked_add(8)?;
+    let field = bytes.get(position..end)?;
+    let mut le_bytes = [0u8; 8];
+    le_bytes.copy_from_slice(field);
+
+    Some(u64::from_le_bytes(le_bytes))
+}
+
+// This function returns the smallest multiple of `alignment` that is greater
+// than or equal to `length`.
+fn align_to(length: u64, alignment: u64) -> u64 {
+    length.div_ceil(alignment) * alignment
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, remove_dir_all};
+
+    use super::*;
+
+    // This function lays out a v2 archive byte by byte, independently of
+    // `Header::encode` and `encode_entries`.
+    fn handcrafted_archive() -> Vec<u8> {
+        let contents = b"hello";
+        let page_size = 16u64;
+
+        let mut entries = Vec::<u8>::new();
+        entries.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0]);            // count
+        entries.extend_from_slice(&[5, 0, 0, 0, 0, 0, 0, 0]);            // name length
+        entries.extend_from_slice(b"a.txt");                             // name
+        entries.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]);            // offset
+        entries.extend_from_slice(&[5, 0, 0, 0, 0, 0, 0, 0]);            // length
+        entries.extend_from_slice(&[16, 0, 0, 0, 0, 0, 0, 0]);           // aligned length
+        entries.extend_from_slice(&checksum(contents).to_le_bytes());    // checksum
+        assert_eq!(entries.len(), 53);
+
+        // 64 + 53 rounded up to a multiple of 16
+        let file_offset = 128u64;
+
+        let mut archive = Vec::<u8>::new();
+        archive.extend_from_slice(b"FILEARCO");
+        archive.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 0]);            // version
+        archive.extend_from_slice(&[144, 0, 0, 0, 0, 0, 0, 0]);          // file length
+        archive.extend_from_slice(&[128, 0, 0, 0, 0, 0, 0, 0]);          // file offset
+        archive.extend_from_slice(&[16, 0, 0, 0, 0, 0, 0, 0]);           // page size
+        archive.extend_from_slice(&[53, 0, 0, 0, 0, 0, 0, 0]);           // entries length
+        archive.extend_from_slice(&checksum(&entries).to_le_bytes());
+        let header_checksum = checksum(&archive);
+        archive.extend_from_slice(&header_checksum.to_le_bytes());
+        archive.extend_from_slice(&entries);
+        archive.resize(file_offset as usize, 0);
+        archive.extend_from_slice(contents);
+        archive.resize((file_offset + page_size) as usize, 0);
+
+        archive
+    }
+
+    #[test]
+    fn test_v2_decode_handcrafted() {
+        let archive_bytes = handcrafted_archive();
+
+        let header = Header::decode(&archive_bytes).ok().unwrap();
+        assert_eq!(header, Header {
+            file_length: 144,
+            file_offset: 128,
+            page_size: 16,
+            entries_length: 53,
+            entries_checksum: header.entries_checksum,
+        });
+        assert_eq!(header.encode(), &archive_bytes[..HEADER_SIZE]);
+
+        let entries = read_entries(&archive_bytes, &header).ok().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries["a.txt"], Entry {
+            offset: 0,
+            length: 5,
+            aligned_length: 16,
+            checksum: checksum(b"hello"),
+        });
+
+        let mut encoded = entries.into_iter().collect::<Vec<_>>();
+        encoded.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(encode_entries(&encoded), &archive_bytes[HEADER_SIZE..HEADER_SIZE + 53]);
+
+        let path = Path::new("tmptest/test_v2_decode_handcrafted.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        File::create(path).ok().unwrap().write_all(&archive_bytes).ok().unwrap();
+
+        let archive = FileArco::new(path).ok().unwrap();
+        assert_eq!(archive.page_size(), 16);
+        let fileref = archive.get("a.txt").unwrap();
+        assert!(fileref.is_valid());
+        assert_eq!(fileref.as_str().ok().unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_v2_decode_corrupted() {
+        let mut archive_bytes = handcrafted_archive();
+
+        archive_bytes[8] = 1;
+        match Header::decode(&archive_bytes) {
+            Err(Error::FileArcoV2(FileArcoV2Error::NotV2Archive)) => {},
+            _ => panic!("version 1 should be rejected"),
+        }
+
+        archive_bytes[8] = 2;
+        archive_bytes[HEADER_SIZE + 10] ^= 1;
+        let header = Header::decode(&archive_bytes).ok().unwrap();
+        match read_entries(&archive_bytes, &header) {
+            Err(Error::FileArcoV2(FileArcoV2Error::CorruptedEntriesTable)) => {},
+            _ => panic!("corrupted entries table should be rejected"),
+        }
+
+        archive_bytes.truncate(140);
+        match Header::decode(&archive_bytes) {
+            Err(Error::FileArcoV2(FileArcoV2Error::FileTruncated)) => {},
+            _ => panic!("truncated archive should be rejected"),
+        }
+    }
+
+    #[test]
+    fn test_v2_make() {
+        let base_path = Path::new("testarchives/reqchandocs");
+        let file_data = ::file_data::get(base_path).ok().unwrap();
+        let names = file_data.clone().into_vec().iter()
+            .map(|datum| datum.name())
+            .collect::<Vec<_>>();
+
+        let archive_path = Path::new("tmptest/test_v2_make.fac");
+        create_dir_all("tmptest").ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        assert_eq!(archive.file_count(), names.len());
+        assert_eq!(archive.page_size(), get_page_size() as u64);
+
+        for name in names.iter() {
+            let mut expected = Vec::<u8>::new();
+            File::open(base_path.join(name)).ok().unwrap()
+                .read_to_end(&mut expected).ok().unwrap();
+
+            let fileref = archive.get(name).unwrap();
+            assert!(fileref.is_valid());
+            assert_eq!(fileref.len(), expected.len() as u64);
+            assert_eq!(fileref.as_slice(), &expected[..]);
+        }
+    }
+
+    #[test]
+    fn test_v2_make_source_changed() {
+        let archive_path = Path::new("tmptest/test_v2_make_source_changed.fac");
+        let dir_path = Path::new("tmptest/test_v2_make_source_changed");
+        create_dir_all(dir_path).ok().unwrap();
+
+        // Same length, different contents; different length; no longer
+        // empty.
+        let changes: [(&[u8], &[u8]); 3] = [(b"original", b"modified"),
+                                            (b"original", b"original contents"),
+                                            (b"", b"grown")];
+
+        for &(before, after) in changes.iter() {
+            File::create(dir_path.join("changed.txt")).ok().unwrap()
+                .write_all(before).ok().unwrap();
+            File::create(dir_path.join("stable.txt")).ok().unwrap()
+                .write_all(b"stable").ok().unwrap();
+
+            let file_data = ::file_data::get(dir_path).ok().unwrap();
+
+            File::create(dir_path.join("changed.txt")).ok().unwrap()
+                .write_all(after).ok().unwrap();
+
+            let archive_file = File::create(archive_path).ok().unwrap();
+            match FileArco::make(file_data, archive_file) {
+                Err(Error::FileArcoV2(FileArcoV2Error::SourceChanged(ref name))) => {
+                    assert_eq!(name, "changed.txt");
+                },
+                _ => panic!("changed source file was archived"),
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_v2_make_non_utf8_name() {
+        let archive_path = Path::new("tmptest/test_v2_make_non_utf8_name.fac");
+        let dir_path = Path::new("tmptest/test_v2_make_non_utf8_name");
+        let _ = remove_dir_all(dir_path);
+        create_dir_all(dir_path).ok().unwrap();
+        File::create(dir_path.join(OsStr::from_bytes(b"caf\xe9.txt"))).ok().unwrap()
+            .write_all(b"latin-1").ok().unwrap();
+
+        let file_data = ::file_data::get(dir_path).ok().unwrap();
+        {
+            let archive_file = File::create(archive_path).ok().unwrap();
+            FileArco::make(file_data, archive_file).ok().unwrap();
+        }
+
+        let archive = FileArco::new(archive_path).ok().unwrap();
+        let fileref = archive.get("caf\u{fffd}.txt").unwrap();
+        assert!(fileref.is_valid());
+        assert_eq!(fileref.as_slice(), b"latin-1");
+    }
+}
@@ -0,0 +1,53 @@
+// This test runs the `filearco` binary, which is only built with the
+// `binaries` feature.
+#![cfg(feature = "binaries")]
+
+extern crate filearco;
+
+use std::fs::create_dir_all;
+use std::process::Command;
+
+use filearco::v1::FileArco;
+
+#[test]
+fn test_cli_create_exclude() {
+    let archive_path = "tmptest/cli_create_exclude_test.fac";
+    create_dir_all("tmptest").ok().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_filearco"))
+        .args(["create", "testarchives/reqchandocs", "--path", archive_path])
+        .args(["--include", "*.html", "--include", "*.css"])
+        .args(["--exclude", "implementors/*", "--exclude", "src/*", "--exclude", "main.css"])
+        .status().ok().unwrap();
+    assert!(status.success());
+
+    let archive = FileArco::new(archive_path).ok().unwrap();
+
+    let mut names = archive.entries().map(|(name, _)| name).collect::<Vec<_>>();
+    names.sort();
+
+    let expected = vec!["normalize.css",
+                        "reqchan/RequestContract.t.html",
+                        "reqchan/Requester.t.html",
+                        "reqchan/Responder.t.html",
+                        "reqchan/ResponseContract.t.html",
+                        "reqchan/TryReceiveError.t.html",
+                        "reqchan/TryRequestError.t.html",
+                        "reqchan/TryRespondError.t.html",
+                        "reqchan/channel.v.html",
+                        "reqchan/enum.TryReceiveError.html",
+                        "reqchan/enum.TryRequestError.html",
+                        "reqchan/enum.TryRespondError.html",
+                        "reqchan/fn.channel.html",
+                        "reqchan/index.html",
+                        "reqchan/struct.RequestContract.html",
+                        "reqchan/struct.Requester.html",
+                        "reqchan/struct.Responder.html",
+                        "reqchan/struct.ResponseContract.html",
+                        "rustdoc.css"];
+    assert_eq!(names, expected);
+
+    for name in names.iter() {
+        assert!(archive.get(name).unwrap().is_valid());
+    }
+}
@@ -0,0 +1,59 @@
+// This test runs the `filearco` binary, which is only built with the
+// `binaries` feature.
+#![cfg(feature = "binaries")]
+
+extern crate filearco;
+
+use std::fs::{copy, create_dir_all, remove_dir_all, OpenOptions};
+use std::io::prelude::*;
+use std::io::SeekFrom;
+use std::process::Command;
+
+use filearco::v1::FileArco;
+
+#[test]
+fn test_cli_extract_count() {
+    let dest = "tmptest/cli_extract_count_test";
+    let _ = remove_dir_all(dest);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_filearco"))
+        .args(["extract", "testarchives/simple_v1.fac", dest])
+        .output().ok().unwrap();
+    assert!(output.status.success());
+
+    let archive = FileArco::new("testarchives/simple_v1.fac").ok().unwrap();
+    let expected = format!("Extracted {} files to {}\n", archive.file_count(), dest);
+    assert_eq!(String::from_utf8(output.stdout).ok().unwrap(), expected);
+}
+
+#[test]
+fn test_cli_info_corrupted_header() {
+    let archive_path = "tmptest/cli_info_corrupted_header_test.fac";
+    create_dir_all("tmptest").ok().unwrap();
+    copy("testarchives/simple_v1.fac", archive_path).ok().unwrap();
+
+    // Flip a bit of the stored entries checksum, which is covered by the
+    // header checksum.
+    {
+        let mut file = OpenOptions::new().read(true).write(true).open(archive_path).ok().unwrap();
+        let mut byte = [0u8; 1];
+        file.seek(SeekFrom::Start(0x30)).ok().unwrap();
+        file.read_exact(&mut byte).ok().unwrap();
+        byte[0] ^= 1;
+        file.seek(SeekFrom::Start(0x30)).ok().unwrap();
+        file.write_all(&byte).ok().unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_filearco"))
+        .args(["info", archive_path])
+        .output().ok().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    // The header fields are printed even though the checksum does not match.
+    let stdout = String::from_utf8(output.stdout).ok().unwrap();
+    let header = filearco::v1::read_raw_header(archive_path).ok().unwrap();
+    assert!(stdout.contains(&format!("page_size: {}\n", header.page_size())));
+    assert!(stdout.contains(&format!("file_offset: {}\n", header.file_offset())));
+    assert!(stdout.contains(&format!("entries_checksum: {:#018x}\n", header.entries_checksum())));
+    assert!(stdout.ends_with("header_checksum_valid: false\n"));
+}
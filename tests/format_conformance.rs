@@ -0,0 +1,113 @@
+// This test pins the byte layout of FileArco v1 archives described in
+// README.md, so it doubles as a reference for readers written in other
+// languages. Every field is little endian.
+
+extern crate crc;
+extern crate filearco;
+extern crate page_size;
+
+use std::sync::Arc;
+
+use crc::crc64::checksum_iso;
+use filearco::v1::FileArco;
+
+fn u32_le(value: u32) -> Vec<u8> {
+    (0..4).map(|i| (value >> (8 * i)) as u8).collect()
+}
+
+fn u64_le(value: u64) -> Vec<u8> {
+    (0..8).map(|i| (value >> (8 * i)) as u8).collect()
+}
+
+// Strings are stored as their length followed by their UTF-8 bytes.
+fn string(value: &str) -> Vec<u8> {
+    let mut bytes = u64_le(value.len() as u64);
+    bytes.extend_from_slice(value.as_bytes());
+    bytes
+}
+
+fn align_to(length: u64, alignment: u64) -> u64 {
+    length.div_ceil(alignment) * alignment
+}
+
+#[test]
+fn test_format_conformance_v1() {
+    let files = vec![(String::from("b.txt"), b"second\n".to_vec(), None),
+                     (String::from("a/z.txt"), b"first\n".to_vec(), None)];
+
+    let mut archive_bytes = Vec::<u8>::new();
+    FileArco::make_from_typed_entries(files, &mut archive_bytes).ok().unwrap();
+
+    let page_size = page_size::get() as u64;
+
+    // Magic number and version number (major version 1, revision 1, no
+    // flags) are at fixed offsets.
+    assert_eq!(&archive_bytes[0x00..0x08], b"FILEARCO");
+    assert_eq!(&archive_bytes[0x08..0x10], &[1, 0, 0, 0, 1, 0, 0, 0]);
+
+    // Entries table: files are stored and laid out in name order.
+    let mut entries = u64_le(2);
+
+    entries.extend(string("a/z.txt"));
+    entries.extend(u64_le(0));                          // offset
+    entries.extend(u64_le(6));                          // length
+    entries.extend(u64_le(page_size));                  // aligned_length
+    entries.extend(u64_le(checksum_iso(b"first\n")));   // checksum
+    entries.extend(u32_le(0o644));                      // mode
+    entries.extend(u64_le(0));                          // modified
+    entries.push(0);                                    // raw_name: None
+    entries.push(0);                                    // compressed: false
+    entries.extend(u64_le(6));                          // stored_length
+    entries.push(0);                                    // content_type: None
+    entries.push(0);                                    // digest: None
+    entries.push(0);                                    // inline: None
+
+    entries.extend(string("b.txt"));
+    entries.extend(u64_le(page_size));
+    entries.extend(u64_le(7));
+    entries.extend(u64_le(page_size));
+    entries.extend(u64_le(checksum_iso(b"second\n")));
+    entries.extend(u32_le(0o644));
+    entries.extend(u64_le(0));
+    entries.push(0);
+    entries.push(0);
+    entries.extend(u64_le(7));
+    entries.push(0);
+    entries.push(0);
+    entries.push(0);
+
+    // producer: Some(..)
+    entries.push(1);
+    entries.extend(string(&format!("filearco {}", env!("CARGO_PKG_VERSION"))));
+
+    // The header is 56 bytes, followed by its checksum, so the entries table
+    // starts at 0x40 and the file contents at the next page boundary.
+    let file_offset = align_to(0x40 + entries.len() as u64, page_size);
+
+    let mut header = b"FILEARCO".to_vec();
+    header.extend(u64_le(1 | (1 << 32)));               // version_number
+    header.extend(u64_le(file_offset + 2 * page_size)); // file_length
+    header.extend(u64_le(file_offset));                 // file_offset
+    header.extend(u64_le(page_size));                   // page_size
+    header.extend(u64_le(entries.len() as u64));        // entries_length
+    header.extend(u64_le(checksum_iso(&entries)));      // entries_checksum
+    assert_eq!(header.len(), 56);
+
+    let mut expected = header.clone();
+    expected.extend(u64_le(checksum_iso(&header)));
+    expected.extend(&entries);
+    expected.resize(file_offset as usize, 0);
+
+    expected.extend_from_slice(b"first\n");
+    expected.resize((file_offset + page_size) as usize, 0);
+    expected.extend_from_slice(b"second\n");
+    expected.resize((file_offset + 2 * page_size) as usize, 0);
+
+    assert_eq!(archive_bytes.len(), expected.len());
+    assert!(archive_bytes == expected);
+
+    // The expected bytes are a valid archive.
+    let archive = FileArco::from_bytes(Arc::new(expected)).ok().unwrap();
+    assert_eq!(archive.get("a/z.txt").unwrap().as_slice(), b"first\n");
+    assert_eq!(archive.get("b.txt").unwrap().as_slice(), b"second\n");
+}